@@ -19,12 +19,13 @@ async fn read2() {
     let mut files = read_from_file(file).unwrap();
     let book = parse_book(&mut files).await.unwrap();
 
-    let pkg = book.packages().first().unwrap();
-    let sref = pkg.spine.get(12).unwrap();
-    let res = pkg.get_res_by_ref(sref).unwrap();
-    let data = files.get_by_res(&res).await.unwrap();
+    let Some(pkg) = book.primary_package() else {
+        return;
+    };
+    let res = pkg.resource_at_spine_index(12).unwrap();
+    let data = files.get_by_res(res).await.unwrap();
 
-    let s = String::from_utf8(data.clone()).unwrap();
+    let s = eparser::charset::decode_document(data);
     let xhtml = eparser::xhtml::parse_xhtml(&s).unwrap();
 
     let body = xhtml.body_str();
@@ -1,14 +1,26 @@
-use crate::file::Files;
-use crate::oebps::{parse_container, ContainerError};
+use crate::charset::decode_document;
+use crate::file::{Files, MemoryFiles};
+use crate::mapping::{parse_mapping, MappingParseError, RenditionMapping};
+use crate::oebps::{parse_container, rewrite_ops_prefix, ContainerError, RenditionSelection};
+use crate::package::manifest::Resource;
 use crate::package::parser::{PackageError, PackageParseOptions, PackageParser};
-use crate::package::prefix::Prefixes;
 use crate::package::Package;
-use std::fmt::{Debug, Display};
+use futures::{stream, Stream};
+use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
 use thiserror::Error;
+use url::Url;
+
+use crate::utils::escape_xml;
 
 #[derive(Debug)]
-pub struct EpubBook(Vec<Package>);
+pub struct EpubBook {
+    packages: Vec<Package>,
+
+    /// The rendition selection attributes of the rootfile of each package, in the same order.
+    renditions: Vec<RenditionSelection>,
+    mapping: Option<RenditionMapping>,
+}
 
 /// An EPUB book. It is A collection of packages.
 ///
@@ -16,7 +28,184 @@ pub struct EpubBook(Vec<Package>);
 impl EpubBook {
     /// Get all the packages in the book.
     pub fn packages(&self) -> &Vec<Package> {
-        &self.0
+        &self.packages
+    }
+
+    /// Get the primary package of the book.
+    ///
+    /// It is the package of the first rootfile in the container, which is the default
+    /// rendition of a book with multiple renditions. Returns `None` if the book has no package.
+    pub fn primary_package(&self) -> Option<&Package> {
+        self.packages.first()
+    }
+
+    /// Get the rendition selection attributes of the package at `index`.
+    pub fn rendition(&self, index: usize) -> Option<&RenditionSelection> {
+        self.renditions.get(index)
+    }
+
+    /// Get the first package whose rendition matches the predicate,
+    /// like the one labelled `rendition:label="Reflowable"`.
+    pub fn select(&self, predicate: impl Fn(&RenditionSelection) -> bool) -> Option<&Package> {
+        self.renditions
+            .iter()
+            .position(predicate)
+            .and_then(|index| self.packages.get(index))
+    }
+
+    /// Treat the first package whose rendition matches the predicate as the primary one,
+    /// by moving it to the front, see [EpubBook::primary_package].
+    ///
+    /// Returns `false` and keeps the order if no rendition matches.
+    pub fn set_primary_rendition(
+        &mut self,
+        predicate: impl Fn(&RenditionSelection) -> bool,
+    ) -> bool {
+        let Some(index) = self.renditions.iter().position(predicate) else {
+            return false;
+        };
+        if index < self.packages.len() {
+            self.packages[..=index].rotate_right(1);
+            self.renditions[..=index].rotate_right(1);
+        }
+        true
+    }
+
+    /// Stream every manifest resource of every package with its content.
+    ///
+    /// The items are `(package_index, resource, bytes)`, in package order and then in manifest order.
+    /// A resource whose file is missing yields [BookResourceError::MissingFile] and the stream goes on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::book::EpubBook;
+    /// use futures::executor::block_on;
+    /// use futures::StreamExt;
+    ///
+    /// let (book, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+    /// let resources = block_on(book.resources(&mut files).collect::<Vec<_>>());
+    /// assert_eq!(resources.len(), 2);
+    /// ```
+    pub fn resources<'a, F: Files>(
+        &'a self,
+        files: &'a mut F,
+    ) -> impl Stream<Item = Result<(usize, &'a Resource, Vec<u8>), BookResourceError>> + 'a {
+        let resources = self
+            .packages
+            .iter()
+            .enumerate()
+            .flat_map(|(index, package)| package.manifest.iter().map(move |res| (index, res)));
+
+        stream::unfold((files, resources), |(files, mut resources)| async move {
+            let (index, res) = resources.next()?;
+            let item = match files.get(&res.href).await {
+                Some(data) => Ok((index, res, data.clone())),
+                None => Err(BookResourceError::MissingFile {
+                    package_index: index,
+                    href: res.href.clone(),
+                }),
+            };
+            Some((item, (files, resources)))
+        })
+    }
+
+    /// Get the rendition mapping document, if the book has multiple renditions and declares one.
+    pub fn mapping(&self) -> Option<&RenditionMapping> {
+        self.mapping.as_ref()
+    }
+
+    /// Create a minimal valid book with one chapter.
+    ///
+    /// It returns the parsed book and the files backing it, which contain the container,
+    /// a package document, a nav document and a single XHTML content document.
+    pub fn minimal(title: &str, language: &str, identifier: &str) -> (EpubBook, MemoryFiles) {
+        let title = escape_xml(title);
+        let language = escape_xml(language);
+        let identifier = escape_xml(identifier);
+        let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#;
+
+        let package = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" unique-identifier="uid" xmlns="http://www.idpf.org/2007/opf">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="uid">{identifier}</dc:identifier>
+        <dc:title>{title}</dc:title>
+        <dc:language>{language}</dc:language>
+        <meta property="dcterms:modified">{modified}</meta>
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+        <item id="chapter1" href="chapter1.xhtml" media-type="application/xhtml+xml"/>
+    </manifest>
+    <spine>
+        <itemref idref="chapter1"/>
+    </spine>
+</package>"#
+        );
+
+        let nav = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{language}">
+<head>
+    <title>{title}</title>
+</head>
+<body>
+    <nav epub:type="toc">
+        <h1>{title}</h1>
+        <ol>
+            <li><a href="chapter1.xhtml">{title}</a></li>
+        </ol>
+    </nav>
+</body>
+</html>"#
+        );
+
+        let chapter = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="{language}">
+<head>
+    <title>{title}</title>
+</head>
+<body>
+    <h1>{title}</h1>
+</body>
+</html>"#
+        );
+
+        let mut files = MemoryFiles::empty();
+        files
+            .insert("mimetype", EPUB_MIMETYPE.as_bytes().to_vec())
+            .unwrap();
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+        files
+            .insert("OEBPS/content.opf", package.as_bytes().to_vec())
+            .unwrap();
+        files.insert("OEBPS/nav.xhtml", nav.into_bytes()).unwrap();
+        files
+            .insert("OEBPS/chapter1.xhtml", chapter.into_bytes())
+            .unwrap();
+
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        let package = PackageParser::new(options)
+            .parse(&package)
+            .expect("the minimal package document is valid");
+
+        let book = EpubBook {
+            packages: vec![package],
+            renditions: vec![RenditionSelection::default()],
+            mapping: None,
+        };
+        (book, files)
     }
 }
 
@@ -24,13 +213,13 @@ impl Deref for EpubBook {
     type Target = Vec<Package>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.packages
     }
 }
 
 impl DerefMut for EpubBook {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.packages
     }
 }
 
@@ -51,12 +240,59 @@ pub enum ParseBookError {
     #[error("Failed to parse package")]
     ParsePackageError(#[from] PackageError),
 
-    #[error("Failed to parse UTF-8")]
-    Utf8Error(#[from] std::str::Utf8Error),
+    #[error("Failed to parse rendition mapping document")]
+    ParseMappingError(#[from] MappingParseError),
+
+    #[error("Missing mimetype file")]
+    MissingMimetype,
+
+    #[error("Invalid mimetype, expected application/epub+zip but found {0:?}")]
+    InvalidMimetype(String),
+}
+
+#[derive(Debug, Error)]
+pub enum BookResourceError {
+    #[error("The file {href} of a resource of the package {package_index} is missing")]
+    MissingFile { package_index: usize, href: Url },
+}
+
+/// Options of parsing an EPUB book.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BookParseOptions {
+    /// Check that the `mimetype` file contains exactly `application/epub+zip`, off by default.
+    ///
+    /// Enable it only for ZIP archives, directory-based and remote inputs often lack the file.
+    pub check_mimetype: bool,
 }
 
-/// Parse an EPUB book.
+/// The content of the `mimetype` file of an EPUB book.
+pub(crate) const EPUB_MIMETYPE: &str = "application/epub+zip";
+
+/// Parse an EPUB book with the default options.
 pub async fn parse_book<F: Files>(files: &mut F) -> Result<EpubBook, ParseBookError> {
+    parse_book_with_options(files, &BookParseOptions::default()).await
+}
+
+/// Parse an EPUB book with the given options.
+pub async fn parse_book_with_options<F: Files>(
+    files: &mut F,
+    options: &BookParseOptions,
+) -> Result<EpubBook, ParseBookError> {
+    if options.check_mimetype {
+        let url = files
+            .root_url()
+            .join("mimetype")
+            .map_err(ParseBookError::UrlParseError)?;
+        let data = files
+            .get(&url)
+            .await
+            .ok_or(ParseBookError::MissingMimetype)?;
+        if data.as_slice() != EPUB_MIMETYPE.as_bytes() {
+            let mimetype = String::from_utf8_lossy(data).into_owned();
+            return Err(ParseBookError::InvalidMimetype(mimetype));
+        }
+    }
+
     let container = {
         let root_url = files.root_url().clone();
         let url = root_url
@@ -67,32 +303,410 @@ pub async fn parse_book<F: Files>(files: &mut F) -> Result<EpubBook, ParseBookEr
             .await
             .ok_or(ParseBookError::MissingContainer)?;
 
-        let str = std::str::from_utf8(data).map_err(ParseBookError::Utf8Error)?;
+        let str = decode_document(data);
 
-        parse_container(str, &root_url).map_err(ParseBookError::ParseContainerError)?
+        parse_container(&str, &root_url).map_err(ParseBookError::ParseContainerError)?
     };
 
-    let package_parse_options = PackageParseOptions {
-        base_url: container.rootfiles[0].full_path.clone(),
-        reserved_prefixes: Prefixes::reserved(),
-    };
+    let mut full_paths = Vec::new();
+    for rootfile in &container.rootfiles {
+        let mut full_path = rootfile.full_path.clone();
 
-    let mut package_parser = PackageParser::new(package_parse_options);
+        // fall back to `OEBPS/` if the container points at a missing `OPS/` package document
+        if files.get(&full_path).await.is_none() {
+            let root_url = files.root_url();
+            let rewritten = root_url
+                .make_relative(&full_path)
+                .filter(|path| !path.starts_with("../"))
+                .and_then(|path| rewrite_ops_prefix(&path))
+                .and_then(|path| root_url.join(&path).ok());
+            if let Some(rewritten) = rewritten {
+                full_path = rewritten;
+            }
+        }
+
+        full_paths.push(full_path);
+    }
 
     let mut packages = Vec::new();
-    for rootfile in &container.rootfiles {
+    for full_path in &full_paths {
+        // every package resolves its hrefs against its own location
+        let options =
+            PackageParseOptions::new(full_path.clone()).with_root_url(files.root_url().clone());
+        let mut package_parser = PackageParser::new(options);
+
         let data = files
-            .get(&rootfile.full_path)
+            .get(full_path)
             .await
-            .ok_or_else(|| ParseBookError::MissingPackage(rootfile.full_path.to_string()))?;
+            .ok_or_else(|| ParseBookError::MissingPackage(full_path.to_string()))?;
 
-        let str = std::str::from_utf8(data).map_err(ParseBookError::Utf8Error)?;
+        let str = decode_document(data);
 
         let package = package_parser
-            .parse(str)
+            .parse(&str)
             .map_err(ParseBookError::ParsePackageError)?;
 
         packages.push(package);
     }
-    Ok(EpubBook(packages))
+
+    // the mapping document is optional, ignore it if it is missing
+    let mapping = match container.mapping_link() {
+        Some(link) => match files.get(&link.href).await {
+            Some(data) => {
+                let str = decode_document(data);
+                Some(parse_mapping(&str, &link.href).map_err(ParseBookError::ParseMappingError)?)
+            }
+            None => None,
+        },
+        None => None,
+    };
+
+    let renditions = container
+        .rootfiles
+        .iter()
+        .map(|rootfile| rootfile.rendition.clone())
+        .collect();
+
+    Ok(EpubBook {
+        packages,
+        renditions,
+        mapping,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::{
+        parse_book, parse_book_with_options, BookParseOptions, BookResourceError, EpubBook,
+        ParseBookError,
+    };
+    use crate::file::{read_from_dir, Files, MemoryFiles};
+    use crate::oebps::{ContainerError, RenditionSelection};
+    use crate::package::prefix::prefixes::OPF;
+    use crate::package::Version;
+    use futures::StreamExt;
+    use url::Url;
+
+    #[tokio::test]
+    async fn test_minimal() {
+        let (book, mut files) = EpubBook::minimal("A & B", "en", "urn:uuid:1234");
+        let parsed = parse_book(&mut files).await.unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        let package = parsed.first().unwrap();
+        assert_eq!(package.spine.len(), book[0].spine.len());
+        assert_eq!(package.manifest.len(), book[0].manifest.len());
+        assert_eq!(
+            package.nav_resource().unwrap().href,
+            book[0].nav_resource().unwrap().href
+        );
+        assert_eq!(
+            package.metadata.last_modified,
+            book[0].metadata.last_modified
+        );
+        assert!(parsed.mapping().is_none());
+        assert_eq!(
+            parsed.primary_package().unwrap().manifest.len(),
+            book[0].manifest.len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resources() {
+        let (book, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let resources = book.resources(&mut files).collect::<Vec<_>>().await;
+        let ids = resources
+            .iter()
+            .map(|item| item.as_ref().unwrap().1.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["nav", "chapter1"]);
+        let (index, _, data) = resources[1].as_ref().unwrap();
+        assert_eq!(*index, 0);
+        assert!(String::from_utf8_lossy(data).contains("<h1>Title</h1>"));
+
+        // only the nav document is present
+        let mut partial = MemoryFiles::empty();
+        partial
+            .insert("OEBPS/nav.xhtml", b"<html/>".to_vec())
+            .unwrap();
+        let resources = book.resources(&mut partial).collect::<Vec<_>>().await;
+        assert_eq!(resources.len(), 2);
+        assert!(resources[0].is_ok());
+        assert!(matches!(
+            &resources[1],
+            Err(BookResourceError::MissingFile { package_index: 0, href })
+                if href.as_str() == "epub:/OEBPS/chapter1.xhtml"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_parse_mapping() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+    <links>
+        <link href="mapping.xhtml" rel="mapping" media-type="application/xhtml+xml"/>
+    </links>
+</container>"#;
+        let mapping = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>Mapping</title></head>
+<body>
+    <nav epub:type="resource-map">
+        <ul>
+            <li><ul><li><a href="OEBPS/chapter1.xhtml"/></li></ul></li>
+        </ul>
+    </nav>
+</body>
+</html>"#;
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+
+        // the mapping document is declared but missing
+        let book = parse_book(&mut files).await.unwrap();
+        assert!(book.mapping().is_none());
+
+        files
+            .insert("mapping.xhtml", mapping.as_bytes().to_vec())
+            .unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let mapping = book.mapping().unwrap();
+        assert_eq!(mapping.entries.len(), 1);
+        assert_eq!(
+            mapping.entries[0].locations[0].href.as_str(),
+            "epub:/OEBPS/chapter1.xhtml"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_ops_prefixed_rootfile() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OPS/content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#;
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(
+            book[0].nav_resource().unwrap().href.as_str(),
+            "epub:/OEBPS/nav.xhtml"
+        );
+
+        // the path is taken relative to the root, not the host
+        let mut remote = MemoryFiles::new(Url::parse("http://host/book/").unwrap());
+        for url in files.urls().into_iter().cloned().collect::<Vec<_>>() {
+            let data = files.get(&url).await.unwrap().clone();
+            remote
+                .insert(url.path().trim_start_matches('/'), data)
+                .unwrap();
+        }
+        let book = parse_book(&mut remote).await.unwrap();
+        assert_eq!(
+            book[0].nav_resource().unwrap().href.as_str(),
+            "http://host/book/OEBPS/nav.xhtml"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_multiple_rootfiles() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let opf = files
+            .get(&Url::parse("epub:/OEBPS/content.opf").unwrap())
+            .await
+            .unwrap()
+            .clone();
+        files.insert("Reflow/content.opf", opf).unwrap();
+
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+        <rootfile full-path="Reflow/content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#;
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(book.len(), 2);
+        assert_eq!(
+            book[0].nav_resource().unwrap().href.as_str(),
+            "epub:/OEBPS/nav.xhtml"
+        );
+        assert_eq!(
+            book[1].nav_resource().unwrap().href.as_str(),
+            "epub:/Reflow/nav.xhtml"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_rendition() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let opf = files
+            .get(&Url::parse("epub:/OEBPS/content.opf").unwrap())
+            .await
+            .unwrap()
+            .clone();
+        files.insert("Reflow/content.opf", opf).unwrap();
+
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    xmlns:rendition="http://www.idpf.org/2013/rendition">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"
+            rendition:label="Fixed Layout" rendition:layout="pre-paginated"/>
+        <rootfile full-path="Reflow/content.opf" media-type="application/oebps-package+xml"
+            rendition:label="Reflowable" rendition:layout="reflowable"/>
+    </rootfiles>
+</container>"#;
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+
+        let mut book = parse_book(&mut files).await.unwrap();
+        assert_eq!(
+            book.rendition(0).unwrap().label.as_deref(),
+            Some("Fixed Layout")
+        );
+
+        let is_reflowable = |r: &RenditionSelection| r.layout.as_deref() == Some("reflowable");
+        let reflow = book.select(is_reflowable).unwrap();
+        assert_eq!(reflow.base_url.as_str(), "epub:/Reflow/content.opf");
+
+        assert!(book.set_primary_rendition(is_reflowable));
+        assert_eq!(
+            book.primary_package().unwrap().base_url.as_str(),
+            "epub:/Reflow/content.opf"
+        );
+        assert_eq!(
+            book.rendition(0).unwrap().label.as_deref(),
+            Some("Reflowable")
+        );
+
+        assert!(!book.set_primary_rendition(|r| r.label.as_deref() == Some("Audio")));
+        assert_eq!(
+            book.primary_package().unwrap().base_url.as_str(),
+            "epub:/Reflow/content.opf"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_epub2() {
+        let mut files =
+            read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample-epub2")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.packages().first().unwrap();
+
+        assert_eq!(package.version, Version::V2);
+        assert!(package.nav_resource().is_none());
+        assert!(package.metadata.last_modified.is_none());
+
+        let metadata = &package.metadata;
+        assert_eq!(
+            metadata.titles()[0].value,
+            "Alice's Adventures in Wonderland"
+        );
+        assert_eq!(metadata.languages()[0].value, "en");
+        assert_eq!(
+            package.unique_identifier().unwrap().value,
+            "urn:uuid:1b6e3c7a-2f5d-4c8e-9b1a-7d3e5f2a4c60"
+        );
+        let creator = metadata
+            .elems
+            .values()
+            .flatten()
+            .find(|elem| elem.tag_name.reference == "creator")
+            .unwrap();
+        assert_eq!(creator.value, "Lewis Carroll");
+        assert_eq!(creator.attr(&OPF, "role"), Some("aut"));
+        assert_eq!(creator.attr(&OPF, "file-as"), Some("Carroll, Lewis"));
+        assert_eq!(metadata.date_for_event("publication"), Some("1865-11-26"));
+        assert_eq!(metadata.date_for_event("modification"), None);
+        assert_eq!(
+            metadata
+                .publication_date()
+                .unwrap()
+                .date_naive()
+                .to_string(),
+            "1865-11-26"
+        );
+        assert_eq!(metadata.legacy_meta("cover"), Some("cover-image"));
+
+        assert_eq!(package.manifest.len(), 2);
+        assert_eq!(package.ncx_resource().unwrap().id, "ncx");
+        assert_eq!(package.spine.len(), 1);
+        assert_eq!(
+            package
+                .get_res_by_ref(&package.spine[0])
+                .unwrap()
+                .href
+                .path(),
+            "/OEBPS/Text/chapter1.xhtml"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_utf16_package() {
+        let mut files =
+            read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample-utf16")).unwrap();
+        let opf = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        assert_eq!(files.get(&opf).await.unwrap()[..2], [0xFF, 0xFE]);
+
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.packages().first().unwrap();
+        assert_eq!(package.metadata.titles()[0].value, "不思議の国のアリス");
+        assert_eq!(package.metadata.languages()[0].value, "ja");
+        assert_eq!(package.manifest.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_check_mimetype() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        files
+            .insert("mimetype", b"application/zip".to_vec())
+            .unwrap();
+
+        let options = BookParseOptions {
+            check_mimetype: true,
+        };
+        let err = parse_book_with_options(&mut files, &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ParseBookError::InvalidMimetype(m) if m == "application/zip"));
+
+        // the content must match exactly
+        files
+            .insert("mimetype", b"application/epub+zip\n".to_vec())
+            .unwrap();
+        assert!(parse_book_with_options(&mut files, &options).await.is_err());
+
+        assert!(parse_book(&mut files).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_no_rootfiles() {
+        let (_, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles/>
+</container>"#;
+        files
+            .insert("META-INF/container.xml", container.as_bytes().to_vec())
+            .unwrap();
+
+        let err = parse_book(&mut files).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ParseBookError::ParseContainerError(ContainerError::NoRootfiles)
+        ));
+    }
 }
@@ -0,0 +1,120 @@
+use std::fmt::Display;
+
+use minidom::Element;
+
+/// A step of an EPUB canonical fragment identifier.
+///
+/// The index of the `n`-th child element is `2 * n`, the odd indexes refer to the text
+/// between the elements.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CfiStep {
+    /// The index of the step.
+    pub index: usize,
+
+    /// The ID assertion of the step, it is the `id` attribute of the element if it has one.
+    pub id: Option<String>,
+}
+
+impl Display for CfiStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}", self.index)?;
+        if let Some(id) = &self.id {
+            write!(f, "[{}]", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// An EPUB canonical fragment identifier (CFI) of an element.
+///
+/// It is made up of the position of the content document in the spine,
+/// and the path of the element in the content document.
+///
+/// # Examples
+///
+/// `epubcfi(/6/4!/4/2[section1])` refers to the element with the ID `section1`,
+/// which is the first child of the body of the second content document in the spine.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cfi {
+    /// The index of the content document in the [Spine](crate::package::spine::Spine).
+    pub spine_index: usize,
+
+    /// The steps from the root element of the content document to the target element.
+    ///
+    /// It is empty if the CFI refers to the whole content document.
+    pub path: Vec<CfiStep>,
+}
+
+/// The index of the spine element in the package element.
+///
+/// The spine always follows the metadata and manifest elements.
+const SPINE_STEP: usize = 6;
+
+impl Display for Cfi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "epubcfi(/{}/{}", SPINE_STEP, (self.spine_index + 1) * 2)?;
+        if !self.path.is_empty() {
+            write!(f, "!")?;
+            for step in &self.path {
+                write!(f, "{}", step)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// Get the steps from the root element to the element with the given ID.
+///
+/// Returns `None` if no element has the ID.
+pub fn element_path(root: &Element, id: &str) -> Option<Vec<CfiStep>> {
+    for (i, child) in root.children().enumerate() {
+        let step = CfiStep {
+            index: (i + 1) * 2,
+            id: child.attr("id").map(|id| id.to_string()),
+        };
+        if child.attr("id") == Some(id) {
+            return Some(vec![step]);
+        }
+        if let Some(mut path) = element_path(child, id) {
+            path.insert(0, step);
+            return Some(path);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfi::{element_path, Cfi, CfiStep};
+    use crate::xhtml::parse_xhtml;
+
+    #[test]
+    fn test_element_path() {
+        let xhtml = parse_xhtml(
+            r#"<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>Chapter</title></head>
+<body>
+    <section id="chapter">
+        <p>text</p>
+        <h2 id="target">Title</h2>
+    </section>
+</body>
+</html>"#,
+        )
+        .unwrap();
+
+        let path = element_path(xhtml.root(), "target").unwrap();
+        let cfi = Cfi {
+            spine_index: 1,
+            path,
+        };
+        assert_eq!(cfi.to_string(), "epubcfi(/6/4!/4/2[chapter]/4[target])");
+        assert!(element_path(xhtml.root(), "missing").is_none());
+
+        let cfi = Cfi {
+            spine_index: 0,
+            path: vec![CfiStep { index: 4, id: None }],
+        };
+        assert_eq!(cfi.to_string(), "epubcfi(/6/2!/4)");
+    }
+}
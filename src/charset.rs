@@ -0,0 +1,177 @@
+use encoding_rs::{Encoding, UTF_8};
+
+/// The number of bytes searched for the declared character encoding.
+const SNIFF_LEN: usize = 1024;
+
+/// Decode an XML or XHTML document to a string.
+///
+/// The character encoding is determined by, in order:
+/// - the byte order mark, which is removed
+/// - the `encoding` of the XML declaration, like `<?xml version="1.0" encoding="GBK"?>`
+/// - the `charset` of a `meta` element, like `<meta charset="Shift_JIS"/>`
+/// - UTF-8 otherwise
+///
+/// Malformed sequences are replaced with U+FFFD. Since the document is no longer in its
+/// declared encoding, the encoding of the XML declaration is rewritten to `UTF-8`,
+/// so that the result can be given to the XML parser.
+pub fn decode_document(data: &[u8]) -> String {
+    let (encoding, data) = match Encoding::for_bom(data) {
+        Some((encoding, bom_len)) => (encoding, &data[bom_len..]),
+        None => (sniff_encoding(data).unwrap_or(UTF_8), data),
+    };
+
+    let (str, _) = encoding.decode_without_bom_handling(data);
+    rewrite_xml_declaration(&str).unwrap_or_else(|| str.into_owned())
+}
+
+/// Find the encoding declared in the beginning of a document.
+fn sniff_encoding(data: &[u8]) -> Option<&'static Encoding> {
+    // the declarations are ASCII in every ASCII compatible encoding
+    let head = &data[..data.len().min(SNIFF_LEN)];
+    let head = String::from_utf8_lossy(head);
+
+    let label = match head.trim_start().strip_prefix("<?xml") {
+        Some(decl) => attr_value(&decl[..decl.find("?>")?], "encoding"),
+        None => None,
+    }
+    .or_else(|| meta_charset(&head))?;
+
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Get the charset of the first `meta` element declaring one, either as
+/// `<meta charset="..."/>` or `<meta http-equiv="Content-Type" content="text/html; charset=..."/>`.
+fn meta_charset(head: &str) -> Option<&str> {
+    let lower = head.to_ascii_lowercase();
+    let mut rest = 0;
+    while let Some(start) = lower[rest..].find("<meta") {
+        let start = rest + start;
+        let end = start + lower[start..].find('>')?;
+        let tag = &head[start..end];
+        // the content is checked first, since it may contain `charset=` itself
+        if let Some(content) = attr_value(tag, "content") {
+            let lower_content = content.to_ascii_lowercase();
+            if let Some(index) = lower_content.find("charset=") {
+                let charset = &content[index + "charset=".len()..];
+                return Some(charset.split(';').next().unwrap_or_default().trim());
+            }
+        }
+        if let Some(charset) = attr_value(tag, "charset") {
+            return Some(charset);
+        }
+        rest = end;
+    }
+    None
+}
+
+/// Get the value of the attribute `name` in the text of a tag.
+fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut rest = 0;
+    while let Some(index) = lower[rest..].find(name) {
+        let index = rest + index;
+        rest = index + name.len();
+
+        // the name must be a whole word followed by `=`
+        let preceded = lower[..index]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-');
+        let value = tag[rest..].trim_start();
+        let Some(value) = value.strip_prefix('=') else {
+            continue;
+        };
+        if preceded {
+            continue;
+        }
+
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        return if quote == '"' || quote == '\'' {
+            let value = &value[1..];
+            value.find(quote).map(|end| &value[..end])
+        } else {
+            value
+                .split(|c: char| c.is_whitespace() || "/;\"'".contains(c))
+                .next()
+        };
+    }
+    None
+}
+
+/// Replace the encoding of the XML declaration with `UTF-8`, if it declares another one.
+fn rewrite_xml_declaration(str: &str) -> Option<String> {
+    let start = str.find("<?xml")?;
+    if !str[..start].trim().is_empty() {
+        return None;
+    }
+    let end = start + str[start..].find("?>")?;
+    let decl = &str[start..end];
+    let label = attr_value(decl, "encoding")?;
+    if label.eq_ignore_ascii_case("utf-8") {
+        return None;
+    }
+
+    // the label is a slice of the declaration
+    let label_start = start + (label.as_ptr() as usize - decl.as_ptr() as usize);
+    let label_end = label_start + label.len();
+    Some(format!("{}UTF-8{}", &str[..label_start], &str[label_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{GBK, SHIFT_JIS};
+
+    use crate::charset::decode_document;
+    use crate::xhtml::parse_xhtml;
+
+    fn xhtml(decl: &str, meta: &str, text: &str) -> String {
+        format!(
+            r#"{decl}<html xmlns="http://www.w3.org/1999/xhtml"><head>{meta}<title>Title</title></head><body><p>{text}</p></body></html>"#
+        )
+    }
+
+    #[test]
+    fn test_decode_xml_declaration() {
+        let doc = xhtml(r#"<?xml version="1.0" encoding="GBK"?>"#, "", "魔女之旅");
+        let (data, _, _) = GBK.encode(&doc);
+
+        let str = decode_document(&data);
+        assert!(str.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert_eq!(parse_xhtml(&str).unwrap().body_text(), "魔女之旅");
+    }
+
+    #[test]
+    fn test_decode_meta_charset() {
+        let metas = [
+            r#"<meta http-equiv="Content-Type" content="text/html; charset=Shift_JIS"/>"#,
+            r#"<meta charset='shift_jis'/>"#,
+        ];
+        for meta in metas {
+            let doc = xhtml("", meta, "魔女の旅々");
+            let (data, _, _) = SHIFT_JIS.encode(&doc);
+            let str = decode_document(&data);
+            assert_eq!(parse_xhtml(&str).unwrap().body_text(), "魔女の旅々");
+        }
+    }
+
+    #[test]
+    fn test_decode_bom() {
+        let doc = xhtml(
+            r#"<?xml version="1.0" encoding="UTF-16"?>"#,
+            "",
+            "魔女の旅々",
+        );
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(doc.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+
+        let str = decode_document(&data);
+        assert!(str.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert_eq!(parse_xhtml(&str).unwrap().body_text(), "魔女の旅々");
+
+        let doc = xhtml("", "", "魔女の旅々");
+        let mut data = vec![0xEF, 0xBB, 0xBF];
+        data.extend(doc.as_bytes());
+        assert_eq!(decode_document(&data), doc);
+    }
+}
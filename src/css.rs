@@ -0,0 +1,92 @@
+use url::Url;
+
+/// Find the URLs referenced by a style sheet.
+///
+/// It scans the `url(...)` functions and the `@import "..."` rules, and resolves them against `base_url`,
+/// which is the URL of the style sheet. The fragments of the URLs are removed.
+pub fn css_urls(css: &str, base_url: &Url) -> Vec<Url> {
+    let css = strip_comments(css);
+    let mut urls = Vec::new();
+
+    let mut rest = css.as_str();
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + 4..];
+        let Some(end) = rest.find(')') else {
+            break;
+        };
+        push_url(&mut urls, &rest[..end], base_url);
+        rest = &rest[end + 1..];
+    }
+
+    let mut rest = css.as_str();
+    while let Some(start) = rest.find("@import") {
+        rest = rest[start + 7..].trim_start();
+        // `@import url(...)` is handled above
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            if let Some(end) = rest[1..].find(quote) {
+                push_url(&mut urls, &rest[1..end + 1], base_url);
+            }
+        }
+    }
+
+    urls
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+fn push_url(urls: &mut Vec<Url>, raw: &str, base_url: &Url) {
+    let raw = raw.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+    if raw.is_empty() || raw.starts_with("data:") {
+        return;
+    }
+    if let Ok(mut url) = base_url.join(raw) {
+        url.set_fragment(None);
+        urls.push(url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::css::css_urls;
+
+    #[test]
+    fn test_css_urls() {
+        let css = r#"@import "base.css";
+@import url('print.css') print;
+/* url(commented.png) */
+@font-face {
+    font-family: "Sample";
+    src: url("../Fonts/font.otf") format("opentype"), url(../Fonts/font.woff#x);
+}
+body { background: url(data:image/png;base64,AAAA); }"#;
+        let base_url = Url::parse("epub:/OEBPS/Styles/style.css").unwrap();
+        let urls = css_urls(css, &base_url)
+            .into_iter()
+            .map(|url| url.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            vec![
+                "epub:/OEBPS/Styles/print.css",
+                "epub:/OEBPS/Fonts/font.otf",
+                "epub:/OEBPS/Fonts/font.woff",
+                "epub:/OEBPS/Styles/base.css",
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use minidom::Element;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+use url::Url;
+
+/// The font obfuscation algorithms.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ObfuscationAlgorithm {
+    /// The IDPF font obfuscation algorithm.
+    ///
+    /// The first 1040 bytes are XORed with the SHA-1 digest of the unique identifier.
+    ///
+    /// [EPUB 3.3 SPEC font obfuscation](https://www.w3.org/TR/epub-33/#sec-font-obfuscation)
+    Idpf,
+
+    /// The Adobe font obfuscation algorithm.
+    ///
+    /// The first 1024 bytes are XORed with the 16 bytes of the UUID in the unique identifier.
+    Adobe,
+}
+
+impl ObfuscationAlgorithm {
+    /// The algorithm URI of the IDPF font obfuscation.
+    pub const IDPF_URI: &'static str = "http://www.idpf.org/2008/embedding";
+
+    /// The algorithm URI of the Adobe font obfuscation.
+    pub const ADOBE_URI: &'static str = "http://ns.adobe.com/pdf/enc#RC";
+
+    /// Get the obfuscation algorithm from the `Algorithm` attribute of the `EncryptionMethod` element.
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        match uri {
+            Self::IDPF_URI => Some(ObfuscationAlgorithm::Idpf),
+            Self::ADOBE_URI => Some(ObfuscationAlgorithm::Adobe),
+            _ => None,
+        }
+    }
+
+    /// The number of obfuscated bytes at the start of the font.
+    pub fn obfuscated_len(&self) -> usize {
+        match self {
+            ObfuscationAlgorithm::Idpf => 1040,
+            ObfuscationAlgorithm::Adobe => 1024,
+        }
+    }
+
+    /// Derive the obfuscation key from the unique identifier of the publication.
+    ///
+    /// It returns `None` if the identifier is not a UUID for the Adobe algorithm.
+    pub fn key(&self, unique_identifier: &str) -> Option<Vec<u8>> {
+        match self {
+            ObfuscationAlgorithm::Idpf => {
+                let identifier = unique_identifier
+                    .chars()
+                    .filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n'))
+                    .collect::<String>();
+                Some(Sha1::digest(identifier.as_bytes()).to_vec())
+            }
+            ObfuscationAlgorithm::Adobe => {
+                let hex = unique_identifier
+                    .trim()
+                    .trim_start_matches("urn:uuid:")
+                    .replace('-', "");
+                // the digits are checked first, so slicing by bytes can not split a character
+                if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return None;
+                }
+                (0..32)
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Obfuscate or deobfuscate the data of a font in place.
+///
+/// The obfuscation is a XOR, so applying it twice gives the original data back.
+pub fn deobfuscate(data: &mut [u8], algorithm: &ObfuscationAlgorithm, key: &[u8]) {
+    if key.is_empty() {
+        return;
+    }
+    let len = data.len().min(algorithm.obfuscated_len());
+    for (i, byte) in data[..len].iter_mut().enumerate() {
+        *byte ^= key[i % key.len()];
+    }
+}
+
+/// An encrypted resource listed in the encryption.xml file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EncryptedData {
+    /// The `Algorithm` attribute of the `EncryptionMethod` element.
+    pub algorithm: String,
+
+    /// The URL of the encrypted resource.
+    pub uri: Url,
+}
+
+impl EncryptedData {
+    /// Get the font obfuscation algorithm, if the resource is an obfuscated font.
+    pub fn obfuscation(&self) -> Option<ObfuscationAlgorithm> {
+        ObfuscationAlgorithm::from_uri(&self.algorithm)
+    }
+}
+
+/// META-INF/encryption.xml
+#[derive(Debug, PartialEq, Clone)]
+pub struct Encryption {
+    /// All the encrypted resources.
+    pub data: Vec<EncryptedData>,
+}
+
+impl Encryption {
+    /// Get the obfuscated fonts with their algorithms.
+    pub fn obfuscated(&self) -> impl Iterator<Item = (&Url, ObfuscationAlgorithm)> {
+        self.data
+            .iter()
+            .filter_map(|data| data.obfuscation().map(|algorithm| (&data.uri, algorithm)))
+    }
+}
+
+/// Errors that can occur when parsing the encryption.xml file.
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("Encryption method MUST have an Algorithm attribute but it is missing")]
+    MissingAlgorithm,
+
+    #[error("Cipher reference MUST have a URI attribute but it is missing")]
+    MissingUri,
+
+    #[error("Invalid URI, {0}")]
+    InvalidUri(#[from] url::ParseError),
+
+    #[error("Invalid XML, {0}")]
+    ParseError(#[from] minidom::Error),
+}
+
+/// Parse the encryption.xml file.
+///
+/// The structure of the encryption.xml file is as follows:
+///
+/// ```xml
+/// <encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+///             xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+///     <enc:EncryptedData>
+///         <enc:EncryptionMethod Algorithm="http://www.idpf.org/2008/embedding"/>
+///         <enc:CipherData>
+///             <enc:CipherReference URI="OEBPS/Fonts/font.otf"/>
+///         </enc:CipherData>
+///     </enc:EncryptedData>
+/// </encryption>
+/// ```
+///
+/// The URIs are resolved against `root_path`, the root URL of the container.
+pub fn parse_encryption(str: &str, root_path: &Url) -> Result<Encryption, EncryptionError> {
+    let data = str
+        .parse::<Element>()
+        .map_err(EncryptionError::ParseError)?
+        // encryption
+        .children()
+        .filter(|n| n.name() == "EncryptedData")
+        // encryption -> EncryptedData
+        .map(|n| {
+            let algorithm = n
+                .children()
+                .find(|n| n.name() == "EncryptionMethod")
+                .and_then(|n| n.attr("Algorithm"))
+                .ok_or(EncryptionError::MissingAlgorithm)?;
+
+            let uri = n
+                .children()
+                .find(|n| n.name() == "CipherData")
+                .and_then(|n| n.children().find(|n| n.name() == "CipherReference"))
+                .and_then(|n| n.attr("URI"))
+                .ok_or(EncryptionError::MissingUri)?;
+
+            Ok::<_, EncryptionError>(EncryptedData {
+                algorithm: algorithm.to_string(),
+                uri: root_path.join(uri)?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Encryption { data })
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::encryption::{deobfuscate, parse_encryption, ObfuscationAlgorithm};
+
+    const ENCRYPTION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container" xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+    <enc:EncryptedData>
+        <enc:EncryptionMethod Algorithm="http://www.idpf.org/2008/embedding"/>
+        <enc:CipherData>
+            <enc:CipherReference URI="OEBPS/Fonts/font.otf"/>
+        </enc:CipherData>
+    </enc:EncryptedData>
+</encryption>"#;
+
+    #[test]
+    fn test_parse_encryption() {
+        let encryption = parse_encryption(ENCRYPTION, &Url::parse("epub:/").unwrap()).unwrap();
+
+        let obfuscated = encryption.obfuscated().collect::<Vec<_>>();
+        assert_eq!(obfuscated.len(), 1);
+        assert_eq!(obfuscated[0].0.as_str(), "epub:/OEBPS/Fonts/font.otf");
+        assert_eq!(obfuscated[0].1, ObfuscationAlgorithm::Idpf);
+    }
+
+    #[test]
+    fn test_key() {
+        let key = ObfuscationAlgorithm::Idpf
+            .key(" urn:uuid:6a4e9a4b-6a8c-4c4e-9a4b-0f4d6c6e2b11\n")
+            .unwrap();
+        let hex = key.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(hex, "7f9edb98c1a1df36f1ab56b21fe8bf7021dbaa14");
+
+        let key = ObfuscationAlgorithm::Adobe
+            .key("urn:uuid:6a4e9a4b-6a8c-4c4e-9a4b-0f4d6c6e2b11")
+            .unwrap();
+        assert_eq!(key.len(), 16);
+        assert_eq!(key[0], 0x6a);
+        assert!(ObfuscationAlgorithm::Adobe.key("isbn:123").is_none());
+        // 32 bytes, but not 32 hex digits
+        let identifier = format!("aé{}", "0".repeat(29));
+        assert_eq!(identifier.len(), 32);
+        assert!(ObfuscationAlgorithm::Adobe.key(&identifier).is_none());
+        assert!(ObfuscationAlgorithm::Adobe
+            .key("+a4e9a4b6a8c4c4e9a4b0f4d6c6e2b11")
+            .is_none());
+    }
+
+    #[test]
+    fn test_deobfuscate() {
+        let original = (0..2000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let key = ObfuscationAlgorithm::Idpf.key("urn:uuid:1234").unwrap();
+
+        let mut data = original.clone();
+        deobfuscate(&mut data, &ObfuscationAlgorithm::Idpf, &key);
+        assert_ne!(data[..1040], original[..1040]);
+        assert_eq!(data[1040..], original[1040..]);
+
+        deobfuscate(&mut data, &ObfuscationAlgorithm::Idpf, &key);
+        assert_eq!(data, original);
+    }
+}
@@ -0,0 +1,73 @@
+use std::io::{Cursor, Read};
+
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::file::MemoryFiles;
+
+#[derive(Debug, Error)]
+pub enum BytesError {
+    #[error("IO error")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid archive")]
+    Zip(#[from] ZipError),
+
+    #[error("Invalid file name in the archive")]
+    UrlParseError(#[from] url::ParseError),
+}
+
+/// Read files from the bytes of an EPUB zip file, which are already in memory.
+///
+/// It does not touch the file system or the network, so it works on every target,
+/// including `wasm32` where the bytes usually come from a `fetch()` call in JavaScript.
+///
+/// # Examples
+///
+/// With `wasm-bindgen`, a `Uint8Array` passed from JavaScript can be converted to the bytes:
+///
+/// ```ignore
+/// #[wasm_bindgen]
+/// pub async fn open_book(data: js_sys::Uint8Array) -> Result<String, JsError> {
+///     let mut files = eparser::file::read_from_bytes(data.to_vec())?;
+///     let book = eparser::book::parse_book(&mut files).await?;
+///     Ok(format!("{:?}", book))
+/// }
+/// ```
+pub fn read_from_bytes(bytes: Vec<u8>) -> Result<MemoryFiles, BytesError> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+    let mut files = MemoryFiles::empty();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        files.insert(file.name(), content)?;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::parse_book;
+    use crate::file::read_from_bytes;
+    use crate::file::testing::sample_zip;
+
+    #[tokio::test]
+    async fn test_read_from_bytes() {
+        let bytes = sample_zip(&[
+            "mimetype",
+            "META-INF/container.xml",
+            "OEBPS/content.opf",
+            "OEBPS/nav.xhtml",
+            "OEBPS/Text/chapter1.xhtml",
+        ]);
+
+        let mut files = read_from_bytes(bytes).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(book.primary_package().unwrap().spine.len(), 4);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use url::Url;
+
+/// A cache of fetched files keyed by URL, shared between files instances.
+///
+/// Cloning it gives another handle to the same cache, so several [RemoteFiles](crate::file::RemoteFiles)
+/// or [RemoteEpub](crate::file::RemoteEpub) opening the same book download every file once.
+#[derive(Clone, Default)]
+pub struct SharedCache {
+    files: Arc<Mutex<BTreeMap<Url, Vec<u8>>>>,
+}
+
+impl SharedCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<Url, Vec<u8>>> {
+        // the map stays valid even if a holder of the lock panicked
+        self.files.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Get a copy of the content of a cached file.
+    pub fn get(&self, url: &Url) -> Option<Vec<u8>> {
+        self.lock().get(url).cloned()
+    }
+
+    /// Check if a file is cached.
+    pub fn contains(&self, url: &Url) -> bool {
+        self.lock().contains_key(url)
+    }
+
+    /// Cache the content of a file.
+    pub fn insert(&self, url: Url, data: Vec<u8>) {
+        self.lock().insert(url, data);
+    }
+
+    /// The number of cached files.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if no file is cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove every cached file.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+impl Debug for SharedCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedCache")
+            .field("files", &self.lock().keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use url::Url;
+
+use crate::encryption::{
+    deobfuscate, parse_encryption, Encryption, EncryptionError, ObfuscationAlgorithm,
+};
+use crate::file::Files;
+use crate::utils::file_key;
+
+/// Files which deobfuscate the fonts listed in the META-INF/encryption.xml file.
+///
+/// The obfuscated fonts are deobfuscated when they are got for the first time,
+/// and the other files are got from the inner files as they are.
+#[derive(Debug)]
+pub struct DeobfuscatedFiles<F: Files> {
+    inner: F,
+
+    /// The obfuscated fonts with their algorithms and keys.
+    fonts: BTreeMap<Url, (ObfuscationAlgorithm, Vec<u8>)>,
+
+    /// The deobfuscated fonts.
+    cache: BTreeMap<Url, Vec<u8>>,
+}
+
+impl<F: Files> Files for DeobfuscatedFiles<F> {
+    fn root_url(&self) -> &Url {
+        self.inner.root_url()
+    }
+
+    fn urls(&self) -> Vec<&Url> {
+        self.inner.urls()
+    }
+
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        self.inner.size_of(url)
+    }
+
+    async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
+        let DeobfuscatedFiles {
+            inner,
+            fonts,
+            cache,
+        } = self;
+
+        let key = file_key(url);
+        let Some((algorithm, font_key)) = fonts.get(&key) else {
+            return inner.get(url).await;
+        };
+
+        if !cache.contains_key(&key) {
+            let mut data = inner.get(url).await?.clone();
+            deobfuscate(&mut data, algorithm, font_key);
+            cache.insert(key.clone(), data);
+        }
+        cache.get(&key)
+    }
+
+    async fn prefetch(&mut self, urls: &[Url]) {
+        self.inner.prefetch(urls).await
+    }
+}
+
+impl<F: Files> DeobfuscatedFiles<F> {
+    /// Wrap files with the parsed encryption.xml file.
+    ///
+    /// `unique_identifier` is the value of the unique identifier of the publication,
+    /// which the obfuscation keys are derived from.
+    pub fn new(inner: F, encryption: &Encryption, unique_identifier: &str) -> Self {
+        let fonts = encryption
+            .obfuscated()
+            .filter_map(|(url, algorithm)| {
+                let key = algorithm.key(unique_identifier)?;
+                Some((file_key(url), (algorithm, key)))
+            })
+            .collect();
+
+        DeobfuscatedFiles {
+            inner,
+            fonts,
+            cache: BTreeMap::new(),
+        }
+    }
+
+    /// Wrap files, reading the META-INF/encryption.xml file from them.
+    ///
+    /// If there is no encryption.xml file, no file is deobfuscated.
+    pub async fn from_files(
+        mut inner: F,
+        unique_identifier: &str,
+    ) -> Result<Self, EncryptionError> {
+        let root_url = inner.root_url().clone();
+        let url = root_url.join("META-INF/encryption.xml")?;
+        let encryption = match inner.get(&url).await {
+            Some(data) => {
+                let str = String::from_utf8_lossy(data);
+                parse_encryption(&str, &root_url)?
+            }
+            None => Encryption { data: vec![] },
+        };
+        Ok(DeobfuscatedFiles::new(
+            inner,
+            &encryption,
+            unique_identifier,
+        ))
+    }
+
+    /// Get the inner files.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::{parse_book, EpubBook};
+    use crate::encryption::{deobfuscate, ObfuscationAlgorithm};
+    use crate::file::{DeobfuscatedFiles, Files, MemoryFiles};
+
+    const ENCRYPTION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container" xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+    <enc:EncryptedData>
+        <enc:EncryptionMethod Algorithm="http://www.idpf.org/2008/embedding"/>
+        <enc:CipherData>
+            <enc:CipherReference URI="OEBPS/Fonts/font.otf"/>
+        </enc:CipherData>
+    </enc:EncryptedData>
+</encryption>"#;
+
+    #[tokio::test]
+    async fn test_deobfuscated_files() {
+        let identifier = "urn:uuid:6a4e9a4b-6a8c-4c4e-9a4b-0f4d6c6e2b11";
+        let font = (0..2048).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let mut obfuscated = font.clone();
+        let key = ObfuscationAlgorithm::Idpf.key(identifier).unwrap();
+        deobfuscate(&mut obfuscated, &ObfuscationAlgorithm::Idpf, &key);
+
+        let mut files = MemoryFiles::empty();
+        files
+            .insert("META-INF/encryption.xml", ENCRYPTION.as_bytes().to_vec())
+            .unwrap();
+        files.insert("OEBPS/Fonts/font.otf", obfuscated).unwrap();
+        files
+            .insert("OEBPS/style.css", b"body {}".to_vec())
+            .unwrap();
+
+        let mut files = DeobfuscatedFiles::from_files(files, identifier)
+            .await
+            .unwrap();
+        let root_url = files.root_url().clone();
+
+        let url = root_url.join("OEBPS/Fonts/font.otf").unwrap();
+        assert_eq!(files.get(&url).await.unwrap(), &font);
+        // the cached font is returned the second time
+        assert_eq!(files.get(&url).await.unwrap(), &font);
+
+        let url = root_url.join("OEBPS/style.css").unwrap();
+        assert_eq!(files.get(&url).await.unwrap(), b"body {}");
+    }
+
+    #[tokio::test]
+    async fn test_without_encryption() {
+        let (book, files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        let identifier = book[0].unique_identifier().unwrap().value.clone();
+        assert_eq!(identifier, "urn:uuid:1234");
+
+        let mut files = DeobfuscatedFiles::from_files(files, &identifier)
+            .await
+            .unwrap();
+        assert!(parse_book(&mut files).await.is_ok());
+    }
+}
@@ -1,7 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::fs::{read_dir, File};
-use std::io::{Read, Seek};
+use std::io::{ErrorKind, Read, Seek};
 use std::path::{Path, PathBuf};
 
 use thiserror::Error;
@@ -9,7 +9,8 @@ use url::Url;
 pub use zip::result::ZipError;
 pub use zip::ZipArchive;
 
-use crate::file::Files;
+use crate::file::{Files, OnEntry};
+use crate::utils::{file_key, file_url};
 
 #[derive(PartialEq, Clone)]
 pub struct LocalFiles {
@@ -22,13 +23,18 @@ impl Files for LocalFiles {
         &self.root_url
     }
 
+    fn urls(&self) -> Vec<&Url> {
+        self.files.keys().collect()
+    }
+
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        self.files
+            .get(&file_key(url))
+            .map(|content| content.len() as u64)
+    }
+
     async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
-        // remove the fragment from the URL
-        if url.path_segments().is_none() {
-            self.files.get(url)
-        } else {
-            self.files.get(&url.join("").unwrap())
-        }
+        self.files.get(&file_key(url))
     }
 }
 
@@ -42,46 +48,84 @@ impl Debug for LocalFiles {
 }
 
 impl LocalFiles {
+    /// Create empty files with the `epub:/` root URL.
     pub fn empty() -> Self {
+        LocalFiles::new(Url::parse("epub:/").unwrap())
+    }
+
+    /// Create empty files with the given root URL.
+    pub fn new(root_url: Url) -> Self {
         LocalFiles {
             files: BTreeMap::new(),
-            root_url: Url::parse("epub:/").unwrap(),
+            root_url,
         }
     }
+
+    /// Move the files under another root URL, like the `file:` URL of the directory they are read from.
+    ///
+    /// The root URL should end with a slash, so that the paths of the files are joined under it.
+    pub fn with_root_url(self, root_url: Url) -> Self {
+        let files = self
+            .files
+            .into_iter()
+            .map(|(url, content)| (rebase(&self.root_url, &root_url, url), content))
+            .collect();
+        LocalFiles { files, root_url }
+    }
+}
+
+/// Get the URL of a file under another root URL, keeping its path relative to the root.
+fn rebase(from: &Url, to: &Url, url: Url) -> Url {
+    from.make_relative(&url)
+        .and_then(|relative| to.join(&relative).ok())
+        .unwrap_or(url)
 }
 
 #[derive(Debug)]
 pub enum LazyFile<R: Read> {
     NotLoaded(R),
     Loaded(Vec<u8>),
+
+    /// Reading the file failed, like it was deleted after the directory was listed.
+    ///
+    /// It is not read again, getting it returns `None`.
+    Failed(ErrorKind),
 }
 
 impl<R: Read> LazyFile<R> {
     pub fn file(&self) -> Option<&R> {
         match self {
             LazyFile::NotLoaded(file) => Some(file),
-            LazyFile::Loaded(_) => None,
+            _ => None,
         }
     }
 
     pub fn file_mut(&mut self) -> Option<&mut R> {
         match self {
             LazyFile::NotLoaded(file) => Some(file),
-            LazyFile::Loaded(_) => None,
+            _ => None,
         }
     }
 
     pub fn bytes(&self) -> Option<&Vec<u8>> {
         match self {
-            LazyFile::NotLoaded(_) => None,
             LazyFile::Loaded(bytes) => Some(bytes),
+            _ => None,
         }
     }
 
     pub fn bytes_mut(&mut self) -> Option<&mut Vec<u8>> {
         match self {
-            LazyFile::NotLoaded(_) => None,
             LazyFile::Loaded(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The kind of the error if reading the file failed.
+    pub fn error(&self) -> Option<ErrorKind> {
+        match self {
+            LazyFile::Failed(kind) => Some(*kind),
+            _ => None,
         }
     }
 }
@@ -90,6 +134,40 @@ impl<R: Read> LazyFile<R> {
 pub struct LazyLocalFiles<R: Read> {
     root_url: Url,
     files: BTreeMap<Url, LazyFile<R>>,
+    loaded: usize,
+    on_entry: Option<OnEntry>,
+}
+
+impl<R: Read> LazyLocalFiles<R> {
+    /// Move the files under another root URL, see [LocalFiles::with_root_url].
+    pub fn with_root_url(self, root_url: Url) -> Self {
+        let files = self
+            .files
+            .into_iter()
+            .map(|(url, file)| (rebase(&self.root_url, &root_url, url), file))
+            .collect();
+        LazyLocalFiles {
+            root_url,
+            files,
+            ..self
+        }
+    }
+
+    /// Set a callback invoked every time a file is loaded into memory.
+    ///
+    /// The index is the number of files loaded before this one.
+    pub fn with_on_entry(mut self, on_entry: OnEntry) -> Self {
+        self.on_entry = Some(on_entry);
+        self
+    }
+
+    /// The files which failed to be read, with the kinds of the errors.
+    pub fn failed(&self) -> Vec<(&Url, ErrorKind)> {
+        self.files
+            .iter()
+            .filter_map(|(url, file)| Some((url, file.error()?)))
+            .collect()
+    }
 }
 
 impl<R: Read> Files for LazyLocalFiles<R> {
@@ -97,38 +175,149 @@ impl<R: Read> Files for LazyLocalFiles<R> {
         &self.root_url
     }
 
+    fn urls(&self) -> Vec<&Url> {
+        self.files.keys().collect()
+    }
+
     async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
-        let LazyLocalFiles { files, .. } = self;
+        let LazyLocalFiles {
+            files,
+            loaded,
+            on_entry,
+            ..
+        } = self;
+        let total = files.len();
+
+        let url = file_key(url);
+        let lazy_file = files.get_mut(&url);
+
+        let lazy_file = lazy_file?;
+
+        if let LazyFile::NotLoaded(file) = lazy_file {
+            // if not loaded, read the file into memory and store the bytes,
+            // or record the failure so it is not read again
+            let mut content = Vec::new();
+            match file.read_to_end(&mut content) {
+                Ok(_) => {
+                    *lazy_file = LazyFile::Loaded(content);
+                    if let Some(on_entry) = on_entry {
+                        on_entry.call(&url, *loaded, total);
+                    }
+                    *loaded += 1;
+                }
+                Err(err) => *lazy_file = LazyFile::Failed(err.kind()),
+            }
+        }
 
-        // remove the fragment from the URL
-        let lazy_file = if url.path_segments().is_none() {
-            files.get_mut(url)
-        } else {
-            files.get_mut(&url.join("").unwrap())
-        };
+        // return the bytes, none if the file failed to be read
+        lazy_file.bytes()
+    }
+}
 
-        if lazy_file.is_none() {
-            return None;
+/// Files of a ZIP archive, read into memory only when they are got.
+pub struct LazyZipFiles<R: Read + Seek> {
+    root_url: Url,
+    zip: ZipArchive<R>,
+
+    /// URL to the index and the uncompressed size of the entry in the archive
+    entries: BTreeMap<Url, (usize, u64)>,
+
+    /// The files already read
+    files: BTreeMap<Url, Vec<u8>>,
+
+    /// The files which failed to be read, they are not read again
+    failed: BTreeSet<Url>,
+    on_entry: Option<OnEntry>,
+}
+
+impl<R: Read + Seek> LazyZipFiles<R> {
+    /// Set a callback invoked every time a file is read into memory.
+    ///
+    /// The index is the number of files read before this one.
+    pub fn with_on_entry(mut self, on_entry: OnEntry) -> Self {
+        self.on_entry = Some(on_entry);
+        self
+    }
+
+    /// Move the files under another root URL, see [LocalFiles::with_root_url].
+    pub fn with_root_url(self, root_url: Url) -> Self {
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|(url, entry)| (rebase(&self.root_url, &root_url, url), entry))
+            .collect();
+        let files = self
+            .files
+            .into_iter()
+            .map(|(url, content)| (rebase(&self.root_url, &root_url, url), content))
+            .collect();
+        let failed = self
+            .failed
+            .into_iter()
+            .map(|url| rebase(&self.root_url, &root_url, url))
+            .collect();
+        LazyZipFiles {
+            root_url,
+            entries,
+            files,
+            failed,
+            ..self
         }
+    }
 
-        let lazy_file = lazy_file.unwrap();
+    /// The files which failed to be read, like the corrupted entries.
+    pub fn failed(&self) -> Vec<&Url> {
+        self.failed.iter().collect()
+    }
+}
 
-        if let LazyFile::Loaded(bytes) = lazy_file {
-            // if the file is already loaded, return the bytes
-            Some(bytes)
-        } else {
-            // if not loaded, load and store the bytes
-            // get file
-            let file = lazy_file.file_mut().unwrap();
+impl<R: Read + Seek> Debug for LazyZipFiles<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyZipFiles")
+            .field("entries", &self.entries.keys().collect::<Vec<_>>())
+            .field("loaded", &self.files.keys().collect::<Vec<_>>())
+            .field("root_url", &self.root_url)
+            .finish()
+    }
+}
 
-            // read the file into memory
-            let mut content = Vec::new();
-            file.read_to_end(&mut content).unwrap();
-            *lazy_file = LazyFile::Loaded(content);
+impl<R: Read + Seek> Files for LazyZipFiles<R> {
+    fn root_url(&self) -> &Url {
+        &self.root_url
+    }
+
+    fn urls(&self) -> Vec<&Url> {
+        self.entries.keys().collect()
+    }
+
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        self.entries.get(&file_key(url)).map(|(_, size)| *size)
+    }
 
-            // return the bytes
-            Some(lazy_file.bytes()?)
+    async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
+        let url = file_key(url);
+        if !self.files.contains_key(&url) {
+            let (index, _) = *self.entries.get(&url)?;
+            if self.failed.contains(&url) {
+                return None;
+            }
+            let mut content = Vec::new();
+            let res = self
+                .zip
+                .by_index(index)
+                .map_err(std::io::Error::from)
+                .and_then(|mut file| file.read_to_end(&mut content));
+            if res.is_err() {
+                self.failed.insert(url);
+                return None;
+            }
+
+            if let Some(on_entry) = &self.on_entry {
+                on_entry.call(&url, self.files.len(), self.entries.len());
+            }
+            self.files.insert(url.clone(), content);
         }
+        self.files.get(&url)
     }
 }
 
@@ -139,27 +328,99 @@ pub enum LocalFilesError {
 
     #[error("Invalid archive")]
     Zip(#[from] ZipError),
+
+    #[error("Invalid file path")]
+    InvalidPath(#[from] url::ParseError),
 }
 
 /// Read files from a ZIP archive.
 pub fn read_from_zip<R: Read + Seek>(
     zip: &mut ZipArchive<R>,
+) -> Result<LocalFiles, LocalFilesError> {
+    read_from_zip_with_progress(zip, |_, _, _| {})
+}
+
+/// Read files from a ZIP archive, invoking `on_entry` with (url, index, total) per file.
+pub fn read_from_zip_with_progress<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    mut on_entry: impl FnMut(&Url, usize, usize),
 ) -> Result<LocalFiles, LocalFilesError> {
     let mut files = LocalFiles::empty();
-    for i in 0..zip.len() {
+    let total = zip.len();
+    for i in 0..total {
         let mut file = zip.by_index(i)?;
         let mut content = Vec::new();
-        let url_str = format!("epub:/{}", file.name());
-        let url = Url::options().parse(&url_str).unwrap();
-        file.read_to_end(&mut content).unwrap();
+        let url = file_url(&files.root_url, file.name())?;
+        file.read_to_end(&mut content)?;
+        on_entry(&url, i, total);
         files.files.insert(url, content);
     }
     Ok(files)
 }
 
+/// Read files from a ZIP archive lazily.
+///
+/// Only the names and the sizes of the entries are read, the content of a file is read
+/// into memory when it is got for the first time.
+pub fn lazy_read_from_zip<R: Read + Seek>(
+    mut zip: ZipArchive<R>,
+) -> Result<LazyZipFiles<R>, LocalFilesError> {
+    let root_url = Url::parse("epub:/").unwrap();
+    let mut entries = BTreeMap::new();
+    for index in 0..zip.len() {
+        // the raw entry is not decompressed
+        let entry = zip.by_index_raw(index)?;
+        // directories have no content
+        if entry.is_dir() {
+            continue;
+        }
+        let url = file_url(&root_url, entry.name())?;
+        let size = entry.size();
+        drop(entry);
+        entries.insert(url, (index, size));
+    }
+    Ok(LazyZipFiles {
+        root_url,
+        zip,
+        entries,
+        files: BTreeMap::new(),
+        failed: BTreeSet::new(),
+        on_entry: None,
+    })
+}
+
+/// Read files from a Reader, which targets a ZIP archive, lazily.
+pub fn lazy_read_from_reader<R: Read + Seek>(
+    reader: R,
+) -> Result<LazyZipFiles<R>, LocalFilesError> {
+    lazy_read_from_zip(ZipArchive::new(reader)?)
+}
+
 /// Read files from a Reader, which targets a ZIP archive.
 pub fn read_from_reader<R: Read + Seek>(reader: R) -> Result<LocalFiles, LocalFilesError> {
-    Ok(read_from_zip(&mut ZipArchive::new(reader)?)?)
+    read_from_zip(&mut ZipArchive::new(reader)?)
+}
+
+/// Read files from an asynchronous reader, which targets a ZIP archive, like a socket.
+///
+/// The whole archive is buffered in memory before it is read, since reading a ZIP archive needs seeking.
+#[cfg(feature = "tokio")]
+pub async fn read_from_async_reader<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+) -> Result<LocalFiles, LocalFilesError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).await?;
+    read_from_reader(std::io::Cursor::new(buf))
+}
+
+/// Read files from a Reader, which targets a ZIP archive, invoking `on_entry` per file.
+pub fn read_from_reader_with_progress<R: Read + Seek>(
+    reader: R,
+    on_entry: impl FnMut(&Url, usize, usize),
+) -> Result<LocalFiles, LocalFilesError> {
+    read_from_zip_with_progress(&mut ZipArchive::new(reader)?, on_entry)
 }
 
 /// Recursively read files from a directory.
@@ -188,13 +449,23 @@ fn recurse_files(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
 ///
 /// It will recursively read all files from the directory.
 pub fn read_from_dir(path: impl AsRef<Path>) -> Result<LocalFiles, LocalFilesError> {
+    read_from_dir_with_progress(path, |_, _, _| {})
+}
+
+/// Read files from a directory, invoking `on_entry` with (url, index, total) per file.
+pub fn read_from_dir_with_progress(
+    path: impl AsRef<Path>,
+    mut on_entry: impl FnMut(&Url, usize, usize),
+) -> Result<LocalFiles, LocalFilesError> {
     let mut files = LocalFiles::empty();
     let paths = recurse_files(&path)?;
-    for file_path in paths {
+    let total = paths.len();
+    for (i, file_path) in paths.into_iter().enumerate() {
         let rel_path = file_path.strip_prefix(&path).unwrap();
         let rel_path_str = rel_path.to_str().unwrap().replace("\\", "/");
-        let url = Url::parse(&format!("epub:/{}", rel_path_str)).unwrap();
+        let url = file_url(&files.root_url, &rel_path_str)?;
         let content = std::fs::read(&file_path)?;
+        on_entry(&url, i, total);
         files.files.insert(url, content);
     }
     Ok(files)
@@ -208,12 +479,14 @@ pub fn lazy_read_from_dir(path: impl AsRef<Path>) -> Result<LazyLocalFiles<File>
     let mut files = LazyLocalFiles {
         root_url: Url::parse("epub:/").unwrap(),
         files: BTreeMap::new(),
+        loaded: 0,
+        on_entry: None,
     };
     let paths = recurse_files(&path)?;
     for file_path in paths {
         let rel_path = file_path.strip_prefix(&path).unwrap();
         let rel_path_str = rel_path.to_str().unwrap().replace("\\", "/");
-        let url = Url::parse(&format!("epub:/{}", rel_path_str)).unwrap();
+        let url = file_url(&files.root_url, &rel_path_str)?;
         files
             .files
             .insert(url, LazyFile::NotLoaded(File::open(&file_path)?));
@@ -223,5 +496,186 @@ pub fn lazy_read_from_dir(path: impl AsRef<Path>) -> Result<LazyLocalFiles<File>
 
 /// Read files from a ZIP file.
 pub fn read_from_file(file: File) -> Result<LocalFiles, LocalFilesError> {
-    Ok(read_from_zip(&mut ZipArchive::new(file)?)?)
+    read_from_zip(&mut ZipArchive::new(file)?)
+}
+
+/// Read files from a ZIP file lazily, see [lazy_read_from_zip].
+pub fn lazy_read_from_file(file: File) -> Result<LazyZipFiles<File>, LocalFilesError> {
+    lazy_read_from_zip(ZipArchive::new(file)?)
+}
+
+/// Read files from a ZIP file, invoking `on_entry` with (url, index, total) per file.
+pub fn read_from_file_with_progress(
+    file: File,
+    on_entry: impl FnMut(&Url, usize, usize),
+) -> Result<LocalFiles, LocalFilesError> {
+    read_from_zip_with_progress(&mut ZipArchive::new(file)?, on_entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::{Cursor, ErrorKind, Read};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use url::Url;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use crate::book::parse_book;
+    use crate::file::testing::{sample_entries, SAMPLE_PATHS};
+    use crate::file::{
+        lazy_read_from_dir, lazy_read_from_zip, read_from_dir, read_from_dir_with_progress,
+        read_from_zip, write_zip, Files, LazyFile, LazyLocalFiles, LocalFilesError, OnEntry,
+        ZipArchive,
+    };
+
+    const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample");
+
+    #[test]
+    fn test_read_from_dir_with_progress() {
+        let mut count = 0;
+        let mut totals = Vec::new();
+        let files = read_from_dir_with_progress(SAMPLE, |_, index, total| {
+            assert_eq!(index, count);
+            count += 1;
+            totals.push(total);
+        })
+        .unwrap();
+
+        assert_eq!(count, files.files.len());
+        assert_eq!(files.urls().len(), count);
+        let container = Url::parse("epub:/META-INF/container.xml").unwrap();
+        assert!(files.urls().contains(&&container));
+        assert!(totals.iter().all(|&total| total == count));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_read_from_dir_with_progress() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let mut files = lazy_read_from_dir(SAMPLE)
+            .unwrap()
+            .with_on_entry(OnEntry::new(move |_, _, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }));
+
+        let url = Url::parse("epub:/META-INF/container.xml").unwrap();
+        files.get(&url).await.unwrap();
+        files.get(&url).await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lazy_read_failure() {
+        /// A reader failing every time, counting the reads.
+        struct FailingReader(Arc<AtomicUsize>);
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Err(std::io::Error::from(ErrorKind::NotFound))
+            }
+        }
+
+        let reads = Arc::new(AtomicUsize::new(0));
+        let url = Url::parse("epub:/deleted.xhtml").unwrap();
+        let mut files = LazyLocalFiles {
+            root_url: Url::parse("epub:/").unwrap(),
+            files: BTreeMap::from([(
+                url.clone(),
+                LazyFile::NotLoaded(FailingReader(reads.clone())),
+            )]),
+            loaded: 0,
+            on_entry: None,
+        };
+
+        assert!(files.get(&url).await.is_none());
+        assert!(files.get(&url).await.is_none());
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert_eq!(files.failed(), vec![(&url, ErrorKind::NotFound)]);
+    }
+
+    #[tokio::test]
+    async fn test_with_root_url() {
+        let root_url = Url::from_directory_path(SAMPLE).unwrap();
+        let mut files = read_from_dir(SAMPLE)
+            .unwrap()
+            .with_root_url(root_url.clone());
+        assert_eq!(files.root_url(), &root_url);
+
+        let url = root_url.join("META-INF/container.xml").unwrap();
+        assert!(files.get(&url).await.is_some());
+
+        let book = parse_book(&mut files).await.unwrap();
+        let nav = book.primary_package().unwrap().nav_resource().unwrap();
+        assert_eq!(nav.href, root_url.join("OEBPS/nav.xhtml").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_lazy_read_from_zip() {
+        let mut entries = sample_entries(&SAMPLE_PATHS);
+        entries.push(("OEBPS/Text/".to_string(), Vec::new()));
+        let zip = write_zip(Cursor::new(Vec::new()), entries).unwrap();
+        let zip = ZipArchive::new(zip).unwrap();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let mut files =
+            lazy_read_from_zip(zip)
+                .unwrap()
+                .with_on_entry(OnEntry::new(move |_, index, total| {
+                    assert_eq!(total, 3);
+                    assert_eq!(index, counter.fetch_add(1, Ordering::SeqCst));
+                }));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(files.urls().len(), 3);
+
+        let url = Url::parse("epub:/META-INF/container.xml").unwrap();
+        let expected = std::fs::read(format!("{}/META-INF/container.xml", SAMPLE)).unwrap();
+        // the size is known before the file is read
+        assert_eq!(files.size_of(&url), Some(expected.len() as u64));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(files.get(&url).await, Some(&expected));
+        assert_eq!(files.get(&url).await, Some(&expected));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let dir = Url::parse("epub:/OEBPS/Text/").unwrap();
+        assert!(files.get(&dir).await.is_none());
+        assert!(files.size_of(&dir).is_none());
+    }
+
+    #[test]
+    fn test_invalid_entry_name() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("//host:badport/x", SimpleFileOptions::default())
+            .unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let mut zip = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+        let err = read_from_zip(&mut zip).unwrap_err();
+        assert!(matches!(err, LocalFilesError::InvalidPath(_)));
+
+        let zip = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let err = lazy_read_from_zip(zip).unwrap_err();
+        assert!(matches!(err, LocalFilesError::InvalidPath(_)));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_read_from_async_reader() {
+        let bytes = crate::file::testing::sample_zip(&SAMPLE_PATHS);
+
+        // a duplex stream delivers the archive in chunks, like a socket
+        let (mut client, server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            client.write_all(&bytes).await.unwrap();
+        });
+
+        let mut files = crate::file::read_from_async_reader(server).await.unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(book.primary_package().unwrap().spine.len(), 4);
+    }
 }
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use url::Url;
+
+use crate::file::Files;
+use crate::utils::{file_key, file_url};
+
+/// Files stored in memory.
+///
+/// Unlike the local files, it is available on every target, and files can be added after it is created.
+#[derive(PartialEq, Clone)]
+pub struct MemoryFiles {
+    files: BTreeMap<Url, Vec<u8>>,
+    root_url: Url,
+}
+
+impl Files for MemoryFiles {
+    fn root_url(&self) -> &Url {
+        &self.root_url
+    }
+
+    fn urls(&self) -> Vec<&Url> {
+        self.files.keys().collect()
+    }
+
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        self.files
+            .get(&file_key(url))
+            .map(|content| content.len() as u64)
+    }
+
+    async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
+        self.files.get(&file_key(url))
+    }
+}
+
+impl Debug for MemoryFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryFiles")
+            .field("files", &self.files.keys().collect::<Vec<_>>())
+            .field("root_url", &self.root_url)
+            .finish()
+    }
+}
+
+impl MemoryFiles {
+    /// Create empty files with the `epub:/` root URL.
+    pub fn empty() -> Self {
+        MemoryFiles::new(Url::parse("epub:/").unwrap())
+    }
+
+    /// Create empty files with a root URL.
+    pub fn new(root_url: Url) -> Self {
+        MemoryFiles {
+            files: BTreeMap::new(),
+            root_url,
+        }
+    }
+
+    /// Insert a file, the path is resolved against the root URL.
+    ///
+    /// The path is a file path like the name of a ZIP entry, not a URL,
+    /// so `#` and `?` are part of the file name.
+    pub fn insert(&mut self, path: &str, content: Vec<u8>) -> Result<(), url::ParseError> {
+        let url = file_url(&self.root_url, path)?;
+        self.files.insert(url, content);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file::{Files, MemoryFiles};
+
+    #[tokio::test]
+    async fn test_percent_encoded_paths() {
+        let mut files = MemoryFiles::empty();
+        files.insert("images/cover 1.jpg", vec![1]).unwrap();
+        files.insert("images/a#b.png", vec![2]).unwrap();
+        files.insert("images/插图.png", vec![3]).unwrap();
+
+        let root_url = files.root_url().clone();
+        let cases = [
+            ("images/cover 1.jpg", 1),
+            ("images/cover%201.jpg", 1),
+            ("images/cover%201.jpg#top", 1),
+            ("images/a%23b.png", 2),
+            ("images/插图.png", 3),
+            ("images/%e6%8f%92%e5%9b%be.png", 3),
+        ];
+        for (href, expected) in cases {
+            let url = root_url.join(href).unwrap();
+            assert_eq!(files.get(&url).await, Some(&vec![expected]), "{}", href);
+        }
+    }
+}
@@ -1,15 +1,38 @@
-use std::fmt::Debug;
-use std::io::Read;
-use std::ops::{Deref, DerefMut};
-
 use url::Url;
 
+#[allow(async_fn_in_trait)]
 pub trait Files {
     /// Get the root URL of the files.
     fn root_url(&self) -> &Url;
 
+    /// Get the URLs of the files known to exist.
+    ///
+    /// Local files list every file of the container, while remote files only list
+    /// the files already fetched, since the server can not be enumerated.
+    ///
+    /// It returns an empty list by default, for files that are not enumerable.
+    fn urls(&self) -> Vec<&Url> {
+        Vec::new()
+    }
+
+    /// Get the size in bytes of a file by its URL, without reading it.
+    ///
+    /// It returns `None` by default. Files read from a ZIP archive know the uncompressed
+    /// size of every entry, and files in memory know their length.
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        let _ = url;
+        None
+    }
+
     /// Get the content of a file by its URL.
     async fn get(&mut self, url: &Url) -> Option<&Vec<u8>>;
+
+    /// Load files that are about to be got, so that the following `get` calls are fast.
+    ///
+    /// It does nothing by default. Remote files fetch them concurrently.
+    async fn prefetch(&mut self, urls: &[Url]) {
+        let _ = urls;
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -22,5 +45,33 @@ mod remote_epub;
 #[cfg(not(target_arch = "wasm32"))]
 pub use remote_epub::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod zip_writer;
+#[cfg(not(target_arch = "wasm32"))]
+pub use zip_writer::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod source;
+#[cfg(not(target_arch = "wasm32"))]
+pub use source::*;
+
 mod remote;
 pub use remote::*;
+
+mod cache;
+pub use cache::*;
+
+mod progress;
+pub use progress::*;
+
+mod memory;
+pub use memory::*;
+
+mod bytes;
+pub use bytes::*;
+
+mod deobfuscate;
+pub use deobfuscate::*;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod testing;
@@ -0,0 +1,32 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use url::Url;
+
+type Callback = dyn FnMut(&Url, usize, usize) + Send;
+
+/// A callback invoked per file while the files of a book are read, used for progress reporting.
+///
+/// It is called with the URL of the file, the index of the file and the total number of files.
+/// The total is `0` if it is unknown, like when files are fetched from a remote server one by one.
+#[derive(Clone)]
+pub struct OnEntry(Arc<Mutex<Callback>>);
+
+impl OnEntry {
+    pub fn new(on_entry: impl FnMut(&Url, usize, usize) + Send + 'static) -> Self {
+        OnEntry(Arc::new(Mutex::new(on_entry)))
+    }
+
+    /// Invoke the callback.
+    pub fn call(&self, url: &Url, index: usize, total: usize) {
+        if let Ok(mut on_entry) = self.0.lock() {
+            on_entry(url, index, total);
+        }
+    }
+}
+
+impl Debug for OnEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnEntry").finish_non_exhaustive()
+    }
+}
@@ -1,5 +1,7 @@
-use crate::file::Files;
-use std::collections::BTreeMap;
+use crate::file::{Files, OnEntry, SharedCache};
+use crate::utils::file_key;
+use futures::{stream, StreamExt};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use thiserror::Error;
 use url::Url;
@@ -12,6 +14,11 @@ pub struct RemoteFiles {
     url: Url,
     client: reqwest::Client,
     cache: BTreeMap<Url, Vec<u8>>,
+    shared_cache: Option<SharedCache>,
+    on_entry: Option<OnEntry>,
+
+    /// The URLs known to exist on the server, like the hrefs of the manifest.
+    manifest_urls: BTreeSet<Url>,
 }
 
 impl Files for RemoteFiles {
@@ -19,19 +26,72 @@ impl Files for RemoteFiles {
         &self.url
     }
 
+    /// The URLs of the fetched files, the ones the server responded to with a success status.
+    ///
+    /// The server can not be listed, so it is empty before any file is fetched.
+    fn urls(&self) -> Vec<&Url> {
+        self.cache.keys().collect()
+    }
+
     async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
-        if !self.cache.contains_key(url) {
+        if !self.cache.contains_key(url) && !self.load_shared(url) {
             // fetch the file from the remote server
-            let response = self.client.get(url.clone()).send().await;
-            if let Ok(response) = response {
-                let data = response.bytes().await;
-                if let Ok(data) = data {
-                    self.cache.insert(url.clone(), data.to_vec());
-                }
+            if let Some(data) = fetch(&self.client, url).await {
+                self.insert(url.clone(), data);
             }
         }
         self.cache.get(url)
     }
+
+    async fn prefetch(&mut self, urls: &[Url]) {
+        let mut missing = BTreeSet::new();
+        for url in urls {
+            if !self.cache.contains_key(url) && !self.load_shared(url) {
+                missing.insert(url.clone());
+            }
+        }
+        let urls = missing;
+
+        let client = &self.client;
+        let fetched = stream::iter(urls)
+            .map(|url| async move {
+                let data = fetch(client, &url).await;
+                (url, data)
+            })
+            .buffer_unordered(PREFETCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        for (url, data) in fetched {
+            if let Some(data) = data {
+                self.insert(url, data);
+            }
+        }
+    }
+}
+
+/// The maximum number of concurrent requests when prefetching files.
+const PREFETCH_CONCURRENCY: usize = 8;
+
+/// Fetch a file from the remote server.
+async fn fetch(client: &reqwest::Client, url: &Url) -> Option<Vec<u8>> {
+    let response = client.get(url.clone()).send().await.ok()?;
+    // the body of an error response is not the file
+    let response = response.error_for_status().ok()?;
+    let data = response.bytes().await.ok()?;
+    Some(data.to_vec())
+}
+
+/// Build the default HTTP client.
+///
+/// Responses with a `gzip` or `deflate` content encoding are decoded transparently,
+/// so compressing servers and proxies can serve the files of a book.
+pub(crate) fn default_client() -> reqwest::Client {
+    let builder = reqwest::Client::builder();
+    // the browser decodes the responses on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder.gzip(true).deflate(true);
+    builder.build().unwrap()
 }
 
 impl RemoteFiles {
@@ -39,7 +99,10 @@ impl RemoteFiles {
         RemoteFiles {
             url,
             cache: BTreeMap::new(),
-            client: reqwest::Client::builder().build().unwrap(),
+            shared_cache: None,
+            client: default_client(),
+            on_entry: None,
+            manifest_urls: BTreeSet::new(),
         }
     }
 
@@ -47,9 +110,80 @@ impl RemoteFiles {
         RemoteFiles {
             url,
             cache: BTreeMap::new(),
+            shared_cache: None,
             client,
+            on_entry: None,
+            manifest_urls: BTreeSet::new(),
         }
     }
+
+    fn insert(&mut self, url: Url, data: Vec<u8>) {
+        if let Some(on_entry) = &self.on_entry {
+            // the total number of files is unknown
+            on_entry.call(&url, self.cache.len(), 0);
+        }
+        if let Some(shared_cache) = &self.shared_cache {
+            shared_cache.insert(url.clone(), data.clone());
+        }
+        self.cache.insert(url, data);
+    }
+
+    /// Copy a file fetched by another instance from the shared cache, returns `false` if it is not there.
+    fn load_shared(&mut self, url: &Url) -> bool {
+        let Some(data) = self.shared_cache.as_ref().and_then(|cache| cache.get(url)) else {
+            return false;
+        };
+        self.cache.insert(url.clone(), data);
+        true
+    }
+
+    /// Share the fetched files with the other files using the same cache.
+    ///
+    /// The files found in the shared cache are not fetched again, and the `on_entry` callback is not invoked for them.
+    pub fn with_shared_cache(mut self, shared_cache: SharedCache) -> Self {
+        self.shared_cache = Some(shared_cache);
+        self
+    }
+
+    /// Add the URLs of files expected on the server, usually the resolved hrefs
+    /// of the manifest of the parsed package, so [RemoteFiles::manifest_urls] lists them.
+    ///
+    /// It lets a book served as an unpacked directory be prefetched as a whole:
+    ///
+    /// ```no_run
+    /// # use eparser::book::parse_book;
+    /// # use eparser::file::{read_from_url_str, Files};
+    /// # async fn example() {
+    /// let mut files = read_from_url_str("https://example.com/book/").await.unwrap();
+    /// let book = parse_book(&mut files).await.unwrap();
+    /// let package = book.primary_package().unwrap();
+    /// files.add_manifest_urls(package.manifest.iter().map(|res| res.href.clone()));
+    ///
+    /// let urls = files.manifest_urls().into_iter().cloned().collect::<Vec<_>>();
+    /// files.prefetch(&urls).await;
+    /// # }
+    /// ```
+    pub fn add_manifest_urls(&mut self, urls: impl IntoIterator<Item = Url>) {
+        self.manifest_urls
+            .extend(urls.into_iter().map(|url| file_key(&url)));
+    }
+
+    /// Get the URLs added by [RemoteFiles::add_manifest_urls], which are not fetched yet
+    /// and may not exist on the server.
+    pub fn manifest_urls(&self) -> Vec<&Url> {
+        self.manifest_urls.iter().collect()
+    }
+
+    /// Check if a file is expected to exist, it is fetched or added by [RemoteFiles::add_manifest_urls].
+    pub fn contains(&self, url: &Url) -> bool {
+        self.cache.contains_key(url) || self.manifest_urls.contains(&file_key(url))
+    }
+
+    /// Set a callback invoked every time a file is fetched from the remote server.
+    pub fn with_on_entry(mut self, on_entry: OnEntry) -> Self {
+        self.on_entry = Some(on_entry);
+        self
+    }
 }
 
 #[derive(Debug, Error)]
@@ -68,3 +202,139 @@ pub async fn read_from_url_str(url: &str) -> Result<RemoteFiles, RemoteError> {
 pub async fn read_from_url(url: Url) -> RemoteFiles {
     RemoteFiles::new(url)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::atomic::Ordering;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use url::Url;
+
+    use crate::book::parse_book;
+    use crate::file::testing::{serve, serve_with_headers, serve_with_not_found};
+    use crate::file::{read_from_url_str, Files, RemoteFiles, SharedCache};
+    use crate::oebps::parse_container;
+
+    const CONTAINER: &str = include_str!("../../res/sample/META-INF/container.xml");
+    const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample");
+
+    #[test]
+    fn test_manifest_urls() {
+        let root_url = Url::parse("http://127.0.0.1/book/").unwrap();
+        let mut files = RemoteFiles::new(root_url.clone());
+        assert!(files.urls().is_empty());
+
+        let container = root_url.join("META-INF/container.xml").unwrap();
+        let chapter = root_url.join("OEBPS/Text/chapter1.xhtml").unwrap();
+        files.insert(container.clone(), CONTAINER.as_bytes().to_vec());
+        files.add_manifest_urls([
+            container.clone(),
+            chapter.clone(),
+            chapter.join("#section1").unwrap(),
+        ]);
+
+        assert_eq!(files.urls(), vec![&container]);
+        assert_eq!(files.manifest_urls(), vec![&container, &chapter]);
+        assert!(files.contains(&chapter.join("#section2").unwrap()));
+        assert!(!files.contains(&root_url.join("OEBPS/missing.xhtml").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_gzip_encoded_response() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(CONTAINER.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+        let (addr, _) =
+            serve_with_headers(&[("Content-Encoding", "gzip")], move |_| body.clone()).await;
+
+        let mut files = read_from_url_str(&format!("http://{}/book/", addr))
+            .await
+            .unwrap();
+        let root_url = files.root_url().clone();
+        let url = root_url.join("META-INF/container.xml").unwrap();
+
+        let data = files.get(&url).await.unwrap();
+        let container = parse_container(std::str::from_utf8(data).unwrap(), &root_url).unwrap();
+        assert_eq!(container.rootfiles.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        // serve the sample as an unpacked directory, without the second chapter
+        let (addr, _) = serve_with_not_found(|path| {
+            let path = path.strip_prefix("/book/")?;
+            if path.ends_with("chapter2.xhtml") {
+                return None;
+            }
+            std::fs::read(format!("{}/{}", SAMPLE, path)).ok()
+        })
+        .await;
+
+        let mut files = read_from_url_str(&format!("http://{}/book/", addr))
+            .await
+            .unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.primary_package().unwrap();
+
+        let missing = package.missing_resources(&mut files).await;
+        assert_eq!(
+            missing
+                .iter()
+                .map(|res| res.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["chapter2"]
+        );
+        // the error page is not cached as the file
+        assert!(!files.urls().contains(&&missing[0].href));
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        // echo the requested path
+        let (addr, requests) = serve(|path| path.as_bytes().to_vec()).await;
+
+        let mut files = read_from_url_str(&format!("http://{}/book/", addr))
+            .await
+            .unwrap();
+        let urls = ["a.xhtml", "b.xhtml", "c.xhtml"]
+            .iter()
+            .map(|path| files.root_url().join(path).unwrap())
+            .collect::<Vec<_>>();
+
+        files.prefetch(&urls).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert!(urls.iter().all(|url| files.urls().contains(&url)));
+
+        for url in &urls {
+            let data = files.get(url).await.unwrap();
+            assert_eq!(std::str::from_utf8(data).unwrap(), url.path());
+        }
+        // the files are got from the cache
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_shared_cache() {
+        let (addr, requests) = serve(|_| CONTAINER.as_bytes().to_vec()).await;
+
+        let cache = SharedCache::new();
+        let root_url = Url::parse(&format!("http://{}/book/", addr)).unwrap();
+        let url = root_url.join("META-INF/container.xml").unwrap();
+
+        let mut first = RemoteFiles::new(root_url.clone()).with_shared_cache(cache.clone());
+        assert!(first.get(&url).await.is_some());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(cache.contains(&url));
+
+        // another reader of the same book does not download it again
+        let mut second = RemoteFiles::new(root_url).with_shared_cache(cache.clone());
+        second.prefetch(std::slice::from_ref(&url)).await;
+        assert_eq!(
+            second.get(&url).await.unwrap().as_slice(),
+            CONTAINER.as_bytes()
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
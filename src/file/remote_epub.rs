@@ -1,4 +1,6 @@
-use crate::file::Files;
+use crate::file::remote::default_client;
+use crate::file::{Files, OnEntry, SharedCache};
+use crate::utils::{file_key, file_url};
 use std::collections::BTreeMap;
 use std::error::Error;
 use std::io::{Cursor, Read};
@@ -14,20 +16,52 @@ pub struct RemoteEpub {
     has_fetched_zip: bool,
     fetch_zip_error: bool,
     files: BTreeMap<Url, Vec<u8>>,
+    shared_cache: Option<SharedCache>,
+    on_entry: Option<OnEntry>,
 }
 
 impl RemoteEpub {
+    /// Set a callback invoked per file while the fetched zip file is extracted.
+    pub fn with_on_entry(mut self, on_entry: OnEntry) -> Self {
+        self.on_entry = Some(on_entry);
+        self
+    }
+
+    /// Share the fetched archive with the other files using the same cache,
+    /// so opening the same EPUB URL again does not download it.
+    pub fn with_shared_cache(mut self, shared_cache: SharedCache) -> Self {
+        self.shared_cache = Some(shared_cache);
+        self
+    }
+
     async fn fetch_zip(&mut self) -> Result<(), Box<dyn Error>> {
-        // fetch zip file from original_url and extract files
-        let response = self.client.get(self.original_url.clone()).send().await?;
-        let stream = response.bytes().await?;
-        let mut reader = Cursor::new(stream);
+        // fetch zip file from original_url, unless it is in the shared cache, and extract files
+        let shared_cache = self.shared_cache.as_ref();
+        let (data, fetched) = match shared_cache.and_then(|cache| cache.get(&self.original_url)) {
+            Some(data) => (data, false),
+            None => {
+                let response = self.client.get(self.original_url.clone()).send().await?;
+                let response = response.error_for_status()?;
+                (response.bytes().await?.to_vec(), true)
+            }
+        };
+        let mut reader = Cursor::new(&data);
         let mut zip = ZipArchive::new(&mut reader)?;
-        for i in 0..zip.len() {
+
+        // only valid archives are shared
+        if let (true, Some(shared_cache)) = (fetched, shared_cache) {
+            shared_cache.insert(self.original_url.clone(), data.clone());
+        }
+
+        let total = zip.len();
+        for i in 0..total {
             let mut file = zip.by_index(i)?;
             let mut content = Vec::new();
             file.read_to_end(&mut content)?;
-            let url = self.logical_root_url.join(file.name())?;
+            let url = file_url(&self.logical_root_url, file.name())?;
+            if let Some(on_entry) = &self.on_entry {
+                on_entry.call(&url, i, total);
+            }
             self.files.insert(url, content);
         }
         Ok(())
@@ -39,6 +73,11 @@ impl Files for RemoteEpub {
         &self.logical_root_url
     }
 
+    /// The files are known once the archive is fetched by the first `get`.
+    fn urls(&self) -> Vec<&Url> {
+        self.files.keys().collect()
+    }
+
     async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
         // if `has_fetched_zip` is false, fetch zip file from original_url and extract files
         if !self.has_fetched_zip {
@@ -54,7 +93,7 @@ impl Files for RemoteEpub {
                 return None;
             }
         }
-        self.files.get(url)
+        self.files.get(&file_key(url))
     }
 }
 
@@ -63,10 +102,12 @@ pub fn read_from_epub_url(url: Url) -> RemoteEpub {
     RemoteEpub {
         original_url: url.clone(),
         logical_root_url: url,
-        client: reqwest::Client::builder().build().unwrap(),
+        client: default_client(),
         has_fetched_zip: false,
         fetch_zip_error: false,
         files: BTreeMap::new(),
+        shared_cache: None,
+        on_entry: None,
     }
 }
 
@@ -75,3 +116,33 @@ pub fn read_from_epub_url_str(url: &str) -> Result<RemoteEpub, url::ParseError>
     let url = Url::parse(url)?;
     Ok(read_from_epub_url(url))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::Ordering;
+
+    use url::Url;
+
+    use crate::file::testing::serve;
+    use crate::file::{read_from_epub_url, write_zip, Files, SharedCache};
+
+    #[tokio::test]
+    async fn test_shared_cache() {
+        let entries = vec![("META-INF/container.xml", b"<container/>".to_vec())];
+        let epub = write_zip(Cursor::new(Vec::new()), entries)
+            .unwrap()
+            .into_inner();
+
+        let (addr, requests) = serve(move |_| epub.clone()).await;
+
+        let cache = SharedCache::new();
+        let url = Url::parse(&format!("http://{}/book.epub", addr)).unwrap();
+        for _ in 0..2 {
+            let mut files = read_from_epub_url(url.clone()).with_shared_cache(cache.clone());
+            let container = files.root_url().join("META-INF/container.xml").unwrap();
+            assert_eq!(files.get(&container).await.unwrap(), b"<container/>");
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::path::Path;
+
+use url::Url;
+
+use crate::file::{
+    read_from_dir, read_from_epub_url, read_from_file, Files, LocalFiles, LocalFilesError,
+    RemoteEpub, RemoteFiles,
+};
+
+/// Files opened by [open], from whichever kind of source the input is.
+#[derive(Debug)]
+pub enum EpubSource {
+    /// A local EPUB file or an unpacked directory.
+    Local(LocalFiles),
+
+    /// An unpacked EPUB served over HTTP.
+    Remote(RemoteFiles),
+
+    /// An EPUB file served over HTTP.
+    RemoteEpub(RemoteEpub),
+}
+
+impl Files for EpubSource {
+    fn root_url(&self) -> &Url {
+        match self {
+            EpubSource::Local(files) => files.root_url(),
+            EpubSource::Remote(files) => files.root_url(),
+            EpubSource::RemoteEpub(files) => files.root_url(),
+        }
+    }
+
+    fn urls(&self) -> Vec<&Url> {
+        match self {
+            EpubSource::Local(files) => files.urls(),
+            EpubSource::Remote(files) => files.urls(),
+            EpubSource::RemoteEpub(files) => files.urls(),
+        }
+    }
+
+    fn size_of(&self, url: &Url) -> Option<u64> {
+        match self {
+            EpubSource::Local(files) => files.size_of(url),
+            EpubSource::Remote(files) => files.size_of(url),
+            EpubSource::RemoteEpub(files) => files.size_of(url),
+        }
+    }
+
+    async fn get(&mut self, url: &Url) -> Option<&Vec<u8>> {
+        match self {
+            EpubSource::Local(files) => files.get(url).await,
+            EpubSource::Remote(files) => files.get(url).await,
+            EpubSource::RemoteEpub(files) => files.get(url).await,
+        }
+    }
+
+    async fn prefetch(&mut self, urls: &[Url]) {
+        match self {
+            EpubSource::Local(files) => files.prefetch(urls).await,
+            EpubSource::Remote(files) => files.prefetch(urls).await,
+            EpubSource::RemoteEpub(files) => files.prefetch(urls).await,
+        }
+    }
+}
+
+/// Open an EPUB from a path or a URL, choosing the matching `read_from_*` function.
+///
+/// - an `http` or `https` URL ending with `.epub` is read by [read_from_epub_url]
+/// - any other `http` or `https` URL is an unpacked EPUB, read by [RemoteFiles]
+/// - a directory, or a `file` URL of one, is read by [read_from_dir]
+/// - any other path is a ZIP file, read by [read_from_file]
+///
+/// The local files are read into memory at once, the remote ones when they are got.
+pub fn open(path_or_url: &str) -> Result<EpubSource, LocalFilesError> {
+    if let Ok(mut url) = Url::parse(path_or_url) {
+        match url.scheme() {
+            "http" | "https" => {
+                if url.path().to_ascii_lowercase().ends_with(".epub") {
+                    return Ok(EpubSource::RemoteEpub(read_from_epub_url(url)));
+                }
+                // the files are resolved against the root URL, which must be a directory
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+                return Ok(EpubSource::Remote(RemoteFiles::new(url)));
+            }
+            "file" => {
+                if let Ok(path) = url.to_file_path() {
+                    return open_path(&path);
+                }
+            }
+            _ => {}
+        }
+    }
+    open_path(Path::new(path_or_url))
+}
+
+fn open_path(path: &Path) -> Result<EpubSource, LocalFilesError> {
+    let files = if path.is_dir() {
+        read_from_dir(path)?
+    } else {
+        read_from_file(File::open(path)?)?
+    };
+    Ok(EpubSource::Local(files))
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::book::parse_book;
+    use crate::file::testing::{sample_zip, SAMPLE_PATHS};
+    use crate::file::{open, EpubSource, Files, LocalFilesError};
+
+    const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample");
+
+    #[tokio::test]
+    async fn test_open_local() {
+        let mut files = open(SAMPLE).unwrap();
+        assert!(matches!(files, EpubSource::Local(_)));
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(book.primary_package().unwrap().spine.len(), 4);
+
+        let path = std::env::temp_dir().join(format!("eparser-open-{}.epub", std::process::id()));
+        std::fs::write(&path, sample_zip(&SAMPLE_PATHS)).unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let mut files = open(url.as_str()).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        assert_eq!(book.primary_package().unwrap().spine.len(), 4);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = open(&format!("{}/missing.epub", SAMPLE)).unwrap_err();
+        assert!(matches!(err, LocalFilesError::Io(_)));
+    }
+
+    #[test]
+    fn test_open_remote() {
+        let files = open("https://example.com/books/book.EPUB").unwrap();
+        assert!(matches!(files, EpubSource::RemoteEpub(_)));
+
+        let files = open("https://example.com/books/book").unwrap();
+        assert!(matches!(files, EpubSource::Remote(_)));
+        assert_eq!(files.root_url().as_str(), "https://example.com/books/book/");
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::file::write_zip;
+
+const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample");
+
+/// The minimal files of the sample to parse a book: the container and the package document.
+pub(crate) const SAMPLE_PATHS: [&str; 3] =
+    ["mimetype", "META-INF/container.xml", "OEBPS/content.opf"];
+
+/// Read the files of the sample as ZIP entries.
+pub(crate) fn sample_entries(paths: &[&str]) -> Vec<(String, Vec<u8>)> {
+    paths
+        .iter()
+        .map(|path| {
+            let data = std::fs::read(format!("{}/{}", SAMPLE, path)).unwrap();
+            (path.to_string(), data)
+        })
+        .collect()
+}
+
+/// Build an EPUB archive of the files of the sample.
+pub(crate) fn sample_zip(paths: &[&str]) -> Vec<u8> {
+    write_zip(Cursor::new(Vec::new()), sample_entries(paths))
+        .unwrap()
+        .into_inner()
+}
+
+/// Serve HTTP requests on a local port, responding with the body `respond` returns for the path.
+///
+/// It returns the address of the server and the number of the connections accepted.
+pub(crate) async fn serve(
+    respond: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+) -> (SocketAddr, Arc<AtomicUsize>) {
+    serve_with_headers(&[], respond).await
+}
+
+/// Serve HTTP requests like [serve], adding `headers` to every response.
+pub(crate) async fn serve_with_headers(
+    headers: &'static [(&'static str, &'static str)],
+    respond: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+) -> (SocketAddr, Arc<AtomicUsize>) {
+    serve_responses(headers, move |path| Some(respond(path))).await
+}
+
+/// Serve HTTP requests like [serve], responding with `404 Not Found` when `respond` returns `None`.
+pub(crate) async fn serve_with_not_found(
+    respond: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+) -> (SocketAddr, Arc<AtomicUsize>) {
+    serve_responses(&[], respond).await
+}
+
+async fn serve_responses(
+    headers: &'static [(&'static str, &'static str)],
+    respond: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let respond = Arc::new(respond);
+
+    let counter = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            let respond = respond.clone();
+            tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = match respond(path) {
+                    Some(body) => ("200 OK", body),
+                    None => ("404 Not Found", b"Not Found".to_vec()),
+                };
+
+                let mut header = format!("HTTP/1.1 {}\r\n", status);
+                for (name, value) in headers {
+                    header.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                header.push_str(&format!(
+                    "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                ));
+                stream.write_all(header.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            });
+        }
+    });
+
+    (addr, requests)
+}
@@ -0,0 +1,114 @@
+use std::io::{Seek, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::book::EPUB_MIMETYPE;
+use crate::file::LocalFilesError;
+
+/// The name of the `mimetype` entry.
+const MIMETYPE: &str = "mimetype";
+
+/// Write the entries to a ZIP archive laid out as an EPUB container.
+///
+/// The `mimetype` entry is written first and STORED without compression, as the OCF spec requires,
+/// whatever its position in `entries`. It contains `application/epub+zip` if `entries` lacks it.
+/// The other entries are DEFLATE-compressed in their original order.
+///
+/// Returns the inner writer after the archive is finished.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use eparser::file::{write_zip, ZipArchive};
+///
+/// let entries = vec![
+///     ("META-INF/container.xml", b"<container/>".to_vec()),
+///     ("mimetype", b"application/epub+zip".to_vec()),
+/// ];
+/// let cursor = write_zip(Cursor::new(Vec::new()), entries).unwrap();
+///
+/// let mut zip = ZipArchive::new(cursor).unwrap();
+/// assert_eq!(zip.by_index(0).unwrap().name(), "mimetype");
+/// ```
+pub fn write_zip<W, P, D>(
+    writer: W,
+    entries: impl IntoIterator<Item = (P, D)>,
+) -> Result<W, LocalFilesError>
+where
+    W: Write + Seek,
+    P: AsRef<str>,
+    D: AsRef<[u8]>,
+{
+    let (mimetype, entries): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|(path, _)| path.as_ref().trim_start_matches('/') == MIMETYPE);
+
+    let mut zip = ZipWriter::new(writer);
+
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    zip.start_file(MIMETYPE, stored)?;
+    match mimetype.first() {
+        Some((_, data)) => zip.write_all(data.as_ref())?,
+        None => zip.write_all(EPUB_MIMETYPE.as_bytes())?,
+    }
+
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (path, data) in entries {
+        zip.start_file(path.as_ref().trim_start_matches('/'), deflated)?;
+        zip.write_all(data.as_ref())?;
+    }
+
+    Ok(zip.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use url::Url;
+    use zip::CompressionMethod;
+
+    use crate::file::{read_from_reader, write_zip, Files, ZipArchive};
+
+    #[tokio::test]
+    async fn test_write_zip() {
+        let entries = vec![
+            ("META-INF/container.xml", b"<container/>".to_vec()),
+            ("OEBPS/content.opf", b"<package/>".to_vec()),
+            ("mimetype", b"application/epub+zip".to_vec()),
+        ];
+        let cursor = write_zip(Cursor::new(Vec::new()), entries).unwrap();
+
+        let mut zip = ZipArchive::new(cursor.clone()).unwrap();
+        assert_eq!(zip.len(), 3);
+        let mimetype = zip.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+        drop(mimetype);
+        let container = zip.by_index(1).unwrap();
+        assert_eq!(container.name(), "META-INF/container.xml");
+        assert_eq!(container.compression(), CompressionMethod::Deflated);
+        drop(container);
+
+        let mut files = read_from_reader(cursor).unwrap();
+        let url = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        assert_eq!(files.get(&url).await.unwrap(), b"<package/>");
+    }
+
+    #[test]
+    fn test_write_zip_missing_mimetype() {
+        let entries: Vec<(&str, &[u8])> = vec![("OEBPS/content.opf", b"<package/>")];
+        let cursor = write_zip(Cursor::new(Vec::new()), entries).unwrap();
+
+        let mut zip = ZipArchive::new(cursor).unwrap();
+        let mut mimetype = zip.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+
+        let mut content = String::new();
+        mimetype.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "application/epub+zip");
+    }
+}
@@ -1,6 +1,14 @@
 pub mod book;
+pub mod cfi;
+pub mod charset;
+pub mod css;
+pub mod encryption;
 pub mod file;
+pub mod mapping;
 pub mod oebps;
 pub mod package;
+pub mod svg;
+#[cfg(feature = "tidy")]
+pub mod tidy;
 pub mod utils;
 pub mod xhtml;
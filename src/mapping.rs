@@ -0,0 +1,220 @@
+use minidom::Element;
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::strip_doctype;
+
+/// A location of a rendition in the rendition mapping document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MappingLocation {
+    /// The URL of the location.
+    pub href: Url,
+
+    /// The `epub:rendition` attribute, describing the rendition the location belongs to,
+    /// e.g. `rendition:layout-pre-paginated`.
+    pub rendition: Option<String>,
+}
+
+/// A set of equivalent locations across the renditions.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MappingEntry {
+    /// The equivalent locations.
+    pub locations: Vec<MappingLocation>,
+}
+
+/// The rendition mapping document.
+///
+/// It associates the equivalent locations across the renditions of a multiple-rendition publication.
+///
+/// # References
+///
+/// [EPUB Multiple-Rendition Publications 1.1](https://www.w3.org/TR/epub-multi-rend-11/#sec-mapping)
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenditionMapping {
+    /// The entries of the `resource-map` nav.
+    pub entries: Vec<MappingEntry>,
+}
+
+impl RenditionMapping {
+    /// Find the entry containing a location.
+    ///
+    /// The fragment of the URL is compared, so pass the exact location to find.
+    pub fn entry_for(&self, href: &Url) -> Option<&MappingEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.locations.iter().any(|loc| &loc.href == href))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MappingParseError {
+    #[error("Invalid XML, {0}")]
+    ParseError(#[from] minidom::Error),
+
+    #[error("Invalid root element: {0}")]
+    InvalidRoot(String),
+
+    #[error("Missing resource-map nav element")]
+    MissingResourceMap,
+}
+
+/// Parse the rendition mapping document.
+///
+/// The structure of the mapping document is as follows:
+///
+/// ```xml
+/// <nav epub:type="resource-map">
+///     <ul>
+///         <li>
+///             <ul>
+///                 <li><a href="fixed/page1.xhtml" epub:rendition="rendition:layout-pre-paginated"/></li>
+///                 <li><a href="reflow/chapter1.xhtml" epub:rendition="rendition:layout-reflowable"/></li>
+///             </ul>
+///         </li>
+///     </ul>
+/// </nav>
+/// ```
+///
+/// Each item of the outermost list is a [MappingEntry]. The `epub:rendition` attribute is
+/// inherited from the enclosing elements. Every `href` is resolved against `base_url`,
+/// which is the URL of the mapping document.
+pub fn parse_mapping(str: &str, base_url: &Url) -> Result<RenditionMapping, MappingParseError> {
+    let root_elem = strip_doctype(str).parse::<Element>()?;
+
+    if root_elem.name() != "html" {
+        return Err(MappingParseError::InvalidRoot(root_elem.name().to_string()));
+    }
+
+    let nav = find_resource_map(&root_elem).ok_or(MappingParseError::MissingResourceMap)?;
+
+    let entries = nav
+        .children()
+        .filter(|n| n.name() == "ul")
+        .flat_map(|ul| {
+            let rendition = ul.attr("epub:rendition").or(nav.attr("epub:rendition"));
+            ul.children().filter(|n| n.name() == "li").map(move |li| {
+                let mut locations = Vec::new();
+                collect_locations(li, rendition, base_url, &mut locations);
+                MappingEntry { locations }
+            })
+        })
+        .filter(|entry| !entry.locations.is_empty())
+        .collect();
+
+    Ok(RenditionMapping { entries })
+}
+
+fn find_resource_map(elem: &Element) -> Option<&Element> {
+    elem.children().find_map(|child| {
+        if child.name() == "nav" && child.attr("epub:type") == Some("resource-map") {
+            Some(child)
+        } else {
+            find_resource_map(child)
+        }
+    })
+}
+
+fn collect_locations(
+    elem: &Element,
+    rendition: Option<&str>,
+    base_url: &Url,
+    locations: &mut Vec<MappingLocation>,
+) {
+    let rendition = elem.attr("epub:rendition").or(rendition);
+    if elem.name() == "a" {
+        if let Some(href) = elem.attr("href").and_then(|href| base_url.join(href).ok()) {
+            locations.push(MappingLocation {
+                href,
+                rendition: rendition.map(|s| s.to_string()),
+            });
+        }
+        return;
+    }
+    for child in elem.children() {
+        collect_locations(child, rendition, base_url, locations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::mapping::parse_mapping;
+
+    #[test]
+    fn test_parse_mapping() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+    <title>Mapping</title>
+</head>
+<body>
+    <nav epub:type="resource-map">
+        <ul>
+            <li>
+                <ul>
+                    <li><a href="fixed/page1.xhtml" epub:rendition="rendition:layout-pre-paginated"/></li>
+                    <li><a href="reflow/chapter1.xhtml#p1" epub:rendition="rendition:layout-reflowable"/></li>
+                </ul>
+            </li>
+            <li>
+                <ul>
+                    <li><a href="fixed/page2.xhtml" epub:rendition="rendition:layout-pre-paginated"/></li>
+                    <li><a href="reflow/chapter1.xhtml#p2" epub:rendition="rendition:layout-reflowable"/></li>
+                </ul>
+            </li>
+        </ul>
+    </nav>
+</body>
+</html>"#;
+        let base_url = Url::parse("epub:/EPUB/mapping.xhtml").unwrap();
+        let mapping = parse_mapping(data, &base_url).unwrap();
+
+        assert_eq!(mapping.entries.len(), 2);
+        let entry = &mapping.entries[1];
+        assert_eq!(entry.locations.len(), 2);
+        assert_eq!(
+            entry.locations[0].href.as_str(),
+            "epub:/EPUB/fixed/page2.xhtml"
+        );
+        assert_eq!(
+            entry.locations[1].rendition.as_deref(),
+            Some("rendition:layout-reflowable")
+        );
+
+        let url = Url::parse("epub:/EPUB/reflow/chapter1.xhtml#p2").unwrap();
+        assert_eq!(mapping.entry_for(&url), Some(entry));
+    }
+
+    #[test]
+    fn test_nearest_rendition() {
+        let data = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<body>
+    <nav epub:type="resource-map" epub:rendition="rendition:layout-pre-paginated">
+        <ul epub:rendition="rendition:layout-reflowable">
+            <li><a href="chapter1.xhtml"/></li>
+        </ul>
+        <ul>
+            <li><a href="page1.xhtml"/></li>
+        </ul>
+    </nav>
+</body>
+</html>"#;
+        let base_url = Url::parse("epub:/EPUB/mapping.xhtml").unwrap();
+        let mapping = parse_mapping(data, &base_url).unwrap();
+
+        let renditions = mapping
+            .entries
+            .iter()
+            .map(|entry| entry.locations[0].rendition.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            renditions,
+            vec![
+                Some("rendition:layout-reflowable"),
+                Some("rendition:layout-pre-paginated")
+            ]
+        );
+    }
+}
@@ -1,7 +1,4 @@
-use std::error::Error;
-use std::fmt::Display;
 use std::ops::Deref;
-use std::str::FromStr;
 
 use minidom::Element;
 use thiserror::Error;
@@ -9,14 +6,60 @@ use url::Url;
 
 use crate::package::media_type::media_types::OEBPS;
 use crate::package::media_type::MediaType;
+use crate::utils::strip_bom;
 
 /// The rootfile element of the container.xml file.
 ///
 /// Each one represents a root file of package document.
 #[derive(Debug)]
 pub struct Rootfile {
+    /// The full path of the package document, resolved against the root url.
+    ///
+    /// A full path starting with `OPS/` is rewritten to `OEBPS/` when
+    /// [ContainerParseOptions::rewrite_ops_prefix] is set.
     pub full_path: Url,
     pub media_type: MediaType,
+
+    /// The rendition selection attributes of the rootfile.
+    pub rendition: RenditionSelection,
+}
+
+/// The namespace of the rendition selection attributes in the container.xml file.
+pub static RENDITION_NAMESPACE: &str = "http://www.idpf.org/2013/rendition";
+
+/// The rendition selection attributes of a rootfile, which describe the rendition
+/// of a multiple-rendition publication so that a reading system can choose one.
+///
+/// # References
+///
+/// [EPUB Multiple-Rendition Publications 1.1](https://www.w3.org/TR/epub-multi-rend-11/#rendition-selection)
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RenditionSelection {
+    /// The `rendition:label` attribute, a human-readable name like `Reflowable`.
+    pub label: Option<String>,
+
+    /// The `rendition:layout` attribute, `reflowable` or `pre-paginated`.
+    pub layout: Option<String>,
+
+    /// The `rendition:media` attribute, a media query like `(min-width: 1024px)`.
+    pub media: Option<String>,
+
+    /// The `rendition:language` attribute, the language of the rendition.
+    pub language: Option<String>,
+
+    /// The `rendition:accessMode` attribute, like `textual` or `visual`.
+    pub access_mode: Option<String>,
+}
+
+/// The link element of the container.xml file.
+///
+/// It identifies resources necessary for the processing of the OCF ZIP container,
+/// like the rendition mapping document.
+#[derive(Debug)]
+pub struct ContainerLink {
+    pub href: Url,
+    pub rel: String,
+    pub media_type: Option<MediaType>,
 }
 
 /// Container.xml
@@ -24,6 +67,49 @@ pub struct Rootfile {
 pub struct Container {
     /// The rootfiles element of the container.xml file.
     pub rootfiles: Vec<Rootfile>,
+
+    /// The links element of the container.xml file.
+    pub links: Vec<ContainerLink>,
+}
+
+impl Container {
+    /// Get the first rootfile matching the predicate, like the reflowable rendition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::oebps::parse_container;
+    /// use url::Url;
+    ///
+    /// let container = parse_container(
+    ///     r#"<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    ///         xmlns:rendition="http://www.idpf.org/2013/rendition">
+    ///     <rootfiles>
+    ///         <rootfile full-path="fixed.opf" media-type="application/oebps-package+xml"
+    ///             rendition:layout="pre-paginated"/>
+    ///         <rootfile full-path="reflow.opf" media-type="application/oebps-package+xml"
+    ///             rendition:layout="reflowable"/>
+    ///     </rootfiles>
+    /// </container>"#,
+    ///     &Url::parse("epub:/").unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let rootfile = container
+    ///     .select(|rootfile| rootfile.rendition.layout.as_deref() == Some("reflowable"))
+    ///     .unwrap();
+    /// assert_eq!(rootfile.full_path.as_str(), "epub:/reflow.opf");
+    /// ```
+    pub fn select(&self, predicate: impl Fn(&Rootfile) -> bool) -> Option<&Rootfile> {
+        self.rootfiles.iter().find(|rootfile| predicate(rootfile))
+    }
+
+    /// Get the link to the rendition mapping document.
+    pub fn mapping_link(&self) -> Option<&ContainerLink> {
+        self.links
+            .iter()
+            .find(|link| link.rel.split_whitespace().any(|rel| rel == "mapping"))
+    }
 }
 
 /// Errors that can occur when parsing the container.xml file.
@@ -32,12 +118,21 @@ pub enum ContainerError {
     #[error("Missing rootfiles element")]
     MissingRootfiles,
 
+    #[error("The rootfiles element MUST contain at least one rootfile")]
+    NoRootfiles,
+
     #[error("Root file MUST have a full-path attribute but it is missing")]
     MissingFullPath,
 
     #[error("Root file MUST have a media-type attribute but it is missing")]
     MissingMediaType,
 
+    #[error("Link MUST have a href attribute but it is missing")]
+    MissingLinkHref,
+
+    #[error("Link MUST have a rel attribute but it is missing")]
+    MissingLinkRel,
+
     #[error("Invalid media type, expected application/oebps-package+xml but found {0}")]
     InvalidMediaType(MediaType),
 
@@ -48,15 +143,63 @@ pub enum ContainerError {
     ParseError(#[from] minidom::Error),
 }
 
+/// Options of parsing the container.xml file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContainerParseOptions {
+    /// The root url of the container, every path in the container.xml file is resolved against it.
+    ///
+    /// The paths are relative to the root of the container, so a leading `/` is ignored
+    /// and they never escape a root like `http://host/book/`.
+    pub root_url: Url,
+
+    /// Rewrite the rootfile full paths starting with `OPS/` to `OEBPS/`.
+    ///
+    /// Some books, like the ones produced by Sigil, point the container at `OPS/content.opf`
+    /// while the zip stores it under `OEBPS/`.
+    pub rewrite_ops_prefix: bool,
+}
+
+impl ContainerParseOptions {
+    /// Create parse options with the default settings.
+    pub fn new(root_url: Url) -> Self {
+        ContainerParseOptions {
+            root_url,
+            rewrite_ops_prefix: false,
+        }
+    }
+}
+
+/// Rewrite a full path starting with `OPS/` to `OEBPS/`.
+///
+/// Returns `None` if the path does not start with `OPS/`.
+pub fn rewrite_ops_prefix(full_path: &str) -> Option<String> {
+    full_path
+        .strip_prefix("OPS/")
+        .map(|rest| format!("OEBPS/{}", rest))
+}
+
 /// Parse the container.xml file.
 pub fn parse_container(str: &str, root_path: &Url) -> Result<Container, ContainerError> {
-    let rootfiles = str
+    parse_container_with_options(str, &ContainerParseOptions::new(root_path.clone()))
+}
+
+/// Parse the container.xml file with the given options.
+pub fn parse_container_with_options(
+    str: &str,
+    options: &ContainerParseOptions,
+) -> Result<Container, ContainerError> {
+    let root_path = &options.root_url;
+    let container = strip_bom(str)
         .parse::<Element>()
-        .map_err(ContainerError::ParseError)?
+        .map_err(ContainerError::ParseError)?;
+
+    let rootfiles_elem = container
         // container
         .children()
         .find(|n| n.name() == "rootfiles")
-        .ok_or(ContainerError::MissingRootfiles)?
+        .ok_or(ContainerError::MissingRootfiles)?;
+
+    let rootfiles = rootfiles_elem
         // container -> rootfiles
         .children()
         .filter(|n| n.name() == "rootfile")
@@ -67,26 +210,85 @@ pub fn parse_container(str: &str, root_path: &Url) -> Result<Container, Containe
                 .attr("media-type")
                 .ok_or(ContainerError::MissingMediaType)?;
 
-            let full_path = root_path.join(full_path_str)?;
+            let full_path_str = full_path_str.trim_start_matches('/');
+            let full_path = match rewrite_ops_prefix(full_path_str) {
+                Some(rewritten) if options.rewrite_ops_prefix => root_path.join(&rewritten)?,
+                _ => root_path.join(full_path_str)?,
+            };
 
             let media_type = MediaType::new(media_type_str);
             if &media_type != OEBPS.deref() {
                 return Err(ContainerError::InvalidMediaType(media_type));
             }
 
+            let rendition = parse_rendition_selection(&[&container, rootfiles_elem, n]);
+
             Ok::<_, ContainerError>(Rootfile {
                 full_path,
                 media_type,
+                rendition,
             })
         })
         .collect::<Result<Vec<Rootfile>, ContainerError>>()?;
 
-    Ok(Container { rootfiles })
+    if rootfiles.is_empty() {
+        return Err(ContainerError::NoRootfiles);
+    }
+
+    let links = container
+        // container
+        .children()
+        .filter(|n| n.name() == "links")
+        // container -> links -> link
+        .flat_map(|n| n.children().filter(|n| n.name() == "link"))
+        .map(|n| {
+            let href_str = n.attr("href").ok_or(ContainerError::MissingLinkHref)?;
+            let rel = n.attr("rel").ok_or(ContainerError::MissingLinkRel)?;
+
+            Ok::<_, ContainerError>(ContainerLink {
+                href: root_path.join(href_str.trim_start_matches('/'))?,
+                rel: rel.to_string(),
+                media_type: n.attr("media-type").map(MediaType::new),
+            })
+        })
+        .collect::<Result<Vec<ContainerLink>, ContainerError>>()?;
+
+    Ok(Container { rootfiles, links })
+}
+
+/// Parse the rendition selection attributes of the last element of `elems`,
+/// the others are its ancestors, which may declare the prefix of the rendition namespace.
+fn parse_rendition_selection(elems: &[&Element]) -> RenditionSelection {
+    let prefix = elems
+        .iter()
+        .rev()
+        .flat_map(|elem| elem.prefixes.declared_prefixes())
+        .find(|(_, ns)| ns.as_str() == RENDITION_NAMESPACE)
+        .and_then(|(prefix, _)| prefix.clone())
+        .unwrap_or_else(|| "rendition".to_string());
+
+    let elem = elems.last().unwrap();
+    let attr = |name: &str| {
+        elem.attr(&format!("{}:{}", prefix, name))
+            .map(|value| value.to_string())
+    };
+    RenditionSelection {
+        label: attr("label"),
+        layout: attr("layout"),
+        media: attr("media"),
+        language: attr("language"),
+        access_mode: attr("accessMode"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::oebps::parse_container;
+    use url::Url;
+
+    use crate::oebps::{
+        parse_container, parse_container_with_options, ContainerError, ContainerParseOptions,
+        RenditionSelection,
+    };
 
     #[test]
     fn test_parse_container() {
@@ -97,8 +299,148 @@ mod tests {
     </rootfiles>
 </container>"#;
 
-        let container = parse_container(data).unwrap();
+        let container = parse_container(data, &Url::parse("epub:/").unwrap()).unwrap();
 
         assert_eq!(container.rootfiles.len(), 1);
+        assert!(container.mapping_link().is_none());
+    }
+
+    #[test]
+    fn test_parse_container_root_url() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+        <rootfile full-path="/OEBPS/fixed.opf" media-type="application/oebps-package+xml"/>
+        <rootfile full-path="./OEBPS/./content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#;
+
+        for root in ["epub:/", "file:///books/x/", "http://host/book/"] {
+            let root_url = Url::parse(root).unwrap();
+            let container = parse_container(data, &root_url).unwrap();
+            assert_eq!(
+                container.rootfiles[0].full_path.as_str(),
+                format!("{}OEBPS/content.opf", root)
+            );
+            assert_eq!(
+                container.rootfiles[1].full_path.as_str(),
+                format!("{}OEBPS/fixed.opf", root)
+            );
+            assert_eq!(
+                container.rootfiles[2].full_path,
+                container.rootfiles[0].full_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_container_no_rootfiles() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <link href="EPUB/mapping.xhtml" rel="mapping"/>
+    </rootfiles>
+</container>"#;
+
+        let err = parse_container(data, &Url::parse("epub:/").unwrap()).unwrap_err();
+        assert!(matches!(err, ContainerError::NoRootfiles));
+    }
+
+    #[test]
+    fn test_parse_container_links() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="EPUB/fixed/package.opf" media-type="application/oebps-package+xml"/>
+        <rootfile full-path="EPUB/reflow/package.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+    <links>
+        <link href="EPUB/mapping.xhtml" rel="mapping" media-type="application/xhtml+xml"/>
+    </links>
+</container>"#;
+
+        let container = parse_container(data, &Url::parse("epub:/").unwrap()).unwrap();
+
+        assert_eq!(container.rootfiles.len(), 2);
+        assert_eq!(
+            container.mapping_link().unwrap().href.as_str(),
+            "epub:/EPUB/mapping.xhtml"
+        );
+    }
+
+    #[test]
+    fn test_parse_container_bom() {
+        let data = "\u{FEFF}<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+    <rootfiles>
+        <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+    </rootfiles>
+</container>";
+
+        let container = parse_container(data, &Url::parse("epub:/").unwrap()).unwrap();
+        assert_eq!(container.rootfiles.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_container_renditions() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    xmlns:r="http://www.idpf.org/2013/rendition">
+    <rootfiles>
+        <rootfile full-path="EPUB/fixed/package.opf" media-type="application/oebps-package+xml"
+            r:label="Fixed Layout" r:layout="pre-paginated" r:accessMode="visual"/>
+        <rootfile full-path="EPUB/reflow/package.opf" media-type="application/oebps-package+xml"
+            r:label="Reflowable" r:layout="reflowable" r:media="(max-width: 600px)" r:language="en"/>
+    </rootfiles>
+</container>"#;
+
+        let container = parse_container(data, &Url::parse("epub:/").unwrap()).unwrap();
+
+        let fixed = &container.rootfiles[0].rendition;
+        assert_eq!(fixed.label.as_deref(), Some("Fixed Layout"));
+        assert_eq!(fixed.access_mode.as_deref(), Some("visual"));
+        assert!(fixed.media.is_none());
+
+        let reflow = container
+            .select(|rootfile| rootfile.rendition.layout.as_deref() == Some("reflowable"))
+            .unwrap();
+        assert_eq!(reflow.full_path.as_str(), "epub:/EPUB/reflow/package.opf");
+        assert_eq!(
+            reflow.rendition,
+            RenditionSelection {
+                label: Some("Reflowable".to_string()),
+                layout: Some("reflowable".to_string()),
+                media: Some("(max-width: 600px)".to_string()),
+                language: Some("en".to_string()),
+                access_mode: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_container_ops_prefix() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OPS/content.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#;
+
+        let root_url = Url::parse("epub:/").unwrap();
+
+        let container = parse_container(data, &root_url).unwrap();
+        assert_eq!(
+            container.rootfiles[0].full_path.as_str(),
+            "epub:/OPS/content.opf"
+        );
+
+        let mut options = ContainerParseOptions::new(root_url);
+        options.rewrite_ops_prefix = true;
+        let container = parse_container_with_options(data, &options).unwrap();
+        assert_eq!(
+            container.rootfiles[0].full_path.as_str(),
+            "epub:/OEBPS/content.opf"
+        );
     }
 }
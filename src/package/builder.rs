@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use url::Url;
+
+use crate::package::manifest::{Manifest, ManifestCheckError, Resource};
+use crate::package::metadata::{Link, Meta, Metadata, MetadataCheckError, MetadataElement};
+use crate::package::prefix::prefixes::DC;
+use crate::package::property::{Properties, Property, WithNamespace};
+use crate::package::spine::{Spine, SpineReference};
+use crate::package::{Direction, Package, Version};
+
+/// The id of the `dc:identifier` element holding the unique identifier.
+const UNIQUE_IDENTIFIER_ID: &str = "uid";
+
+#[derive(Debug, Error)]
+pub enum PackageBuildError {
+    #[error("The spine reference {0} does not point to a resource in the manifest")]
+    SpineReferenceNotFound(String),
+
+    #[error("Invalid manifest: {0}")]
+    ManifestCheckError(#[from] ManifestCheckError),
+
+    #[error("Invalid metadata: {0}")]
+    MetadataCheckError(#[from] MetadataCheckError),
+}
+
+/// A builder to construct an EPUB 3 [Package] in code.
+///
+/// The built package is validated by the same checks as a parsed one,
+/// see [Manifest::new] and [Metadata::new].
+///
+/// # Examples
+///
+/// ```
+/// use eparser::package::builder::PackageBuilder;
+/// use eparser::package::manifest::Resource;
+/// use eparser::package::media_type::MediaType;
+/// use url::Url;
+///
+/// let resource = |id: &str, href: &str| Resource {
+///     id: id.to_string(),
+///     href: Url::parse("epub:/OEBPS/").unwrap().join(href).unwrap(),
+///     media_type: MediaType::new("application/xhtml+xml"),
+///     fallback: None,
+///     media_overlay: None,
+///     properties: None,
+/// };
+///
+/// let package = PackageBuilder::new("urn:uuid:1234")
+///     .title("Title")
+///     .language("en")
+///     .nav(resource("nav", "nav.xhtml"))
+///     .resource(resource("chapter1", "chapter1.xhtml"))
+///     .spine_ref("chapter1")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(package.unique_identifier().unwrap().value, "urn:uuid:1234");
+/// assert_eq!(package.nav_resource().unwrap().id, "nav");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PackageBuilder {
+    id: Option<String>,
+    base_url: Url,
+    prefix: Option<String>,
+    dir: Option<Direction>,
+    lang: Option<String>,
+    elems: Vec<MetadataElement>,
+    metas: Vec<Meta>,
+    links: Vec<Link>,
+    modified: Option<DateTime<Utc>>,
+    resources: Vec<Resource>,
+    spine: Vec<SpineReference>,
+    spine_dir: Option<String>,
+}
+
+impl PackageBuilder {
+    /// Create a builder of a package with the given unique identifier.
+    pub fn new(unique_identifier: &str) -> Self {
+        let builder = PackageBuilder {
+            id: None,
+            base_url: Url::parse("epub:/OEBPS/content.opf").unwrap(),
+            prefix: None,
+            dir: None,
+            lang: None,
+            elems: Vec::new(),
+            metas: Vec::new(),
+            links: Vec::new(),
+            modified: None,
+            resources: Vec::new(),
+            spine: Vec::new(),
+            spine_dir: None,
+        };
+        builder.element(MetadataElement {
+            id: Some(UNIQUE_IDENTIFIER_ID.to_string()),
+            lang: None,
+            dir: None,
+            tag_name: WithNamespace::from_prefix(&DC, "identifier".to_string()),
+            value: unique_identifier.to_string(),
+            attrs: BTreeMap::new(),
+        })
+    }
+
+    /// Set the `id` attribute of the package element.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    /// Set the URL of the package document, it is `epub:/OEBPS/content.opf` by default.
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Set the `prefix` attribute of the package element.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Set the `dir` attribute of the package element.
+    pub fn dir(mut self, dir: Direction) -> Self {
+        self.dir = Some(dir);
+        self
+    }
+
+    /// Set the `xml:lang` attribute of the package element.
+    pub fn lang(mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    /// Add a Dublin Core element, like `title` or `creator`.
+    pub fn dc(self, name: &str, value: &str) -> Self {
+        self.element(MetadataElement {
+            id: None,
+            lang: None,
+            dir: None,
+            tag_name: WithNamespace::from_prefix(&DC, name.to_string()),
+            value: value.to_string(),
+            attrs: BTreeMap::new(),
+        })
+    }
+
+    /// Add a `dc:title` element.
+    pub fn title(self, title: &str) -> Self {
+        self.dc("title", title)
+    }
+
+    /// Add a `dc:language` element.
+    pub fn language(self, language: &str) -> Self {
+        self.dc("language", language)
+    }
+
+    /// Add a metadata element.
+    pub fn element(mut self, elem: MetadataElement) -> Self {
+        self.elems.push(elem);
+        self
+    }
+
+    /// Add a meta element.
+    pub fn meta(mut self, meta: Meta) -> Self {
+        self.metas.push(meta);
+        self
+    }
+
+    /// Add a link element.
+    pub fn link(mut self, link: Link) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// Set the last modification date, it is the time of building by default.
+    pub fn modified(mut self, modified: DateTime<Utc>) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Add a resource to the manifest.
+    pub fn resource(mut self, resource: Resource) -> Self {
+        self.resources.push(resource);
+        self
+    }
+
+    /// Add the navigation document to the manifest, the `nav` property is assigned to it.
+    pub fn nav(self, mut resource: Resource) -> Self {
+        let nav = Property::opf("nav");
+        let properties = resource
+            .properties
+            .get_or_insert_with(|| Properties::new(Vec::new()));
+        if !properties.contains(&nav) {
+            properties.push(nav);
+        }
+        self.resource(resource)
+    }
+
+    /// Add a reference to the resource with the given id to the spine.
+    pub fn spine_ref(self, id: &str) -> Self {
+        self.spine_item(SpineReference::new(id))
+    }
+
+    /// Add a reference to the spine.
+    pub fn spine_item(mut self, spine_ref: SpineReference) -> Self {
+        self.spine.push(spine_ref);
+        self
+    }
+
+    /// Set the direction of the spine.
+    pub fn spine_dir(mut self, dir: &str) -> Self {
+        self.spine_dir = Some(dir.to_string());
+        self
+    }
+
+    /// Validate and build the [Package].
+    pub fn build(self) -> Result<Package, PackageBuildError> {
+        let mut metas = self.metas;
+        if !metas
+            .iter()
+            .any(|meta| meta.property == Property::dcterms("modified"))
+        {
+            let modified = self.modified.unwrap_or_else(Utc::now);
+            metas.push(Meta {
+                id: None,
+                lang: None,
+                dir: None,
+                property: Property::dcterms("modified"),
+                refines: None,
+                scheme: None,
+                value: modified.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            });
+        }
+
+        let metadata =
+            Metadata::new(self.elems, metas, self.links)?.with_base_url(self.base_url.clone());
+        let manifest = Manifest::new(None, self.resources)?;
+
+        if let Some(spine_ref) = self
+            .spine
+            .iter()
+            .find(|spine_ref| manifest.get_resource_by_id(&spine_ref.id).is_none())
+        {
+            return Err(PackageBuildError::SpineReferenceNotFound(
+                spine_ref.id.clone(),
+            ));
+        }
+
+        Ok(Package {
+            id: self.id,
+            base_url: self.base_url,
+            unique_identifier_ref: UNIQUE_IDENTIFIER_ID.to_string(),
+            version: Version::V3,
+            metadata,
+            manifest,
+            spine: Spine {
+                id: None,
+                dir: self.spine_dir,
+                toc: None,
+                refs: self.spine,
+            },
+            guide: Vec::new(),
+            prefix: self.prefix,
+            dir: self.dir,
+            lang: self.lang,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::package::builder::{PackageBuildError, PackageBuilder};
+    use crate::package::manifest::{ManifestCheckError, Resource};
+    use crate::package::media_type::MediaType;
+    use crate::package::Version;
+
+    fn resource(id: &str) -> Resource {
+        Resource {
+            id: id.to_string(),
+            href: Url::parse(&format!("epub:/OEBPS/{}.xhtml", id)).unwrap(),
+            media_type: MediaType::new("application/xhtml+xml"),
+            fallback: None,
+            media_overlay: None,
+            properties: None,
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let package = PackageBuilder::new("urn:uuid:1234")
+            .title("Title")
+            .language("en")
+            .dc("creator", "Author")
+            .nav(resource("nav"))
+            .resource(resource("chapter1"))
+            .spine_ref("chapter1")
+            .build()
+            .unwrap();
+
+        assert_eq!(package.version, Version::V3);
+        assert_eq!(package.unique_identifier_ref(), "uid");
+        assert_eq!(package.metadata.titles()[0].value, "Title");
+        assert!(package.metadata.last_modified.is_some());
+        assert_eq!(package.nav_resource().unwrap().id, "nav");
+        assert_eq!(package.spine.len(), 1);
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let builder = PackageBuilder::new("urn:uuid:1234")
+            .title("Title")
+            .language("en")
+            .resource(resource("chapter1"));
+
+        let err = builder.clone().build().unwrap_err();
+        assert!(matches!(
+            err,
+            PackageBuildError::ManifestCheckError(ManifestCheckError::NavResourceNotFound)
+        ));
+
+        let err = builder
+            .nav(resource("nav"))
+            .spine_ref("chapter2")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, PackageBuildError::SpineReferenceNotFound(id) if id == "chapter2"));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use url::Url;
+
+use crate::package::nav::Nav;
+use crate::package::Package;
+
+/// The mismatches between a nav and the spine, see [Package::nav_consistency].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct NavConsistencyReport {
+    /// The hrefs of the nav points referring to no resource in the manifest.
+    pub missing_resources: Vec<Url>,
+
+    /// The hrefs of the nav points referring to a resource which is not in the spine.
+    pub not_in_spine: Vec<Url>,
+
+    /// The idrefs of the spine items no nav point refers to.
+    pub not_in_nav: Vec<String>,
+}
+
+impl NavConsistencyReport {
+    /// Check if the nav and the spine are consistent.
+    pub fn is_empty(&self) -> bool {
+        self.missing_resources.is_empty()
+            && self.not_in_spine.is_empty()
+            && self.not_in_nav.is_empty()
+    }
+}
+
+impl Package {
+    /// Check the nav points of a nav, usually the table of contents, against the spine.
+    ///
+    /// It reports the nav points referring to a resource missing in the manifest or the spine,
+    /// and the spine items without a nav point. The fragments of the hrefs are ignored,
+    /// and every href or idref is reported once, in document order.
+    pub fn nav_consistency(&self, nav: &Nav) -> NavConsistencyReport {
+        let mut report = NavConsistencyReport::default();
+        let mut reported = HashSet::new();
+        let mut referred = HashSet::new();
+
+        for (_, point) in nav.flatten() {
+            let Some(href) = &point.label.href else {
+                continue;
+            };
+            let mut url = href.clone();
+            url.set_fragment(None);
+            match self.get_res_by_href(href) {
+                Some(resource) => {
+                    referred.insert(resource.id.as_str());
+                    if self.spine.position_of(&resource.id).is_none()
+                        && reported.insert(&resource.id)
+                    {
+                        report.not_in_spine.push(url);
+                    }
+                }
+                None => {
+                    if !report.missing_resources.contains(&url) {
+                        report.missing_resources.push(url);
+                    }
+                }
+            }
+        }
+
+        for spine_ref in self.spine.iter() {
+            if !referred.contains(spine_ref.id.as_str())
+                && !report.not_in_nav.contains(&spine_ref.id)
+            {
+                report.not_in_nav.push(spine_ref.id.clone());
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::package::nav::parse_nav_document;
+    use crate::package::parser::{PackageParseOptions, PackageParser};
+
+    #[test]
+    fn test_nav_consistency() {
+        let base_url = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let package = PackageParser::new(PackageParseOptions::new(base_url))
+            .parse(opf)
+            .unwrap();
+
+        let nav_url = Url::parse("epub:/OEBPS/nav.xhtml").unwrap();
+        let nav = include_str!("../../res/sample/OEBPS/nav.xhtml");
+        let nav_doc = parse_nav_document(nav, &nav_url).unwrap();
+        assert!(package.nav_consistency(nav_doc.toc().unwrap()).is_empty());
+
+        let nav = nav
+            .replace(
+                r#"<a href="Text/chapter2.xhtml">"#,
+                r#"<a href="Text/chapter3.xhtml#s1">"#,
+            )
+            .replace(
+                r#"<a href="Text/contents.xhtml">"#,
+                r#"<a href="Styles/style.css#top">"#,
+            );
+        let nav_doc = parse_nav_document(&nav, &nav_url).unwrap();
+        let report = package.nav_consistency(nav_doc.toc().unwrap());
+        assert_eq!(
+            report.missing_resources,
+            vec![Url::parse("epub:/OEBPS/Text/chapter3.xhtml").unwrap()]
+        );
+        assert_eq!(
+            report.not_in_spine,
+            vec![Url::parse("epub:/OEBPS/Styles/style.css").unwrap()]
+        );
+        assert_eq!(report.not_in_nav, vec!["contents", "chapter2"]);
+    }
+}
@@ -0,0 +1,156 @@
+use thiserror::Error;
+
+use crate::charset::decode_document;
+use crate::file::Files;
+use crate::package::media_type::media_types;
+use crate::package::media_type::MediaType;
+use crate::package::Package;
+use crate::svg::{parse_svg, SvgDocument, SvgParseError};
+use crate::xhtml::{parse_xhtml, XHTMLParseError, XHTML};
+
+#[derive(Debug, Error)]
+pub enum ContentDocumentError {
+    #[error("The resource {0} is not in the manifest")]
+    ResourceNotFound(String),
+
+    #[error("The resource {0} is not a content document, its media type is {1}")]
+    NotContentDocument(String, MediaType),
+
+    #[error("The file of the resource {0} is missing")]
+    MissingFile(String),
+
+    #[error("Invalid content document: {0}")]
+    ParseError(#[from] XHTMLParseError),
+
+    #[error("Invalid SVG content document: {0}")]
+    SvgParseError(#[from] SvgParseError),
+}
+
+impl Package {
+    /// Get the parsed content document of the resource with the given id, usually the idref of a spine item.
+    ///
+    /// The media type declared in the manifest is checked before parsing, it has to be
+    /// `application/xhtml+xml`, so a spine item that references an image
+    /// or a style sheet by mistake gives [ContentDocumentError::NotContentDocument]
+    /// instead of a confusing parse error. The SVG content documents are got by [Package::svg_document].
+    pub async fn content_document<F: Files>(
+        &self,
+        idref: &str,
+        files: &mut F,
+    ) -> Result<XHTML, ContentDocumentError> {
+        let data = self
+            .content_document_data(idref, &media_types::XHTML, files)
+            .await?;
+        Ok(parse_xhtml(&data)?)
+    }
+
+    /// Get the parsed SVG content document of the resource with the given id,
+    /// like the full-page wrapper of a fixed-layout picture book.
+    ///
+    /// Its media type has to be `image/svg+xml`, see [Package::content_document].
+    pub async fn svg_document<F: Files>(
+        &self,
+        idref: &str,
+        files: &mut F,
+    ) -> Result<SvgDocument, ContentDocumentError> {
+        let data = self
+            .content_document_data(idref, &media_types::SVG, files)
+            .await?;
+        Ok(parse_svg(&data)?)
+    }
+
+    /// Get the decoded content of the resource, checking its media type first.
+    async fn content_document_data<F: Files>(
+        &self,
+        idref: &str,
+        media_type: &MediaType,
+        files: &mut F,
+    ) -> Result<String, ContentDocumentError> {
+        let res = self
+            .get_res_by_id(idref)
+            .ok_or_else(|| ContentDocumentError::ResourceNotFound(idref.to_string()))?;
+
+        if res.media_type != *media_type {
+            return Err(ContentDocumentError::NotContentDocument(
+                idref.to_string(),
+                res.media_type.clone(),
+            ));
+        }
+
+        let data = files
+            .get(&res.href)
+            .await
+            .ok_or_else(|| ContentDocumentError::MissingFile(idref.to_string()))?;
+        Ok(decode_document(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::book::{parse_book, EpubBook};
+    use crate::file::read_from_dir;
+    use crate::package::content::ContentDocumentError;
+    use crate::package::manifest::{Manifest, Resource};
+    use crate::package::media_type::MediaType;
+
+    #[tokio::test]
+    async fn test_content_document() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.primary_package().unwrap();
+
+        let xhtml = package
+            .content_document("chapter1", &mut files)
+            .await
+            .unwrap();
+        assert!(!xhtml.body_text().is_empty());
+
+        let err = package
+            .content_document("style", &mut files)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ContentDocumentError::NotContentDocument(id, _) if id == "style"));
+
+        let err = package
+            .content_document("missing", &mut files)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ContentDocumentError::ResourceNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_svg_document() {
+        let (mut book, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        files
+            .insert(
+                "OEBPS/page1.svg",
+                br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 600 800"/>"#.to_vec(),
+            )
+            .unwrap();
+        let resources = book[0]
+            .manifest
+            .iter()
+            .cloned()
+            .chain([Resource {
+                id: "page1".to_string(),
+                href: Url::parse("epub:/OEBPS/page1.svg").unwrap(),
+                media_type: MediaType::new("image/svg+xml"),
+                fallback: None,
+                media_overlay: None,
+                properties: None,
+            }])
+            .collect();
+        book[0].manifest = Manifest::new(None, resources).unwrap();
+
+        let svg = book[0].svg_document("page1", &mut files).await.unwrap();
+        assert_eq!(svg.view_box().unwrap().height, 800.0);
+
+        let err = book[0]
+            .content_document("page1", &mut files)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ContentDocumentError::NotContentDocument(..)));
+    }
+}
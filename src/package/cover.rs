@@ -0,0 +1,64 @@
+use crate::file::Files;
+use crate::package::manifest::Resource;
+use crate::package::media_type::MediaType;
+use crate::package::Package;
+
+impl Package {
+    /// Get the cover image, the resource with the `cover-image` property in the manifest.
+    ///
+    /// It falls back to the resource the EPUB 2 `<meta name="cover" content="..."/>` points at by id.
+    pub fn cover_resource(&self) -> Option<&Resource> {
+        self.manifest
+            .iter()
+            .find(|res| res.is_cover_image())
+            .or_else(|| self.get_res_by_id(self.metadata.legacy_meta("cover")?))
+    }
+
+    /// Get the media type and the content of the cover image, see [Package::cover_resource].
+    ///
+    /// Returns `None` if the package has no cover image or its file is missing.
+    pub async fn cover_image<F: Files>(&self, files: &mut F) -> Option<(MediaType, Vec<u8>)> {
+        let res = self.cover_resource()?;
+        let data = files.get(&res.href).await?;
+        Some((res.media_type.clone(), data.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::book::{parse_book, EpubBook};
+    use crate::file::read_from_dir;
+    use crate::package::media_type::media_types::JPG;
+    use crate::package::parser::{PackageParseOptions, PackageParser};
+
+    #[tokio::test]
+    async fn test_cover_image() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.primary_package().unwrap();
+
+        assert_eq!(package.cover_resource().unwrap().id, "cover-image");
+        let (media_type, data) = package.cover_image(&mut files).await.unwrap();
+        assert_eq!(&media_type, &*JPG);
+        assert!(!data.is_empty());
+
+        let (book, mut files) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+        assert!(book[0].cover_resource().is_none());
+        assert!(book[0].cover_image(&mut files).await.is_none());
+    }
+
+    #[test]
+    fn test_legacy_cover_resource() {
+        let opf = include_str!("../../res/sample-epub2/OEBPS/content.opf").replace(
+            "<manifest>",
+            r#"<manifest><item id="cover-image" href="Images/cover.jpg" media-type="image/jpeg"/>"#,
+        );
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        let package = PackageParser::new(options).parse(&opf).unwrap();
+
+        let cover = package.cover_resource().unwrap();
+        assert_eq!(cover.href.as_str(), "epub:/OEBPS/Images/cover.jpg");
+    }
+}
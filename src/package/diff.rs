@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use crate::package::Package;
+
+/// The differences between two versions of a package, by resource id.
+///
+/// The lists keep the order of the package they are found in.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PackageDiff {
+    /// The idrefs of the spine items that are only in the new package.
+    pub added_spine_items: Vec<String>,
+
+    /// The idrefs of the spine items that are only in the old package.
+    pub removed_spine_items: Vec<String>,
+
+    /// The idrefs of the spine items of both packages that moved in the reading order.
+    ///
+    /// The reading order of the other items is kept, so moving one chapter reports only that chapter.
+    pub reordered_spine_items: Vec<String>,
+
+    /// The ids of the manifest resources that are only in the new package.
+    pub added_resources: Vec<String>,
+
+    /// The ids of the manifest resources that are only in the old package.
+    pub removed_resources: Vec<String>,
+
+    /// The ids of the manifest resources of both packages whose href, media type,
+    /// properties, fallback or media overlay changed.
+    pub changed_resources: Vec<String>,
+}
+
+impl PackageDiff {
+    /// Check if the packages have the same spine and manifest.
+    pub fn is_empty(&self) -> bool {
+        self == &PackageDiff::default()
+    }
+}
+
+impl Package {
+    /// Compare the spine and the manifest of the package, the old version, with `other`, the new one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::book::EpubBook;
+    ///
+    /// let (old, _) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+    /// let (mut new, _) = EpubBook::minimal("Title", "en", "urn:uuid:1234");
+    /// new[0].spine.clear();
+    ///
+    /// let diff = old[0].diff(&new[0]);
+    /// assert_eq!(diff.removed_spine_items, vec!["chapter1"]);
+    /// assert!(old[0].diff(&old[0]).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Package) -> PackageDiff {
+        let old_spine = self.spine.iter().map(|r| r.id.as_str()).collect::<Vec<_>>();
+        let new_spine = other
+            .spine
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect::<Vec<_>>();
+        let old_ids = old_spine.iter().copied().collect::<HashSet<_>>();
+        let new_ids = new_spine.iter().copied().collect::<HashSet<_>>();
+
+        let added_spine_items = new_spine
+            .iter()
+            .filter(|id| !old_ids.contains(*id))
+            .map(|id| id.to_string())
+            .collect();
+        let removed_spine_items = old_spine
+            .iter()
+            .filter(|id| !new_ids.contains(*id))
+            .map(|id| id.to_string())
+            .collect();
+
+        // the common items that are not part of the longest common subsequence moved
+        let old_common = old_spine
+            .iter()
+            .copied()
+            .filter(|id| new_ids.contains(id))
+            .collect::<Vec<_>>();
+        let new_common = new_spine
+            .iter()
+            .copied()
+            .filter(|id| old_ids.contains(id))
+            .collect::<Vec<_>>();
+        let kept = longest_common_subsequence(&old_common, &new_common);
+        let reordered_spine_items = new_common
+            .iter()
+            .filter(|id| !kept.contains(*id))
+            .map(|id| id.to_string())
+            .collect();
+
+        let added_resources = other
+            .manifest
+            .iter()
+            .filter(|res| self.get_res_by_id(&res.id).is_none())
+            .map(|res| res.id.clone())
+            .collect();
+        let removed_resources = self
+            .manifest
+            .iter()
+            .filter(|res| other.get_res_by_id(&res.id).is_none())
+            .map(|res| res.id.clone())
+            .collect();
+        let changed_resources = other
+            .manifest
+            .iter()
+            .filter(|res| self.get_res_by_id(&res.id).is_some_and(|old| old != *res))
+            .map(|res| res.id.clone())
+            .collect();
+
+        PackageDiff {
+            added_spine_items,
+            removed_spine_items,
+            reordered_spine_items,
+            added_resources,
+            removed_resources,
+            changed_resources,
+        }
+    }
+}
+
+/// The items of the longest common subsequence of `a` and `b`.
+fn longest_common_subsequence<'a>(a: &[&'a str], b: &[&'a str]) -> HashSet<&'a str> {
+    // lengths[i][j] is the length of the LCS of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut items = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            items.insert(a[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::package::tests::parse_opf;
+
+    const SAMPLE_OPF: &str = include_str!("../../res/sample/OEBPS/content.opf");
+
+    #[test]
+    fn test_diff() {
+        let old = parse_opf(SAMPLE_OPF);
+        assert!(old.diff(&old).is_empty());
+
+        let opf = SAMPLE_OPF
+            .replace(
+                r#"<itemref idref="chapter1"/>"#,
+                r#"<itemref idref="chapter3"/>"#,
+            )
+            .replace(r#"<itemref idref="contents"/>"#, "")
+            .replace(
+                r#"<itemref idref="chapter2"/>"#,
+                r#"<itemref idref="chapter2"/><itemref idref="contents"/>"#,
+            )
+            .replace(
+                r#"<item id="chapter1" href="Text/chapter1.xhtml""#,
+                r#"<item id="chapter3" href="Text/chapter3.xhtml""#,
+            )
+            .replace(r#"href="Styles/style.css""#, r#"href="Styles/main.css""#);
+        let new = parse_opf(&opf);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added_spine_items, vec!["chapter3"]);
+        assert_eq!(diff.removed_spine_items, vec!["chapter1"]);
+        assert_eq!(diff.reordered_spine_items, vec!["contents"]);
+        assert_eq!(diff.added_resources, vec!["chapter3"]);
+        assert_eq!(diff.removed_resources, vec!["chapter1"]);
+        assert_eq!(diff.changed_resources, vec!["style"]);
+    }
+}
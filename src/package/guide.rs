@@ -0,0 +1,100 @@
+use url::Url;
+
+use crate::package::manifest::Resource;
+use crate::package::nav::NavDocument;
+use crate::package::Package;
+
+/// A reference element of the EPUB 2 guide, which points at a structural component like the cover or the text.
+///
+/// EPUB 3 deprecates the guide in favor of the landmarks nav, see [NavDocument::landmarks].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GuideReference {
+    /// The `type` attribute, like `cover`, `toc` or `text`.
+    pub ty: String,
+
+    /// The `title` attribute.
+    pub title: Option<String>,
+
+    /// The `href` attribute, resolved against the URL of the package document.
+    pub href: Url,
+}
+
+impl Package {
+    /// Get the guide reference of the given type, compared case-insensitively.
+    pub fn guide_reference(&self, ty: &str) -> Option<&GuideReference> {
+        self.guide
+            .iter()
+            .find(|reference| reference.ty.eq_ignore_ascii_case(ty))
+    }
+
+    /// Get the content document where the reading starts, skipping the cover and the title page.
+    ///
+    /// It is the target of the `bodymatter` landmark of the nav document, or the target of
+    /// the `text` reference of the EPUB 2 guide, or the first linear item of the spine.
+    pub fn reading_start(&self, nav: Option<&NavDocument>) -> Option<&Resource> {
+        let landmark = nav
+            .and_then(|nav| nav.landmark("bodymatter"))
+            .and_then(|point| point.label.href.as_ref());
+        let guide = self
+            .guide_reference("text")
+            .map(|reference| &reference.href);
+
+        landmark
+            .and_then(|href| self.get_res_by_href(href))
+            .or_else(|| guide.and_then(|href| self.get_res_by_href(href)))
+            .or_else(|| {
+                self.spine
+                    .linear_refs()
+                    .find_map(|spine_ref| self.get_res_by_ref(spine_ref))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::package::nav::parse_nav_document;
+    use crate::package::tests::parse_opf;
+
+    const SAMPLE_OPF: &str = include_str!("../../res/sample/OEBPS/content.opf");
+    const SAMPLE_NAV: &str = include_str!("../../res/sample/OEBPS/nav.xhtml");
+
+    #[test]
+    fn test_reading_start() {
+        let sample_opf = SAMPLE_OPF.replace(
+            r#"<itemref idref="cover"/>"#,
+            r#"<itemref idref="cover" linear="no"/>"#,
+        );
+        let package = parse_opf(&sample_opf);
+        let nav =
+            parse_nav_document(SAMPLE_NAV, &Url::parse("epub:/OEBPS/nav.xhtml").unwrap()).unwrap();
+
+        // the bodymatter landmark
+        assert_eq!(package.reading_start(Some(&nav)).unwrap().id, "chapter1");
+
+        // the first linear spine item, the cover is not linear
+        assert_eq!(package.reading_start(None).unwrap().id, "contents");
+
+        let opf = sample_opf.replace(
+            "</spine>",
+            r#"</spine>
+  <guide>
+    <reference type="cover" title="Cover" href="Text/cover.xhtml"/>
+    <reference type="Text" title="Start" href="Text/chapter2.xhtml#start"/>
+  </guide>"#,
+        );
+        let package = parse_opf(&opf);
+        assert_eq!(package.guide.len(), 2);
+        assert_eq!(
+            package.guide_reference("text").unwrap().title.as_deref(),
+            Some("Start")
+        );
+        assert_eq!(package.reading_start(None).unwrap().id, "chapter2");
+        assert_eq!(package.reading_start(Some(&nav)).unwrap().id, "chapter1");
+
+        let reparsed = parse_opf(&package.to_opf_string());
+        assert_eq!(reparsed.guide, package.guide);
+    }
+}
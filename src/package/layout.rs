@@ -0,0 +1,107 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::package::prefix::prefixes::RENDITION;
+use crate::package::spine::SpineReference;
+use crate::package::Package;
+
+/// The layout of the content, the value of the `rendition:layout` property.
+///
+/// # References
+///
+/// [EPUB 3.3 SPEC layout](https://www.w3.org/TR/epub-33/#layout)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Layout {
+    /// The content is paginated by the reading system, declared as `reflowable`.
+    #[default]
+    Reflowable,
+
+    /// The content is fixed-layout, declared as `pre-paginated`.
+    PrePaginated,
+}
+
+impl FromStr for Layout {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reflowable" => Ok(Layout::Reflowable),
+            "pre-paginated" => Ok(Layout::PrePaginated),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layout::Reflowable => write!(f, "reflowable"),
+            Layout::PrePaginated => write!(f, "pre-paginated"),
+        }
+    }
+}
+
+impl Package {
+    /// The global layout of the publication.
+    ///
+    /// It is the `rendition:layout` property which does not refine any element,
+    /// and [Layout::Reflowable] if it is absent or invalid.
+    pub fn layout(&self) -> Layout {
+        self.metadata
+            .metas
+            .iter()
+            .find(|meta| meta.refines.is_none() && meta.property.is(&RENDITION, "layout"))
+            .and_then(|meta| meta.value.trim().parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Check if the publication is fixed-layout by default, see [Package::layout].
+    pub fn is_fixed_layout(&self) -> bool {
+        self.layout() == Layout::PrePaginated
+    }
+
+    /// The layout of a spine item.
+    ///
+    /// The `rendition:layout-pre-paginated` and `rendition:layout-reflowable` properties
+    /// of the spine reference override the global layout.
+    pub fn layout_of(&self, spine_ref: &SpineReference) -> Layout {
+        if spine_ref.has_property(&RENDITION, "layout-pre-paginated") {
+            Layout::PrePaginated
+        } else if spine_ref.has_property(&RENDITION, "layout-reflowable") {
+            Layout::Reflowable
+        } else {
+            self.layout()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::package::layout::Layout;
+    use crate::package::tests::parse_opf;
+
+    #[test]
+    fn test_layout() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let package = parse_opf(opf);
+        assert!(!package.is_fixed_layout());
+        assert_eq!(package.layout_of(&package.spine[1]), Layout::Reflowable);
+
+        let opf = opf
+            .replace(
+                "</metadata>",
+                r#"<meta property="rendition:layout">pre-paginated</meta></metadata>"#,
+            )
+            .replace(
+                r#"<itemref idref="contents"/>"#,
+                r#"<itemref idref="contents" properties="rendition:layout-reflowable"/>"#,
+            );
+        let package = parse_opf(&opf);
+        assert!(package.is_fixed_layout());
+        assert_eq!(package.layout_of(&package.spine[1]), Layout::Reflowable);
+        assert_eq!(package.layout_of(&package.spine[2]), Layout::PrePaginated);
+
+        let written = parse_opf(&package.to_opf_string());
+        assert_eq!(written.spine, package.spine);
+    }
+}
@@ -1,20 +1,20 @@
-use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
+use std::collections::{HashMap, HashSet};
 use std::string::ToString;
 
-use once_cell::sync::Lazy;
 use thiserror::Error;
 use url::Url;
 
 use crate::file::Files;
-use crate::package::media_type::MediaType;
+use crate::package::media_type::{media_types, MediaType};
 use crate::package::prefix::prefixes::*;
-use crate::package::property::{Properties, Property};
+use crate::package::property::Properties;
+use crate::utils::{decode_path, file_key, normalize_url, url_extension};
 
 /// A Publication Resource.
 ///
 /// identifies a publication resource by the URL in its [href] attribute.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Resource {
     /// Unique identifier for the resource.
     pub id: String,
@@ -38,13 +38,131 @@ pub struct Resource {
     /// For foreign resources, the fallback attribute is REQUIRED.
     pub fallback: Option<String>,
 
-    /// TODO: realize the media-overlay attribute
+    /// The id of the media overlay document of the resource, a SMIL resource in the manifest.
+    ///
+    /// See [Package::media_overlay_for](crate::package::Package::media_overlay_for).
     pub media_overlay: Option<String>,
 
     /// The properties attribute is a space-separated list of property values.
     pub properties: Option<Properties>,
 }
 
+impl Resource {
+    /// Check if the resource is a foreign resource, whose media type is not a core media type.
+    pub fn is_foreign(&self) -> bool {
+        !self.media_type.is_core_media_type()
+    }
+
+    /// Check if the resource is a foreign resource without the required fallback.
+    ///
+    /// It only checks the presence of the fallback attribute, use [Manifest::core_fallback]
+    /// to check whether the fallback chain ends at a core media type resource.
+    pub fn needs_fallback(&self) -> bool {
+        self.is_foreign() && self.fallback.is_none()
+    }
+
+    /// The file extension of the href, like `xhtml` for `epub:/OEBPS/Text/chapter1.xhtml`.
+    ///
+    /// Returns `None` if the last path segment has no extension.
+    pub fn extension(&self) -> Option<&str> {
+        url_extension(&self.href)
+    }
+
+    /// The path of the resource relative to `root`, like `Images/x.jpg` for
+    /// `epub:/OEBPS/Images/x.jpg`, to lay the files out on disk or in an archive.
+    ///
+    /// `root` is a directory URL ending with `/`, like `epub:/OEBPS/`, or the URL of a file
+    /// in the directory, like the package document `epub:/OEBPS/content.opf`.
+    /// The path is percent-decoded and has no fragment.
+    ///
+    /// Returns `None` if the resource is not under the directory of `root`, like a remote resource.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::package::manifest::Resource;
+    /// use eparser::package::media_type::MediaType;
+    /// use url::Url;
+    ///
+    /// let resource = Resource {
+    ///     id: "cover".to_string(),
+    ///     href: Url::parse("epub:/OEBPS/Images/my%20cover.jpg").unwrap(),
+    ///     media_type: MediaType::new("image/jpeg"),
+    ///     fallback: None,
+    ///     media_overlay: None,
+    ///     properties: None,
+    /// };
+    ///
+    /// let opf = Url::parse("epub:/OEBPS/content.opf").unwrap();
+    /// assert_eq!(resource.relative_path(&opf).unwrap(), "Images/my cover.jpg");
+    ///
+    /// let root = Url::parse("epub:/").unwrap();
+    /// assert_eq!(resource.relative_path(&root).unwrap(), "OEBPS/Images/my cover.jpg");
+    ///
+    /// let text = Url::parse("epub:/OEBPS/Text/").unwrap();
+    /// assert!(resource.relative_path(&text).is_none());
+    /// ```
+    pub fn relative_path(&self, root: &Url) -> Option<String> {
+        let relative = file_key(root).make_relative(&file_key(&self.href))?;
+        if relative.is_empty()
+            || relative.ends_with('/')
+            || relative.starts_with("../")
+            || relative.contains(['?', '#'])
+        {
+            return None;
+        }
+        Some(decode_path(&relative))
+    }
+
+    /// Check if the resource has the property `reference` of the default `opf` vocabulary,
+    /// like `nav`, `cover-image` or `scripted`.
+    ///
+    /// # References
+    ///
+    /// [EPUB 3.3 SPEC item-properties](https://www.w3.org/TR/epub-33/#app-item-properties-vocab)
+    pub fn has_property(&self, reference: &str) -> bool {
+        self.properties
+            .as_ref()
+            .is_some_and(|properties| properties.iter().any(|p| p.is(&OPF, reference)))
+    }
+
+    /// Check if the resource is the navigation document.
+    pub fn is_nav(&self) -> bool {
+        self.has_property("nav")
+    }
+
+    /// Check if the resource is the cover image.
+    pub fn is_cover_image(&self) -> bool {
+        self.has_property("cover-image")
+    }
+
+    /// Check if the resource contains scripts or forms.
+    pub fn has_scripted(&self) -> bool {
+        self.has_property("scripted")
+    }
+
+    /// Check if the resource contains MathML markup.
+    pub fn has_mathml(&self) -> bool {
+        self.has_property("mathml")
+    }
+
+    /// Check if the resource contains SVG markup.
+    pub fn has_svg(&self) -> bool {
+        self.has_property("svg")
+    }
+
+    /// Check if the resource references remote resources, like fonts or videos
+    /// outside the container, so rendering it makes outbound requests.
+    pub fn uses_remote_resources(&self) -> bool {
+        self.has_property("remote-resources")
+    }
+}
+
+/// Get the content of the resources of a [Manifest] from the files of the publication.
+///
+/// It is implemented for every [Files], the lookups of resources by id and href
+/// are done by [Manifest::get_resource_by_id] and [Manifest::get_resource_by_href].
+#[allow(async_fn_in_trait)]
 pub trait ResourceMap {
     /// Get a resource content by [Resource].
     async fn get_by_res(&mut self, res: &Resource) -> Option<&Vec<u8>>;
@@ -73,8 +191,13 @@ pub enum ManifestCheckError {
 
 /// Manifest provides an exhaustive list of publication resources used in the rendering of the content.
 ///
-/// Do not modify it after it has been created.
+/// It can not be modified after it has been created, so the lookups by id and href stay valid.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "SerdeManifest", try_from = "SerdeManifest")
+)]
 pub struct Manifest {
     /// The unique identifier of the manifest element.
     pub id: Option<String>,
@@ -89,14 +212,37 @@ pub struct Manifest {
     href_to_resource: HashMap<Url, usize>,
 
     /// The nav resource
-    nav_resource: usize,
+    ///
+    /// It is always present in EPUB 3 manifests.
+    nav_resource: Option<usize>,
 }
 
-static NAV: Lazy<Property> = Lazy::new(|| Property::from_prefix(&OPF, "nav".to_string()));
+/// Two manifests are equal if their ids and resources are equal, the lookup maps are derived from them.
+impl PartialEq for Manifest {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.resources == other.resources
+    }
+}
+
+impl Eq for Manifest {}
 
 impl Manifest {
     /// Create a new Manifest
     pub fn new(id: Option<&str>, resources: Vec<Resource>) -> Result<Self, ManifestCheckError> {
+        let manifest = Self::new_epub2(id, resources)?;
+        if manifest.nav_resource.is_none() {
+            return Err(ManifestCheckError::NavResourceNotFound);
+        }
+        Ok(manifest)
+    }
+
+    /// Create a new Manifest of an EPUB 2 publication.
+    ///
+    /// Unlike [Manifest::new], the nav resource is not required.
+    pub fn new_epub2(
+        id: Option<&str>,
+        resources: Vec<Resource>,
+    ) -> Result<Self, ManifestCheckError> {
         let mut id_to_resource = HashMap::new();
         let mut href_to_resource = HashMap::new();
 
@@ -106,7 +252,7 @@ impl Manifest {
                 return Err(ManifestCheckError::DeduplicatedId(resource.id.clone()));
             }
 
-            let res = href_to_resource.insert(resource.href.clone(), index);
+            let res = href_to_resource.insert(normalize_url(&resource.href), index);
             if res.is_some() {
                 return Err(ManifestCheckError::DeduplicatedHref(resource.href.clone()));
             }
@@ -121,17 +267,8 @@ impl Manifest {
             }
         }
 
-        // check nav
-        let nav_resource = resources
-            .iter()
-            .position(|resource| {
-                resource
-                    .properties
-                    .as_ref()
-                    .map(|properties| properties.contains(&NAV))
-                    .unwrap_or(false)
-            })
-            .ok_or(ManifestCheckError::NavResourceNotFound)?;
+        // find nav
+        let nav_resource = resources.iter().position(Resource::is_nav);
 
         Ok(Manifest {
             id: id.map(|id| id.to_string()),
@@ -142,6 +279,38 @@ impl Manifest {
         })
     }
 
+    /// Iterate over the resources in document order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Resource> {
+        self.resources.iter()
+    }
+
+    /// Iterate over the resources in document order with their positions in the manifest.
+    ///
+    /// The positions are stable and can be passed to [Manifest::get].
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &Resource)> {
+        self.resources.iter().enumerate()
+    }
+
+    /// The position of the resource with the `id` in the manifest.
+    pub fn index_of_id(&self, id: &str) -> Option<usize> {
+        self.id_to_resource.get(id).copied()
+    }
+
+    /// The number of resources.
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// Check if the manifest has no resource.
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Get a resource by its position in the manifest.
+    pub fn get(&self, index: usize) -> Option<&Resource> {
+        self.resources.get(index)
+    }
+
     /// Get a resource by id
     pub fn get_resource_by_id(&self, id: &str) -> Option<&Resource> {
         self.id_to_resource
@@ -150,27 +319,278 @@ impl Manifest {
     }
 
     /// Get a resource by href
+    ///
+    /// The percent-encoding of the hrefs is normalized, so `a%2dB.xhtml` finds `a-B.xhtml`.
     pub fn get_resource_by_href(&self, href: &Url) -> Option<&Resource> {
         self.href_to_resource
-            .get(href)
+            .get(&normalize_url(href))
             .map(|index| &self.resources[*index])
     }
 
+    /// Get a resource by href, comparing the paths case-insensitively if there is no exact match.
+    ///
+    /// Archives made on case-insensitive file systems may refer to `Text/Chapter.xhtml`
+    /// as `text/chapter.xhtml`.
+    pub fn get_resource_by_href_ci(&self, href: &Url) -> Option<&Resource> {
+        self.get_resource_by_href(href).or_else(|| {
+            let href = normalize_url(href);
+            self.resources.iter().find(|res| {
+                let res_href = normalize_url(&res.href);
+                res_href.scheme() == href.scheme()
+                    && res_href.host() == href.host()
+                    && res_href.path().eq_ignore_ascii_case(href.path())
+            })
+        })
+    }
+
     /// Get the nav resource
     pub fn nav_resource(&self) -> Option<&Resource> {
-        self.resources.get(self.nav_resource)
+        self.resources.get(self.nav_resource?)
+    }
+
+    /// Get the first NCX resource, the one whose media type is `application/x-dtbncx+xml`.
+    ///
+    /// EPUB 3 publications may include an NCX for EPUB 2 reading systems
+    /// without referencing it from the `toc` attribute of the spine.
+    pub fn ncx_resource(&self) -> Option<&Resource> {
+        self.resources
+            .iter()
+            .find(|resource| resource.media_type == *media_types::NCX)
+    }
+
+    /// Follow the fallback chain of a resource until a core media type resource is found.
+    ///
+    /// Returns the resource itself if it is not foreign, and `None` if the chain is broken,
+    /// circular or has no core media type resource.
+    pub fn core_fallback<'a>(&'a self, resource: &'a Resource) -> Option<&'a Resource> {
+        let mut current = resource;
+        let mut visited = HashSet::new();
+        while current.is_foreign() {
+            if !visited.insert(&current.id) {
+                return None;
+            }
+            current = self.get_resource_by_id(current.fallback.as_ref()?)?;
+        }
+        Some(current)
+    }
+
+    /// Check if every resource in the manifest is a core media type resource.
+    ///
+    /// If it is `true`, no resource needs a fallback to be rendered.
+    pub fn all_core_media_types(&self) -> bool {
+        self.resources
+            .iter()
+            .all(|resource| resource.media_type.is_core_media_type())
     }
 }
 
-impl Deref for Manifest {
-    type Target = Vec<Resource>;
-    fn deref(&self) -> &Vec<Resource> {
-        &self.resources
+impl<'a> IntoIterator for &'a Manifest {
+    type Item = &'a Resource;
+    type IntoIter = std::slice::Iter<'a, Resource>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
-impl DerefMut for Manifest {
-    fn deref_mut(&mut self) -> &mut Vec<Resource> {
-        &mut self.resources
+/// The (de)serialized form of a [Manifest], the lookup maps are rebuilt on deserialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeManifest {
+    id: Option<String>,
+    resources: Vec<Resource>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Manifest> for SerdeManifest {
+    fn from(manifest: Manifest) -> Self {
+        SerdeManifest {
+            id: manifest.id,
+            resources: manifest.resources,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerdeManifest> for Manifest {
+    type Error = ManifestCheckError;
+
+    fn try_from(manifest: SerdeManifest) -> Result<Self, Self::Error> {
+        Manifest::new_epub2(manifest.id.as_deref(), manifest.resources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::package::manifest::{Manifest, Resource};
+    use crate::package::media_type::MediaType;
+    use crate::package::property::{Properties, Property};
+
+    fn resource(id: &str, media_type: &str, fallback: Option<&str>) -> Resource {
+        Resource {
+            id: id.to_string(),
+            href: Url::parse(&format!("epub:/{}", id)).unwrap(),
+            media_type: MediaType::new(media_type),
+            fallback: fallback.map(|f| f.to_string()),
+            media_overlay: None,
+            properties: None,
+        }
+    }
+
+    #[test]
+    fn test_foreign_resources() {
+        let manifest = Manifest::new_epub2(
+            None,
+            vec![
+                resource("chapter", "application/xhtml+xml", None),
+                resource("pdf", "application/pdf", Some("chapter")),
+                resource("doc", "application/msword", None),
+                resource("a", "application/x-a", Some("b")),
+                resource("b", "application/x-b", Some("a")),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(manifest.len(), 5);
+        assert_eq!(manifest.get(1).unwrap().id, "pdf");
+        assert_eq!((&manifest).into_iter().count(), manifest.len());
+        assert_eq!(manifest.index_of_id("doc"), Some(2));
+        assert_eq!(manifest.index_of_id("missing"), None);
+        for (index, resource) in manifest.iter_indexed() {
+            assert_eq!(manifest.index_of_id(&resource.id), Some(index));
+        }
+
+        let chapter = manifest.get_resource_by_id("chapter").unwrap();
+        assert!(!chapter.is_foreign());
+        assert!(!chapter.needs_fallback());
+        assert_eq!(manifest.core_fallback(chapter).unwrap().id, "chapter");
+
+        let pdf = manifest.get_resource_by_id("pdf").unwrap();
+        assert!(pdf.is_foreign());
+        assert!(!pdf.needs_fallback());
+        assert_eq!(manifest.core_fallback(pdf).unwrap().id, "chapter");
+
+        let doc = manifest.get_resource_by_id("doc").unwrap();
+        assert!(doc.needs_fallback());
+        assert!(manifest.core_fallback(doc).is_none());
+
+        let a = manifest.get_resource_by_id("a").unwrap();
+        assert!(!a.needs_fallback());
+        assert!(manifest.core_fallback(a).is_none());
+    }
+
+    #[test]
+    fn test_extension() {
+        for (href, ext) in [
+            ("Text/chapter1.xhtml", Some("xhtml")),
+            ("Images/cover.JPG", Some("JPG")),
+            ("archive.tar.gz", Some("gz")),
+            ("Text/.hidden", None),
+            ("mimetype", None),
+        ] {
+            let res = resource(href, "application/octet-stream", None);
+            assert_eq!(res.extension(), ext, "{}", href);
+        }
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        for (href, path) in [
+            ("OEBPS/Text/chapter1.xhtml#s1", Some("Text/chapter1.xhtml")),
+            (
+                "OEBPS/Text/%E7%AC%AC%E4%B8%80%E7%AB%A0.xhtml",
+                Some("Text/第一章.xhtml"),
+            ),
+            ("OEBPS/./Images/../style.css", Some("style.css")),
+            // not a percent-encoded byte
+            ("OEBPS/Text/%+1.xhtml", Some("Text/%+1.xhtml")),
+            ("META-INF/container.xml", None),
+            ("OEBPS/", None),
+        ] {
+            let res = resource(href, "application/octet-stream", None);
+            assert_eq!(res.relative_path(&root).as_deref(), path, "{}", href);
+        }
+
+        let mut res = resource("audio", "audio/mpeg", None);
+        res.href = Url::parse("https://example.com/OEBPS/audio.mp3").unwrap();
+        assert!(res.relative_path(&root).is_none());
+    }
+
+    #[test]
+    fn test_ncx_resource() {
+        let manifest = Manifest::new_epub2(
+            None,
+            vec![
+                resource("chapter", "application/xhtml+xml", None),
+                resource("toc", "application/x-dtbncx+xml", None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(manifest.ncx_resource().unwrap().id, "toc");
+
+        let manifest = Manifest::new_epub2(
+            None,
+            vec![resource("chapter", "application/xhtml+xml", None)],
+        )
+        .unwrap();
+        assert!(manifest.ncx_resource().is_none());
+    }
+
+    #[test]
+    fn test_get_resource_by_href_percent_encoding() {
+        let manifest = Manifest::new_epub2(
+            None,
+            vec![resource("chapter-%e4%b8%80", "application/xhtml+xml", None)],
+        )
+        .unwrap();
+
+        for href in ["epub:/chapter-一", "epub:/chapter%2D%E4%B8%80"] {
+            let href = Url::parse(href).unwrap();
+            assert!(manifest.get_resource_by_href(&href).is_some());
+        }
+    }
+
+    #[test]
+    fn test_get_resource_by_href_ci() {
+        let manifest = Manifest::new_epub2(
+            None,
+            vec![resource(
+                "Text/Chapter.xhtml",
+                "application/xhtml+xml",
+                None,
+            )],
+        )
+        .unwrap();
+
+        let href = Url::parse("epub:/text/chapter.xhtml").unwrap();
+        assert!(manifest.get_resource_by_href(&href).is_none());
+        assert_eq!(
+            manifest.get_resource_by_href_ci(&href).unwrap().id,
+            "Text/Chapter.xhtml"
+        );
+
+        let href = Url::parse("epub:/text/other.xhtml").unwrap();
+        assert!(manifest.get_resource_by_href_ci(&href).is_none());
+    }
+
+    #[test]
+    fn test_properties() {
+        let mut res = resource("chapter", "application/xhtml+xml", None);
+        assert!(!res.has_property("nav"));
+
+        res.properties = Some(Properties::new(vec![
+            Property::opf("scripted"),
+            Property::opf("mathml"),
+            Property::rendition("svg"),
+        ]));
+        assert!(res.has_scripted());
+        assert!(res.has_mathml());
+        assert!(!res.has_svg());
+        assert!(!res.uses_remote_resources());
+        assert!(!res.is_nav());
+        assert!(!res.is_cover_image());
     }
 }
@@ -1,4 +1,5 @@
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
@@ -15,20 +16,43 @@ use std::str::FromStr;
 ///
 /// # References
 /// [EPUB 3.3 SPEC](https://www.w3.org/TR/epub-33/#sec-core-media-types)
-#[derive(Debug, PartialEq, Clone)]
-pub struct MediaType(String);
+///
+/// Two media types are equal if their values are equal, the original strings are not compared.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaType {
+    /// The value of the media type, normalized or not.
+    value: String,
+
+    /// The original string of the media type.
+    raw: String,
+}
+
+impl PartialEq for MediaType {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for MediaType {}
+
+impl Hash for MediaType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
 
 impl Deref for MediaType {
     type Target = str;
 
     fn deref(&self) -> &str {
-        &self.0
+        &self.value
     }
 }
 
 impl DerefMut for MediaType {
     fn deref_mut(&mut self) -> &mut str {
-        &mut self.0
+        &mut self.value
     }
 }
 
@@ -36,20 +60,76 @@ impl FromStr for MediaType {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(MediaType(s.to_string()))
+        Ok(MediaType::new(s))
     }
 }
 
 impl Display for MediaType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.value)
     }
 }
 
 impl MediaType {
     /// Create a new media type
     pub fn new(media_type: &str) -> Self {
-        MediaType(media_type.to_string())
+        MediaType {
+            value: media_type.to_string(),
+            raw: media_type.to_string(),
+        }
+    }
+
+    /// Create a new normalized media type.
+    ///
+    /// The type and subtype are lowercased, and the white spaces around the type,
+    /// subtype and parameters are trimmed, so `" Image/JPEG "` is equal to `image/jpeg`.
+    /// The original string is still available by [MediaType::raw].
+    pub fn normalized(media_type: &str) -> Self {
+        let mut parts = media_type.split(';');
+        let essence = parts.next().unwrap_or_default().trim().to_lowercase();
+        let value = parts
+            .map(|param| param.trim())
+            .filter(|param| !param.is_empty())
+            .fold(essence, |value, param| format!("{}; {}", value, param));
+
+        MediaType {
+            value,
+            raw: media_type.to_string(),
+        }
+    }
+
+    /// Guess the core media type of a file extension, like `jpg` or `XHTML`.
+    ///
+    /// The extension is compared case-insensitively and may start with a `.`.
+    /// Returns `None` if it is not the extension of a core media type.
+    pub fn from_extension(ext: &str) -> Option<MediaType> {
+        let ext = ext.strip_prefix('.').unwrap_or(ext).to_ascii_lowercase();
+        let media_type = match ext.as_str() {
+            "gif" => &media_types::GIF,
+            "jpg" | "jpeg" | "jpe" => &media_types::JPG,
+            "png" => &media_types::PNG,
+            "svg" => &media_types::SVG,
+            "webp" => &media_types::WEBP,
+            "mp3" => &media_types::MP3,
+            "mp4" | "m4a" => &media_types::MP4,
+            "ogg" | "opus" => &media_types::OGG,
+            "css" => &media_types::CSS,
+            "ttf" => &media_types::TTF,
+            "otf" => &media_types::OTF,
+            "woff" => &media_types::WOFF,
+            "woff2" => &media_types::WOFF2,
+            "xhtml" | "html" | "htm" => &media_types::XHTML,
+            "js" | "mjs" => &media_types::TEXT_JAVASCRIPT,
+            "ncx" => &media_types::NCX,
+            "smil" => &media_types::SMIL,
+            _ => return None,
+        };
+        Some(MediaType::clone(media_type))
+    }
+
+    /// The original string of the media type, as it was declared.
+    pub fn raw(&self) -> &str {
+        &self.raw
     }
 
     /// Check if the media type is a core media type
@@ -68,43 +148,38 @@ pub mod media_types {
 
     // Core media types
     // images
-    pub static GIF: Lazy<MediaType> = Lazy::new(|| MediaType("image/gif".to_string()));
-    pub static JPG: Lazy<MediaType> = Lazy::new(|| MediaType("image/jpeg".to_string()));
-    pub static PNG: Lazy<MediaType> = Lazy::new(|| MediaType("image/png".to_string()));
-    pub static SVG: Lazy<MediaType> = Lazy::new(|| MediaType("image/svg+xml".to_string()));
-    pub static WEBP: Lazy<MediaType> = Lazy::new(|| MediaType("image/webp".to_string()));
+    pub static GIF: Lazy<MediaType> = Lazy::new(|| MediaType::new("image/gif"));
+    pub static JPG: Lazy<MediaType> = Lazy::new(|| MediaType::new("image/jpeg"));
+    pub static PNG: Lazy<MediaType> = Lazy::new(|| MediaType::new("image/png"));
+    pub static SVG: Lazy<MediaType> = Lazy::new(|| MediaType::new("image/svg+xml"));
+    pub static WEBP: Lazy<MediaType> = Lazy::new(|| MediaType::new("image/webp"));
 
     // audio
-    pub static MP3: Lazy<MediaType> = Lazy::new(|| MediaType("audio/mpeg".to_string()));
-    pub static MP4: Lazy<MediaType> = Lazy::new(|| MediaType("video/mp4".to_string()));
-    pub static OGG: Lazy<MediaType> = Lazy::new(|| MediaType("audio/ogg; codecs=opus".to_string()));
+    pub static MP3: Lazy<MediaType> = Lazy::new(|| MediaType::new("audio/mpeg"));
+    pub static MP4: Lazy<MediaType> = Lazy::new(|| MediaType::new("video/mp4"));
+    pub static OGG: Lazy<MediaType> = Lazy::new(|| MediaType::new("audio/ogg; codecs=opus"));
 
     // style
-    pub static CSS: Lazy<MediaType> = Lazy::new(|| MediaType("text/css".to_string()));
+    pub static CSS: Lazy<MediaType> = Lazy::new(|| MediaType::new("text/css"));
 
     // fonts
-    pub static TTF: Lazy<MediaType> = Lazy::new(|| MediaType("font/ttf".to_string()));
-    pub static OTF: Lazy<MediaType> = Lazy::new(|| MediaType("font/otf".to_string()));
-    pub static WOFF: Lazy<MediaType> = Lazy::new(|| MediaType("font/woff".to_string()));
-    pub static WOFF2: Lazy<MediaType> = Lazy::new(|| MediaType("font/woff2".to_string()));
-    pub static SFNT: Lazy<MediaType> = Lazy::new(|| MediaType("application/font-sfnt".to_string()));
+    pub static TTF: Lazy<MediaType> = Lazy::new(|| MediaType::new("font/ttf"));
+    pub static OTF: Lazy<MediaType> = Lazy::new(|| MediaType::new("font/otf"));
+    pub static WOFF: Lazy<MediaType> = Lazy::new(|| MediaType::new("font/woff"));
+    pub static WOFF2: Lazy<MediaType> = Lazy::new(|| MediaType::new("font/woff2"));
+    pub static SFNT: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/font-sfnt"));
     pub static VND_MS: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/vnd.ms-opentype".to_string()));
-    pub static APP_WOFF: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/font-woff".to_string()));
+        Lazy::new(|| MediaType::new("application/vnd.ms-opentype"));
+    pub static APP_WOFF: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/font-woff"));
 
     // other
-    pub static XHTML: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/xhtml+xml".to_string()));
-    pub static TEXT_JAVASCRIPT: Lazy<MediaType> =
-        Lazy::new(|| MediaType("text/javascript".to_string()));
+    pub static XHTML: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/xhtml+xml"));
+    pub static TEXT_JAVASCRIPT: Lazy<MediaType> = Lazy::new(|| MediaType::new("text/javascript"));
     pub static APP_JAVASCRIPT: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/javascript".to_string()));
-    pub static ECMASCRIPT: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/ecmascript".to_string()));
-    pub static NCX: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/x-dtbncx+xml".to_string()));
-    pub static SMIL: Lazy<MediaType> = Lazy::new(|| MediaType("application/smil+xml".to_string()));
+        Lazy::new(|| MediaType::new("application/javascript"));
+    pub static ECMASCRIPT: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/ecmascript"));
+    pub static NCX: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/x-dtbncx+xml"));
+    pub static SMIL: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/smil+xml"));
 
     // all media types
     pub static ALL_CORE_MEDIA_TYPES: [&Lazy<MediaType>; 22] = [
@@ -133,11 +208,11 @@ pub mod media_types {
     ];
 
     // epub media type
-    pub static EPUB: Lazy<MediaType> = Lazy::new(|| MediaType("application/epub+zip".to_string()));
+    pub static EPUB: Lazy<MediaType> = Lazy::new(|| MediaType::new("application/epub+zip"));
 
     // oebps media type
     pub static OEBPS: Lazy<MediaType> =
-        Lazy::new(|| MediaType("application/oebps-package+xml".to_string()));
+        Lazy::new(|| MediaType::new("application/oebps-package+xml"));
 }
 
 #[cfg(test)]
@@ -152,4 +227,30 @@ mod tests {
                 assert!(media_type.is_core_media_type());
             });
     }
+
+    #[test]
+    fn test_normalized() {
+        let media_type = MediaType::normalized(" Image/JPEG ");
+        assert_eq!(&media_type, media_types::JPG.deref());
+        assert_eq!(media_type.raw(), " Image/JPEG ");
+        assert!(media_type.is_core_media_type());
+
+        let media_type = MediaType::normalized("Audio/OGG ;codecs=opus");
+        assert_eq!(&media_type, media_types::OGG.deref());
+
+        assert_ne!(&MediaType::new("Image/JPEG"), media_types::JPG.deref());
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            &MediaType::from_extension("JPEG").unwrap(),
+            media_types::JPG.deref()
+        );
+        assert_eq!(
+            &MediaType::from_extension(".xhtml").unwrap(),
+            media_types::XHTML.deref()
+        );
+        assert!(MediaType::from_extension("pdf").is_none());
+    }
 }
@@ -1,15 +1,19 @@
 use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use once_cell::sync::Lazy;
 use thiserror::Error;
 use url::{ParseError, Url};
 
 use crate::package::media_type::MediaType;
 use crate::package::prefix::prefixes::*;
+use crate::package::prefix::Prefix;
 use crate::package::property::{Properties, Property, WithNamespace};
+use crate::package::{Direction, Package};
+use crate::utils::decode_path;
 
 /// The basic metadata element of an EPUB.
 ///
@@ -17,6 +21,7 @@ use crate::package::property::{Properties, Property, WithNamespace};
 ///
 /// [EPUB 3.3 SPEC metadata-elem](https://www.w3.org/TR/epub-33/#sec-metadata-elem)
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetadataElement {
     /// The ID of the meta element.
     pub id: Option<String>,
@@ -25,7 +30,7 @@ pub struct MetadataElement {
     pub lang: Option<String>,
 
     /// The `dir` attribute of the meta element.
-    pub dir: Option<String>,
+    pub dir: Option<Direction>,
 
     /// The property of the meta element.
     ///
@@ -33,6 +38,57 @@ pub struct MetadataElement {
     ///
     /// `dc:title`, `dc:creator`, `dc:language`
     pub tag_name: WithNamespace,
+
+    /// The value of the element.
+    pub value: String,
+
+    /// The attributes in other namespaces, like `opf:event`, `opf:role` and `opf:file-as`
+    /// of EPUB 2 publications.
+    #[cfg_attr(feature = "serde", serde(default, with = "serde_attrs"))]
+    pub attrs: BTreeMap<WithNamespace, String>,
+}
+
+impl MetadataElement {
+    /// Get the value of the attribute `name` in the namespace of `prefix`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use eparser::package::metadata::MetadataElement;
+    /// use eparser::package::prefix::prefixes::{DC, OPF};
+    /// use eparser::package::property::WithNamespace;
+    ///
+    /// let elem = MetadataElement {
+    ///     id: None,
+    ///     lang: None,
+    ///     dir: None,
+    ///     tag_name: WithNamespace::from_prefix(&DC, "date".to_string()),
+    ///     value: "1865-11-26".to_string(),
+    ///     attrs: BTreeMap::from([(
+    ///         WithNamespace::from_prefix(&OPF, "event".to_string()),
+    ///         "publication".to_string(),
+    ///     )]),
+    /// };
+    /// assert_eq!(elem.attr(&OPF, "event"), Some("publication"));
+    /// ```
+    pub fn attr(&self, prefix: &Prefix, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.is(prefix, name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The language of the element, like the one a hyphenation engine needs.
+    ///
+    /// An element without the `xml:lang` attribute inherits the one of the package,
+    /// and the [primary language](Package::primary_language) of the publication if it is absent too.
+    pub fn effective_lang<'a>(&'a self, package: &'a Package) -> Option<&'a str> {
+        self.lang
+            .as_deref()
+            .or(package.lang.as_deref())
+            .or_else(|| package.primary_language())
+    }
 }
 
 /// Establishes an association between the current expression and
@@ -44,6 +100,7 @@ pub struct MetadataElement {
 /// The path-relative-scheme-less-URL string will be parsed as a URL with the
 /// "refines" scheme, and the URL-fragment string will be parsed as a URL fragment.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Refines(Url);
 
 #[derive(Debug, Error)]
@@ -79,13 +136,40 @@ impl Refines {
         Ok(Refines(Url::parse(url)?))
     }
 
+    /// Resolve the value of a `refines` attribute against the URL of the package document.
+    ///
+    /// A pure fragment like `#title` resolves to the package document itself,
+    /// so it is equal to `content.opf#title` when `base_url` is `.../content.opf`.
     pub fn from_relative_url(relative: &str, base_url: &Url) -> Result<Self, RefinesError> {
-        Ok(Refines(base_url.join(relative)?))
+        Ok(Refines(base_url.join(relative.trim())?))
+    }
+
+    /// The resolved URL of the refined element or resource.
+    pub fn url(&self) -> &Url {
+        &self.0
+    }
+
+    /// The id of the refined element, the percent-decoded fragment of the URL.
+    ///
+    /// Returns `None` if the target is a whole resource, like a `refines` pointing at a content document.
+    pub fn fragment(&self) -> Option<String> {
+        self.0
+            .fragment()
+            .filter(|fragment| !fragment.is_empty())
+            .map(decode_path)
+    }
+
+    /// The URL of the document containing the refined element, the URL without the fragment.
+    pub fn document_url(&self) -> Url {
+        let mut url = self.0.clone();
+        url.set_fragment(None);
+        url
     }
 }
 
 /// Meta element
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Meta {
     /// The unique identifier of the \<meta\> element.
     pub id: Option<String>,
@@ -94,7 +178,7 @@ pub struct Meta {
     pub lang: Option<String>,
 
     /// The `dir` attribute of the \<meta\> element.
-    pub dir: Option<String>,
+    pub dir: Option<Direction>,
 
     /// The property attribute of the meta element.
     pub property: Property,
@@ -111,8 +195,43 @@ pub struct Meta {
     pub value: String,
 }
 
+impl Meta {
+    /// Check if the meta element has the property `reference` in the namespace of `prefix`,
+    /// and refines the element with the `id` in the package document at `base_url`.
+    ///
+    /// Only the ids are compared if `base_url` is unknown.
+    fn is_refinement(
+        &self,
+        base_url: Option<&Url>,
+        id: &str,
+        prefix: &Prefix,
+        reference: &str,
+    ) -> bool {
+        self.property.is(prefix, reference)
+            && self.refines.as_ref().is_some_and(|refines| {
+                refines.fragment().as_deref() == Some(id)
+                    && base_url.is_none_or(|base_url| refines.document_url() == *base_url)
+            })
+    }
+}
+
+/// An EPUB 2 meta element, like `<meta name="cover" content="cover-image"/>`.
+///
+/// EPUB 3 publications keep them for backward compatibility, and tools like calibre
+/// store their own metadata in them, e.g. `calibre:series`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyMeta {
+    /// The `name` attribute of the meta element.
+    pub name: String,
+
+    /// The `content` attribute of the meta element, empty if it is absent.
+    pub content: String,
+}
+
 /// The link element associates resources with an EPUB publication, such as metadata records.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Link {
     /// The unique identifier of the link element.
     pub id: Option<String>,
@@ -143,6 +262,262 @@ pub struct Link {
     pub value: String,
 }
 
+/// The accessibility metadata of an EPUB Publication.
+///
+/// # References
+///
+/// [EPUB Accessibility 1.1](https://www.w3.org/TR/epub-a11y-11/#sec-discovery)
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AccessibilitySummary {
+    /// The `schema:accessMode` values, e.g. `textual`, `visual`.
+    pub access_modes: Vec<String>,
+
+    /// The `schema:accessModeSufficient` values, e.g. `textual,visual`.
+    pub access_modes_sufficient: Vec<String>,
+
+    /// The `schema:accessibilityFeature` values, e.g. `alternativeText`, `tableOfContents`.
+    pub features: Vec<String>,
+
+    /// The `schema:accessibilityHazard` values, e.g. `none`, `flashing`.
+    pub hazards: Vec<String>,
+
+    /// The `schema:accessibilitySummary` value.
+    pub summary: Option<String>,
+
+    /// The `dcterms:conformsTo` values, identifying the accessibility specifications the publication conforms to.
+    pub conforms_to: Vec<String>,
+
+    /// The `a11y:certifiedBy` value, the party that certified the content.
+    pub certified_by: Option<String>,
+
+    /// The `a11y:certifierCredential` value, the credential of the certifier.
+    pub certifier_credential: Option<String>,
+
+    /// The `a11y:certifierReport` link, the report of the evaluation.
+    pub certifier_report: Option<Url>,
+}
+
+/// A `dc:subject` element with the code of the subject in a subject authority.
+///
+/// # References
+///
+/// [EPUB 3.3 SPEC sec-opf-dcsubject](https://www.w3.org/TR/epub-33/#sec-opf-dcsubject)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Subject {
+    /// The text of the subject, e.g. `FICTION / Romance / Historical`.
+    pub value: String,
+
+    /// The `authority` refinement, the subject scheme like `BISAC` or a URL.
+    pub authority: Option<String>,
+
+    /// The `term` refinement, the code of the subject in the scheme like `FIC027050`.
+    pub term: Option<String>,
+}
+
+/// A collection the publication belongs to, like a series.
+///
+/// # References
+///
+/// [EPUB 3.3 SPEC belongs-to-collection](https://www.w3.org/TR/epub-33/#belongs-to-collection)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Collection {
+    /// The name of the collection, e.g. `魔女之旅`.
+    pub name: String,
+
+    /// The `collection-type` refinement, `series` or `set`.
+    pub collection_type: Option<String>,
+
+    /// The `group-position` refinement, the position of the publication in the collection.
+    pub group_position: Option<f32>,
+}
+
+/// The scheme of a dc:identifier, like ISBN or DOI.
+///
+/// # References
+///
+/// [EPUB 3.3 SPEC identifier-type](https://www.w3.org/TR/epub-33/#sec-identifier-type)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdentifierType {
+    /// International Standard Book Number, ISBN-10 or ISBN-13.
+    Isbn,
+
+    /// International Standard Serial Number.
+    Issn,
+
+    /// Digital Object Identifier.
+    Doi,
+
+    /// Universally Unique Identifier.
+    Uuid,
+
+    /// Any other scheme, e.g. an ONIX code or the namespace of a URN.
+    Other(String),
+}
+
+impl IdentifierType {
+    /// The identifier type of an ONIX code list 5 code, e.g. `15` for ISBN-13.
+    fn from_onix_code(code: &str) -> Self {
+        match code {
+            "02" | "15" => IdentifierType::Isbn,
+            "06" => IdentifierType::Doi,
+            _ => IdentifierType::Other(code.to_string()),
+        }
+    }
+
+    /// The identifier type of a scheme name, like `ISBN` of the `opf:scheme` attribute.
+    fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "isbn" => IdentifierType::Isbn,
+            "issn" => IdentifierType::Issn,
+            "doi" => IdentifierType::Doi,
+            "uuid" => IdentifierType::Uuid,
+            _ => IdentifierType::Other(name.to_string()),
+        }
+    }
+
+    /// The identifier type of a URN value, like `urn:isbn:9787...`.
+    fn from_urn(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let prefix = value.get(..4)?;
+        if !prefix.eq_ignore_ascii_case("urn:") {
+            return None;
+        }
+        let (nid, _) = value[4..].split_once(':')?;
+        Some(IdentifierType::from_name(nid))
+    }
+}
+
+/// Parse a date and time value, like the one of the `dcterms:modified` property.
+///
+/// The value SHOULD be an RFC 3339 date and time, like `2024-08-13T04:09:43Z`,
+/// but these non-conformant forms found in real books are accepted too:
+/// - date and time with a space separator: `2024-08-13 04:09:43`, `2024-08-13 04:09:43+08:00`
+/// - date and time without a time zone: `2024-08-13T04:09:43`, which is taken as UTC
+/// - date only: `2011-01-01`, which is taken as midnight UTC
+/// - year and month: `2011-08`, which is taken as the first day of the month
+/// - year only: `2011`, which is taken as January 1
+///
+/// The error of the RFC 3339 parsing is returned if none of the forms matches.
+/// See [parse_epub_date] to get an `Option` instead.
+pub fn parse_date_time(s: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    let s = s.trim();
+    let rfc3339_err = match DateTime::parse_from_rfc3339(s) {
+        Ok(dt) => return Ok(dt.to_utc()),
+        Err(err) => err,
+    };
+
+    if let Ok(dt) = DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Ok(dt.to_utc());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(dt.and_utc());
+        }
+    }
+    if let Some(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .or_else(|| parse_partial_date(s))
+    {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    Err(rfc3339_err)
+}
+
+/// Parse a date value, like the one of the `dc:date` element or the `dcterms:modified` property,
+/// returning `None` if it is invalid.
+///
+/// It accepts the same forms as [parse_date_time], including the partial dates `2011` and `2011-08`
+/// which are common in `dc:date` elements.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use eparser::package::metadata::parse_epub_date;
+///
+/// let expected = Utc.with_ymd_and_hms(2011, 8, 1, 0, 0, 0).unwrap();
+/// assert_eq!(parse_epub_date("2011-08"), Some(expected));
+/// assert!(parse_epub_date("August 2011").is_none());
+/// ```
+pub fn parse_epub_date(s: &str) -> Option<DateTime<Utc>> {
+    parse_date_time(s).ok()
+}
+
+/// Parse a year, `YYYY`, or a year and month, `YYYY-MM`, to the first day of the period.
+fn parse_partial_date(s: &str) -> Option<NaiveDate> {
+    let mut parts = s.split('-');
+    let year = parts.next().filter(|year| year.len() == 4)?;
+    let month = match parts.next() {
+        Some(month) if month.len() == 2 => month.parse().ok()?,
+        Some(_) => return None,
+        None => 1,
+    };
+    if parts.next().is_some() || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month, 1)
+}
+
+/// Parse a SMIL clock value, which is used by the `media:duration` property.
+///
+/// The supported forms are:
+/// - full clock values: `5:34:31.396`
+/// - partial clock values: `34:31.396`
+/// - timecount values: `76.2s`, `7.75h`, `12min`, `123ms`, or `76.2` in seconds
+/// - ISO 8601 durations: `PT1H2M3.5S`
+///
+/// # References
+///
+/// [SMIL 3.0 clock values](https://www.w3.org/TR/SMIL3/smil-timing.html#q22)
+pub fn parse_clock_value(s: &str) -> Option<Duration> {
+    let s = s.trim();
+
+    if let Some(iso) = s.strip_prefix("PT") {
+        let mut secs = 0.0;
+        let mut number = String::new();
+        for c in iso.chars() {
+            let unit = match c {
+                'H' => 3600.0,
+                'M' => 60.0,
+                'S' => 1.0,
+                _ => {
+                    number.push(c);
+                    continue;
+                }
+            };
+            secs += number.parse::<f64>().ok()? * unit;
+            number.clear();
+        }
+        // trailing number without unit
+        if !number.is_empty() {
+            return None;
+        }
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+
+    if s.contains(':') {
+        let parts = s.split(':').collect::<Vec<_>>();
+        let (hours, minutes, seconds) = match parts.as_slice() {
+            [h, m, s] => (h.parse::<u64>().ok()?, m.parse::<u64>().ok()?, *s),
+            [m, s] => (0, m.parse::<u64>().ok()?, *s),
+            _ => return None,
+        };
+        let seconds = seconds.parse::<f64>().ok()?;
+        if minutes >= 60 || seconds >= 60.0 {
+            return None;
+        }
+        let secs = (hours * 3600 + minutes * 60) as f64 + seconds;
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+
+    let (number, unit) = [("ms", 0.001), ("min", 60.0), ("h", 3600.0), ("s", 1.0)]
+        .iter()
+        .find_map(|(suffix, unit)| s.strip_suffix(suffix).map(|n| (n, *unit)))
+        .unwrap_or((s, 1.0));
+    Duration::try_from_secs_f64(number.parse::<f64>().ok()? * unit).ok()
+}
+
 #[derive(Debug, Error)]
 pub enum MetadataCheckError {
     #[error("The metadata section MUST contain exactly at least one {0} element.")]
@@ -156,11 +531,13 @@ pub enum MetadataCheckError {
     DateParseError(#[from] chrono::ParseError),
 }
 
-fn x() {
-}
-
 /// The metadata section of an EPUB Publication.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "SerdeMetadata", try_from = "SerdeMetadata")
+)]
 pub struct Metadata {
     /// All metadata elements
     ///
@@ -175,18 +552,96 @@ pub struct Metadata {
     /// All link elements
     pub links: Vec<Link>,
 
+    /// All EPUB 2 meta elements, which have a `name` instead of a `property` attribute.
+    pub legacy_metas: Vec<LegacyMeta>,
+
     /// The date and time the metadata was last modified.
     ///
     /// The metadata section MUST contain exactly one dcterms:modified property containing the last modification date.
     /// The value of this property MUST be an xmlschema-2 dateTime conformant date of the form: CCYY-MM-DDThh:mm:ssZ
-    pub last_modified: DateTime<Utc>,
+    ///
+    /// It is present in EPUB 3 publications unless they are parsed with
+    /// [PackageParseOptions::require_modified](crate::package::parser::PackageParseOptions::require_modified) off,
+    /// EPUB 2 publications may omit it.
+    pub last_modified: Option<DateTime<Utc>>,
+
+    /// The URL of the package document, the `refines` pointing at other documents are ignored.
+    ///
+    /// It is set by [Metadata::with_base_url].
+    base_url: Option<Url>,
 
     /// can not be instantiated from outside
     _private: PhantomData<()>,
 }
 
-static DCTERMS_MODIFIED: Lazy<Property> =
-    Lazy::new(|| Property::from_prefix(&DCTERMS, "modified".to_string()));
+/// The (de)serialized form of a [Metadata], the metadata elements are a flat list,
+/// since the keys of a map can not be structured in formats like JSON.
+///
+/// The metadata is checked again on deserialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeMetadata {
+    elems: Vec<MetadataElement>,
+    metas: Vec<Meta>,
+    links: Vec<Link>,
+    #[serde(default)]
+    legacy_metas: Vec<LegacyMeta>,
+    last_modified: Option<DateTime<Utc>>,
+    #[serde(default)]
+    base_url: Option<Url>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Metadata> for SerdeMetadata {
+    fn from(metadata: Metadata) -> Self {
+        SerdeMetadata {
+            elems: metadata.elems.into_values().flatten().collect(),
+            metas: metadata.metas,
+            links: metadata.links,
+            legacy_metas: metadata.legacy_metas,
+            last_modified: metadata.last_modified,
+            base_url: metadata.base_url,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerdeMetadata> for Metadata {
+    type Error = MetadataCheckError;
+
+    fn try_from(metadata: SerdeMetadata) -> Result<Self, Self::Error> {
+        let mut checked = Metadata::new_epub2(metadata.elems, metadata.metas, metadata.links)?;
+        checked.legacy_metas = metadata.legacy_metas;
+        checked.last_modified = metadata.last_modified;
+        checked.base_url = metadata.base_url;
+        Ok(checked)
+    }
+}
+
+/// (De)serialize the attributes as a list of pairs, for the same reason as [SerdeMetadata].
+#[cfg(feature = "serde")]
+mod serde_attrs {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::package::property::WithNamespace;
+
+    pub fn serialize<S: Serializer>(
+        attrs: &BTreeMap<WithNamespace, String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        attrs.iter().collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<WithNamespace, String>, D::Error> {
+        Ok(Vec::<(WithNamespace, String)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
 
 static DC_TITLE: Lazy<WithNamespace> =
     Lazy::new(|| WithNamespace::from_prefix(&DC, "title".to_string()));
@@ -197,12 +652,38 @@ static DC_LANGUAGE: Lazy<WithNamespace> =
 static DC_IDENTIFIER: Lazy<WithNamespace> =
     Lazy::new(|| WithNamespace::from_prefix(&DC, "identifier".to_string()));
 
+static DC_CREATOR: Lazy<WithNamespace> =
+    Lazy::new(|| WithNamespace::from_prefix(&DC, "creator".to_string()));
+
+static DC_SUBJECT: Lazy<WithNamespace> =
+    Lazy::new(|| WithNamespace::from_prefix(&DC, "subject".to_string()));
+
+static DC_DATE: Lazy<WithNamespace> =
+    Lazy::new(|| WithNamespace::from_prefix(&DC, "date".to_string()));
+
 impl Metadata {
     /// Create a new Metadata
     pub fn new(
         elems: Vec<MetadataElement>,
         metas: Vec<Meta>,
         links: Vec<Link>,
+    ) -> Result<Self, MetadataCheckError> {
+        let metadata = Self::new_epub2(elems, metas, links)?;
+        if metadata.last_modified.is_none() {
+            return Err(MetadataCheckError::MissingLastModifiedError(
+                "dcterms:modified".to_string(),
+            ));
+        }
+        Ok(metadata)
+    }
+
+    /// Create a new Metadata of an EPUB 2 publication.
+    ///
+    /// Unlike [Metadata::new], the `dcterms:modified` property is not required.
+    pub fn new_epub2(
+        elems: Vec<MetadataElement>,
+        metas: Vec<Meta>,
+        links: Vec<Link>,
     ) -> Result<Self, MetadataCheckError> {
         let elems = {
             let mut elems_map = BTreeMap::new();
@@ -221,7 +702,7 @@ impl Metadata {
                 elems_map: &BTreeMap<WithNamespace, Vec<MetadataElement>>,
                 tag_name: &WithNamespace,
             ) -> Result<(), MetadataCheckError> {
-                let elems = elems_map.get(&tag_name);
+                let elems = elems_map.get(tag_name);
                 if elems.is_none() || elems.unwrap().is_empty() {
                     Err(MetadataCheckError::MissingElementError(
                         tag_name.reference.clone(),
@@ -236,27 +717,33 @@ impl Metadata {
             check(&elems, &DC_IDENTIFIER)?;
         }
 
-        // check lastModified
-        let last_modified = {
-            let last_modified = metas
-                .iter()
-                .find(|&meta| meta.property.eq(&DCTERMS_MODIFIED))
-                .ok_or(MetadataCheckError::MissingLastModifiedError(
-                    "dcterms:modified".to_string(),
-                ))?;
-
-            DateTime::parse_from_rfc3339(&last_modified.value)?.to_utc()
-        };
+        // get lastModified
+        let last_modified = metas
+            .iter()
+            .find(|&meta| meta.property.is(&DCTERMS, "modified"))
+            .map(|meta| parse_date_time(&meta.value))
+            .transpose()?;
 
         Ok(Metadata {
             elems,
             metas,
             links,
+            legacy_metas: Vec::new(),
             last_modified,
+            base_url: None,
             _private: Default::default(),
         })
     }
 
+    /// Set the URL of the package document, which the `refines` of the meta elements are resolved against.
+    ///
+    /// Without it, a meta element refines every element with the id of its fragment,
+    /// even the ones of other documents.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
     /// All dc:title elements
     pub fn titles(&self) -> &Vec<MetadataElement> {
         self.elems.get(&DC_TITLE).unwrap()
@@ -271,4 +758,564 @@ impl Metadata {
     pub fn identifiers(&self) -> &Vec<MetadataElement> {
         self.elems.get(&DC_IDENTIFIER).unwrap()
     }
+
+    /// The scheme of the dc:identifier element with the `id`.
+    ///
+    /// It is resolved in this order:
+    /// 1. the `identifier-type` refinement, with ONIX code list 5 codes when its scheme
+    ///    is `onix:codelist5`, e.g. `15` for ISBN-13 and `06` for DOI;
+    /// 2. the `opf:scheme` attribute of EPUB 2 publications;
+    /// 3. the URN prefix of the value, like `urn:isbn:`, `urn:uuid:` or `urn:doi:`.
+    ///
+    /// Returns `None` if there is no such element or its scheme is unknown.
+    pub fn identifier_type(&self, id: &str) -> Option<IdentifierType> {
+        let elem = self
+            .identifiers()
+            .iter()
+            .find(|elem| elem.id.as_deref() == Some(id))?;
+        if let Some(meta) = self.refinement(id, &OPF, "identifier-type") {
+            let value = meta.value.trim();
+            let is_onix = meta
+                .scheme
+                .as_ref()
+                .is_some_and(|scheme| scheme.is(&ONIX, "codelist5"));
+            return Some(if is_onix {
+                IdentifierType::from_onix_code(value)
+            } else {
+                IdentifierType::from_name(value)
+            });
+        }
+        if let Some(scheme) = elem.attr(&OPF, "scheme") {
+            return Some(IdentifierType::from_name(scheme.trim()));
+        }
+        IdentifierType::from_urn(&elem.value)
+    }
+
+    /// The content of the first EPUB 2 meta element with the given name, like `cover`.
+    pub fn legacy_meta(&self, name: &str) -> Option<&str> {
+        self.legacy_metas
+            .iter()
+            .find(|meta| meta.name == name)
+            .map(|meta| meta.content.as_str())
+    }
+
+    /// All dc:creator elements, the first one is the primary creator.
+    pub fn creators(&self) -> &[MetadataElement] {
+        self.elems.get(&DC_CREATOR).map_or(&[], |elems| elems)
+    }
+
+    /// All the elements of a Dublin Core term, like `publisher`, `description` or `rights`.
+    ///
+    /// The term is the name of the element without the `dc:` prefix.
+    /// Returns an empty slice if there is no such element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use eparser::package::parser::{PackageParseOptions, PackageParser};
+    /// # use url::Url;
+    /// # let opf = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample/OEBPS/content.opf"));
+    /// # let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+    /// # let package = PackageParser::new(options).parse(opf).unwrap();
+    /// let metadata = &package.metadata;
+    /// assert_eq!(metadata.dc("publisher")[0].value, "天闻角川");
+    /// assert!(metadata.dc("rights").is_empty());
+    /// ```
+    pub fn dc(&self, term: &str) -> &[MetadataElement] {
+        let key = WithNamespace::from_prefix(&DC, term.to_string());
+        self.elems.get(&key).map_or(&[], |elems| elems)
+    }
+
+    /// The text of all dc:subject elements, like genres or keywords.
+    pub fn subjects(&self) -> Vec<&str> {
+        self.elems
+            .get(&DC_SUBJECT)
+            .into_iter()
+            .flatten()
+            .map(|elem| elem.value.as_str())
+            .collect()
+    }
+
+    /// All dc:subject elements with their `authority` and `term` refinements.
+    pub fn subjects_with_authority(&self) -> Vec<Subject> {
+        self.elems
+            .get(&DC_SUBJECT)
+            .into_iter()
+            .flatten()
+            .map(|elem| {
+                let refinement = |reference: &str| {
+                    self.refinement(elem.id.as_deref()?, &OPF, reference)
+                        .map(|meta| meta.value.trim().to_string())
+                };
+                Subject {
+                    value: elem.value.clone(),
+                    authority: refinement("authority"),
+                    term: refinement("term"),
+                }
+            })
+            .collect()
+    }
+
+    /// The collections the publication belongs to, like a series.
+    ///
+    /// They are the `belongs-to-collection` properties which do not refine any element,
+    /// the collections refining another collection are not included.
+    pub fn collections(&self) -> Vec<Collection> {
+        self.metas
+            .iter()
+            .filter(|meta| {
+                meta.refines.is_none() && meta.property.is(&OPF, "belongs-to-collection")
+            })
+            .map(|meta| {
+                let refinement = |reference: &str| {
+                    self.refinement(meta.id.as_deref()?, &OPF, reference)
+                        .map(|meta| meta.value.trim())
+                };
+                Collection {
+                    name: meta.value.trim().to_string(),
+                    collection_type: refinement("collection-type").map(|ty| ty.to_string()),
+                    group_position: refinement("group-position")
+                        .and_then(|position| position.parse().ok()),
+                }
+            })
+            .collect()
+    }
+
+    /// The first meta element with the property `reference` in the namespace of `prefix`,
+    /// which refines the element with the `id`.
+    fn refinement(&self, id: &str, prefix: &Prefix, reference: &str) -> Option<&Meta> {
+        self.metas
+            .iter()
+            .find(|meta| meta.is_refinement(self.base_url.as_ref(), id, prefix, reference))
+    }
+
+    /// The `alternate-script` refinements of an element, like a title or a creator,
+    /// keyed by their language.
+    ///
+    /// It gives the value of the element in another language or script,
+    /// e.g. the Japanese title of a translated book.
+    /// The refinements without the `xml:lang` attribute are skipped.
+    pub fn alternate_scripts(&self, elem: &MetadataElement) -> BTreeMap<&str, &str> {
+        let Some(id) = elem.id.as_deref() else {
+            return BTreeMap::new();
+        };
+        self.metas
+            .iter()
+            .filter(|meta| meta.is_refinement(self.base_url.as_ref(), id, &OPF, "alternate-script"))
+            .filter_map(|meta| Some((meta.lang.as_deref()?, meta.value.trim())))
+            .collect()
+    }
+
+    /// The `file-as` refinement of an element, the normalized form used for sorting,
+    /// e.g. `Carroll, Lewis`.
+    ///
+    /// The `opf:file-as` attribute of EPUB 2 publications is used if there is no refinement.
+    pub fn file_as<'a>(&'a self, elem: &'a MetadataElement) -> Option<&'a str> {
+        elem.id
+            .as_deref()
+            .and_then(|id| self.refinement(id, &OPF, "file-as"))
+            .map(|meta| meta.value.trim())
+            .or_else(|| elem.attr(&OPF, "file-as"))
+    }
+
+    /// The value of the `dc:date` element whose `opf:event` attribute is `event`,
+    /// like `publication`, `creation` or `modification` in EPUB 2 publications.
+    ///
+    /// The event is compared case-insensitively.
+    pub fn date_for_event(&self, event: &str) -> Option<&str> {
+        self.elems
+            .get(&DC_DATE)?
+            .iter()
+            .find(|elem| {
+                elem.attr(&OPF, "event")
+                    .is_some_and(|e| e.eq_ignore_ascii_case(event))
+            })
+            .map(|elem| elem.value.as_str())
+    }
+
+    /// The publication date, like for sorting a library by publication year.
+    ///
+    /// It is the first `dc:date` element without an `opf:event` attribute,
+    /// or whose event is `publication` in EPUB 2 publications, parsed by [parse_epub_date].
+    pub fn publication_date(&self) -> Option<DateTime<Utc>> {
+        self.elems
+            .get(&DC_DATE)?
+            .iter()
+            .find(|elem| {
+                elem.attr(&OPF, "event")
+                    .is_none_or(|e| e.eq_ignore_ascii_case("publication"))
+            })
+            .and_then(|elem| parse_epub_date(&elem.value))
+    }
+
+    /// The total duration of the media overlays of the publication.
+    ///
+    /// It is the `media:duration` property which does not refine any element.
+    pub fn total_duration(&self) -> Option<Duration> {
+        self.metas
+            .iter()
+            .find(|meta| meta.refines.is_none() && meta.property.is(&MEDIA, "duration"))
+            .and_then(|meta| parse_clock_value(&meta.value))
+    }
+
+    /// The duration of a media overlay.
+    ///
+    /// It is the `media:duration` property which refines the manifest item with the `id`.
+    pub fn duration_for(&self, id: &str) -> Option<Duration> {
+        self.refinement(id, &MEDIA, "duration")
+            .and_then(|meta| parse_clock_value(&meta.value))
+    }
+
+    /// Collect the accessibility metadata of the publication.
+    pub fn accessibility(&self) -> AccessibilitySummary {
+        let values = |prefix: &Prefix, reference: &str| {
+            self.metas
+                .iter()
+                .filter(|meta| meta.property.is(prefix, reference))
+                .map(|meta| meta.value.trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        let value = |prefix: &Prefix, reference: &str| values(prefix, reference).into_iter().next();
+
+        let certifier_report = self
+            .links
+            .iter()
+            .find(|link| link.rel.iter().any(|rel| rel.is(&A11Y, "certifierReport")))
+            .map(|link| link.href.clone());
+
+        AccessibilitySummary {
+            access_modes: values(&SCHEMA, "accessMode"),
+            access_modes_sufficient: values(&SCHEMA, "accessModeSufficient"),
+            features: values(&SCHEMA, "accessibilityFeature"),
+            hazards: values(&SCHEMA, "accessibilityHazard"),
+            summary: value(&SCHEMA, "accessibilitySummary"),
+            conforms_to: values(&DCTERMS, "conformsTo"),
+            certified_by: value(&A11Y, "certifiedBy"),
+            certifier_credential: value(&A11Y, "certifierCredential"),
+            certifier_report,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::DateTime;
+
+    use url::Url;
+
+    use crate::package::metadata::{
+        parse_clock_value, parse_date_time, parse_epub_date, Collection, IdentifierType, Metadata,
+        Refines, Subject,
+    };
+    use crate::package::parser::{PackageParseOptions, PackageParser};
+
+    fn parse_metadata(metadata: &str) -> Metadata {
+        let opf = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" unique-identifier="uid" xmlns="http://www.idpf.org/2007/opf">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="uid">urn:uuid:1234</dc:identifier>
+        <dc:title id="title">Title</dc:title>
+        <dc:language>en</dc:language>
+        <meta property="dcterms:modified">2024-08-13T04:09:43Z</meta>
+        {metadata}
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    </manifest>
+    <spine>
+        <itemref idref="nav"/>
+    </spine>
+</package>"#
+        );
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        PackageParser::new(options).parse(&opf).unwrap().metadata
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_checks() {
+        let metadata = parse_metadata("");
+        let mut json = serde_json::to_value(&metadata).unwrap();
+        assert!(serde_json::from_value::<Metadata>(json.clone()).is_ok());
+
+        json["elems"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|elem| elem["tag_name"]["reference"] != "title");
+        assert!(serde_json::from_value::<Metadata>(json)
+            .unwrap_err()
+            .to_string()
+            .contains("title"));
+    }
+
+    #[test]
+    fn test_accessibility() {
+        let metadata = parse_metadata(
+            r#"<meta property="schema:accessMode">textual</meta>
+        <meta property="schema:accessMode">visual</meta>
+        <meta property="schema:accessModeSufficient">textual</meta>
+        <meta property="schema:accessibilityFeature">tableOfContents</meta>
+        <meta property="schema:accessibilityFeature">alternativeText</meta>
+        <meta property="schema:accessibilityHazard">none</meta>
+        <meta property="schema:accessibilitySummary">This publication is accessible.</meta>
+        <meta property="dcterms:conformsTo">EPUB Accessibility 1.1 - WCAG 2.1 Level AA</meta>
+        <meta property="a11y:certifiedBy">Accessibility Testers Group</meta>
+        <link rel="a11y:certifierReport" href="https://example.com/report.html"/>"#,
+        );
+
+        let accessibility = metadata.accessibility();
+        assert_eq!(accessibility.access_modes, vec!["textual", "visual"]);
+        assert_eq!(accessibility.access_modes_sufficient, vec!["textual"]);
+        assert_eq!(
+            accessibility.features,
+            vec!["tableOfContents", "alternativeText"]
+        );
+        assert_eq!(accessibility.hazards, vec!["none"]);
+        assert_eq!(
+            accessibility.certified_by.as_deref(),
+            Some("Accessibility Testers Group")
+        );
+        assert!(accessibility.certifier_credential.is_none());
+        assert_eq!(
+            accessibility.certifier_report.unwrap().as_str(),
+            "https://example.com/report.html"
+        );
+        assert_eq!(accessibility.conforms_to.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_clock_value() {
+        let ms = Duration::from_millis;
+        assert_eq!(parse_clock_value("5:34:31.396"), Some(ms(20071396)));
+        assert_eq!(parse_clock_value("124:59:36"), Some(ms(449976000)));
+        assert_eq!(parse_clock_value("0:05:01.2"), Some(ms(301200)));
+        assert_eq!(parse_clock_value("09:58"), Some(ms(598000)));
+        assert_eq!(parse_clock_value("00:56.78"), Some(ms(56780)));
+        assert_eq!(parse_clock_value("76.2s"), Some(ms(76200)));
+        assert_eq!(parse_clock_value("7.75h"), Some(ms(27900000)));
+        assert_eq!(parse_clock_value("13min"), Some(ms(780000)));
+        assert_eq!(parse_clock_value("2345ms"), Some(ms(2345)));
+        assert_eq!(parse_clock_value("12.345"), Some(ms(12345)));
+        assert_eq!(parse_clock_value("PT1234S"), Some(ms(1234000)));
+        assert_eq!(parse_clock_value("PT1H2M3S"), Some(ms(3723000)));
+        assert_eq!(parse_clock_value("1:60:00"), None);
+        assert_eq!(parse_clock_value("abc"), None);
+    }
+
+    #[test]
+    fn test_duration() {
+        let metadata = parse_metadata(
+            r##"<meta property="media:duration">1:36:20</meta>
+        <meta property="media:duration" refines="#chapter1_audio">0:32:29</meta>
+        <meta property="media:duration" refines="#chapter2_audio">1903.5s</meta>"##,
+        );
+
+        assert_eq!(metadata.total_duration(), Some(Duration::from_secs(5780)));
+        assert_eq!(
+            metadata.duration_for("chapter1_audio"),
+            Some(Duration::from_secs(1949))
+        );
+        assert_eq!(
+            metadata.duration_for("chapter2_audio"),
+            Some(Duration::from_millis(1903500))
+        );
+        assert_eq!(metadata.duration_for("chapter3_audio"), None);
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        let expected = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().to_utc();
+
+        assert_eq!(
+            parse_date_time("2024-08-13T04:09:43Z").unwrap(),
+            expected("2024-08-13T04:09:43Z")
+        );
+        assert_eq!(
+            parse_date_time("2024-08-13 04:09:43").unwrap(),
+            expected("2024-08-13T04:09:43Z")
+        );
+        assert_eq!(
+            parse_date_time("2024-08-13 12:09:43+08:00").unwrap(),
+            expected("2024-08-13T04:09:43Z")
+        );
+        assert_eq!(
+            parse_date_time("2024-08-13T04:09:43.5").unwrap(),
+            expected("2024-08-13T04:09:43.5Z")
+        );
+        assert_eq!(
+            parse_date_time("2011-01-01").unwrap(),
+            expected("2011-01-01T00:00:00Z")
+        );
+        assert!(parse_date_time("August 13, 2024").is_err());
+
+        assert_eq!(
+            parse_epub_date("2011"),
+            Some(expected("2011-01-01T00:00:00Z"))
+        );
+        assert_eq!(
+            parse_epub_date(" 2011-08 "),
+            Some(expected("2011-08-01T00:00:00Z"))
+        );
+        for s in [
+            "2011-13",
+            "2011-8",
+            "201",
+            "20111",
+            "2011-08-01-01",
+            "-2011",
+            "",
+        ] {
+            assert!(parse_epub_date(s).is_none(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_subjects() {
+        let metadata = parse_metadata(
+            r##"<dc:subject id="subject1">FICTION / Romance / Historical</dc:subject>
+        <meta refines="#subject1" property="authority">BISAC</meta>
+        <meta refines="#subject1" property="term">FIC027050</meta>
+        <dc:subject>轻小说</dc:subject>"##,
+        );
+
+        assert_eq!(
+            metadata.subjects(),
+            vec!["FICTION / Romance / Historical", "轻小说"]
+        );
+        assert_eq!(
+            metadata.subjects_with_authority(),
+            vec![
+                Subject {
+                    value: "FICTION / Romance / Historical".to_string(),
+                    authority: Some("BISAC".to_string()),
+                    term: Some("FIC027050".to_string()),
+                },
+                Subject {
+                    value: "轻小说".to_string(),
+                    authority: None,
+                    term: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        let metadata = parse_metadata(
+            r##"<meta property="belongs-to-collection" id="c01">魔女之旅</meta>
+        <meta refines="#c01" property="collection-type">series</meta>
+        <meta refines="#c01" property="group-position">5</meta>
+        <meta property="belongs-to-collection" id="c02">Light Novels</meta>
+        <meta refines="#c02" property="belongs-to-collection">GA Bunko</meta>"##,
+        );
+
+        assert_eq!(
+            metadata.collections(),
+            vec![
+                Collection {
+                    name: "魔女之旅".to_string(),
+                    collection_type: Some("series".to_string()),
+                    group_position: Some(5.0),
+                },
+                Collection {
+                    name: "Light Novels".to_string(),
+                    collection_type: None,
+                    group_position: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identifier_type() {
+        let metadata = parse_metadata(
+            r##"<dc:identifier id="isbn">9787540895489</dc:identifier>
+        <meta refines="#isbn" property="identifier-type" scheme="onix:codelist5">15</meta>
+        <dc:identifier id="doi">10.1000/182</dc:identifier>
+        <meta refines="#doi" property="identifier-type">DOI</meta>
+        <dc:identifier id="urn-doi">urn:doi:10.1000/183</dc:identifier>
+        <dc:identifier id="plain">abc</dc:identifier>"##,
+        );
+
+        assert_eq!(metadata.identifier_type("isbn"), Some(IdentifierType::Isbn));
+        assert_eq!(metadata.identifier_type("doi"), Some(IdentifierType::Doi));
+        assert_eq!(metadata.identifier_type("uid"), Some(IdentifierType::Uuid));
+        assert_eq!(
+            metadata.identifier_type("urn-doi"),
+            Some(IdentifierType::Doi)
+        );
+        assert_eq!(metadata.identifier_type("plain"), None);
+        assert_eq!(metadata.identifier_type("missing"), None);
+    }
+
+    #[test]
+    fn test_refines_fragment() {
+        let base_url = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        let pure = Refines::from_relative_url("#title", &base_url).unwrap();
+        let with_path = Refines::from_relative_url("content.opf#title", &base_url).unwrap();
+        assert_eq!(pure, with_path);
+        assert_eq!(pure.url().as_str(), "epub:/OEBPS/content.opf#title");
+        assert_eq!(pure.fragment().as_deref(), Some("title"));
+        assert_eq!(pure.document_url(), base_url);
+
+        let resource = Refines::from_relative_url("chapter1.xhtml", &base_url).unwrap();
+        assert_eq!(resource.fragment(), None);
+
+        // the fragment is percent-encoded in the URL
+        let non_ascii = Refines::from_relative_url("#作者", &base_url).unwrap();
+        assert_eq!(non_ascii.fragment().as_deref(), Some("作者"));
+
+        let metadata = parse_metadata(
+            r#"<meta refines="content.opf#title" property="title-type">main</meta>"#,
+        );
+        assert_eq!(
+            metadata.metas[1]
+                .refines
+                .as_ref()
+                .unwrap()
+                .fragment()
+                .as_deref(),
+            Some("title")
+        );
+    }
+
+    #[test]
+    fn test_refinement_targets() {
+        let metadata = parse_metadata(
+            r##"<dc:creator id="作者">白石定规</dc:creator>
+        <meta refines="#作者" property="file-as">Shiraishi, Jougi</meta>
+        <dc:contributor id="illustrator">あずーる</dc:contributor>
+        <meta refines="Text/chapter1.xhtml#illustrator" property="file-as">Chapter</meta>"##,
+        );
+        let creator = &metadata.creators()[0];
+        assert_eq!(metadata.file_as(creator), Some("Shiraishi, Jougi"));
+
+        // the refinement of an element with the same id in another document
+        let contributor = &metadata.dc("contributor")[0];
+        assert_eq!(metadata.file_as(contributor), None);
+    }
+
+    #[test]
+    fn test_title_refinements() {
+        let metadata = parse_metadata(
+            r##"<meta refines="#title" property="alternate-script" xml:lang="ja">魔女の旅々</meta>
+        <meta refines="#title" property="alternate-script" xml:lang="zh">魔女之旅</meta>
+        <meta refines="#title" property="alternate-script">Ignored</meta>
+        <meta refines="#title" property="file-as">Title, The</meta>"##,
+        );
+
+        let title = &metadata.titles()[0];
+        let scripts = metadata.alternate_scripts(title);
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts["ja"], "魔女の旅々");
+        assert_eq!(scripts["zh"], "魔女之旅");
+        assert_eq!(metadata.file_as(title), Some("Title, The"));
+
+        let language = &metadata.languages()[0];
+        assert!(metadata.alternate_scripts(language).is_empty());
+        assert_eq!(metadata.file_as(language), None);
+    }
 }
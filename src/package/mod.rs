@@ -1,10 +1,25 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
 use manifest::Manifest;
 use metadata::Metadata;
 use spine::Spine;
+use url::Url;
 
+use crate::package::guide::GuideReference;
 use crate::package::manifest::Resource;
+use crate::package::media_type::media_types;
+use crate::package::metadata::MetadataElement;
+use crate::package::nav::NavDocument;
 use crate::package::spine::SpineReference;
 
+pub mod builder;
+pub mod consistency;
+pub mod content;
+pub mod cover;
+pub mod diff;
+pub mod guide;
+pub mod layout;
 pub mod manifest;
 pub mod media_type;
 pub mod metadata;
@@ -12,28 +27,118 @@ pub mod nav;
 pub mod parser;
 pub mod prefix;
 pub mod property;
+pub mod references;
+pub mod search;
 pub mod spine;
+pub mod writer;
+
+/// The version of the EPUB specification to which a package conforms.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Version {
+    /// EPUB 2, declared as `version="2.0"`.
+    ///
+    /// Only the metadata, manifest and spine are supported, the EPUB 3 requirements
+    /// like the nav resource and the `dcterms:modified` property are skipped.
+    V2,
+
+    /// EPUB 3, declared as `version="3.0"`.
+    V3,
+}
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2.0" => Ok(Version::V2),
+            "3.0" => Ok(Version::V3),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::V2 => write!(f, "2.0"),
+            Version::V3 => write!(f, "3.0"),
+        }
+    }
+}
+
+/// The base direction of the text of an element, the value of a `dir` attribute.
+///
+/// # References
+///
+/// [EPUB 3.3 SPEC dir](https://www.w3.org/TR/epub-33/#attrdef-dir)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// Left-to-right, declared as `dir="ltr"`.
+    Ltr,
+
+    /// Right-to-left, declared as `dir="rtl"`.
+    Rtl,
+
+    /// Determined by the content with the Unicode bidi algorithm, declared as `dir="auto"`.
+    Auto,
+}
+
+impl Direction {
+    /// Check if the direction is right-to-left.
+    pub fn is_rtl(&self) -> bool {
+        *self == Direction::Rtl
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ltr" => Ok(Direction::Ltr),
+            "rtl" => Ok(Direction::Rtl),
+            "auto" => Ok(Direction::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Ltr => write!(f, "ltr"),
+            Direction::Rtl => write!(f, "rtl"),
+            Direction::Auto => write!(f, "auto"),
+        }
+    }
+}
 
 /// A Package is made up of:
 /// - A [Metadata]: provides a standard way to include publication metadata.
-/// contains titles, authors, identifiers, languages, and other metadata.
+///   contains titles, authors, identifiers, languages, and other metadata.
 /// - A [Manifest]: provides an exhaustive list of publication resources used in the rendering of the content.
-/// Like xhtml files, images, stylesheets, fonts, and other media.
+///   Like xhtml files, images, stylesheets, fonts, and other media.
 /// - A [Spine]: provides the linear reading order of the [Resource]s in the [Manifest].
 ///
 /// It is important to point out that [Manifest] contains exactly one [Nav] [Resource] which is a special resource
 /// that provides the table of contents of the publication.
 ///
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Package {
     /// The unique identifier of the package element.
     pub id: Option<String>,
 
+    /// The URL of the package document, the hrefs in it are resolved against it.
+    pub base_url: Url,
+
     /// The unique identifier reference of the package.
     unique_identifier_ref: String,
 
     /// The version of the EPUB specification to which the publication conforms.
-    pub version: String,
+    pub version: Version,
 
     /// [Metadata] provides a standard way to include publication metadata.
     pub metadata: Metadata,
@@ -44,12 +149,55 @@ pub struct Package {
     /// [Spine] provides the linear reading order of the [Resource]s in the [Manifest].
     pub spine: Spine,
 
+    /// The references of the EPUB 2 guide element, empty if it is absent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub guide: Vec<GuideReference>,
+
     pub prefix: Option<String>,
-    pub dir: Option<String>,
+    pub dir: Option<Direction>,
     pub lang: Option<String>,
 }
 
 impl Package {
+    /// The id of the `dc:identifier` element holding the unique identifier of the publication.
+    pub fn unique_identifier_ref(&self) -> &str {
+        &self.unique_identifier_ref
+    }
+
+    /// Get the `dc:identifier` element holding the unique identifier of the publication.
+    pub fn unique_identifier(&self) -> Option<&MetadataElement> {
+        self.metadata
+            .identifiers()
+            .iter()
+            .find(|elem| elem.id.as_deref() == Some(self.unique_identifier_ref.as_str()))
+    }
+
+    /// The primary language of the publication, the value of a `dc:language` element.
+    ///
+    /// The element matching the `xml:lang` attribute of the package is preferred,
+    /// compared case-insensitively and then by the primary subtag, e.g. `zh` matches `zh-CN`.
+    /// Otherwise it is the first `dc:language` element.
+    pub fn primary_language(&self) -> Option<&str> {
+        let languages = self.metadata.languages();
+        fn primary_subtag(lang: &str) -> &str {
+            lang.split('-').next().unwrap_or_default().trim()
+        }
+        self.lang
+            .as_deref()
+            .and_then(|lang| {
+                languages
+                    .iter()
+                    .find(|elem| elem.value.trim().eq_ignore_ascii_case(lang.trim()))
+                    .or_else(|| {
+                        languages.iter().find(|elem| {
+                            primary_subtag(&elem.value).eq_ignore_ascii_case(primary_subtag(lang))
+                        })
+                    })
+            })
+            .or_else(|| languages.first())
+            .map(|elem| elem.value.trim())
+    }
+
     /// A sugar method to get the [Resource] by id in the [Manifest].
     pub fn get_res_by_id(&self, id: &str) -> Option<&Resource> {
         self.manifest.get_resource_by_id(id)
@@ -60,8 +208,319 @@ impl Package {
         self.manifest.get_resource_by_id(&ref_.id)
     }
 
+    /// Get the [Resource] at a position in the [Spine].
+    ///
+    /// Returns `None` if the index is out of bounds or the reference does not resolve.
+    pub fn resource_at_spine_index(&self, index: usize) -> Option<&Resource> {
+        self.get_res_by_ref(self.spine.get(index)?)
+    }
+
+    /// Get the position in the [Spine] of the resource with the given id.
+    ///
+    /// Returns `None` if the resource is not in the reading order, like the nav document may not be.
+    pub fn spine_index_of_resource(&self, id: &str) -> Option<usize> {
+        self.spine.position_of(id)
+    }
+
+    /// The number of references in the [Spine].
+    pub fn spine_len(&self) -> usize {
+        self.spine.len()
+    }
+
+    /// A sugar method to get the [Resource] by href in the [Manifest].
+    ///
+    /// The fragment of the href is ignored, so links like `chapter1.xhtml#section1` can be resolved.
+    pub fn get_res_by_href(&self, href: &Url) -> Option<&Resource> {
+        let mut href = href.clone();
+        href.set_fragment(None);
+        self.manifest.get_resource_by_href(&href)
+    }
+
     /// A sugar method to get the nav resource in the manifest.
     pub fn nav_resource(&self) -> Option<&Resource> {
         self.manifest.nav_resource()
     }
+
+    /// Get the NCX resource the `toc` attribute of the [Spine] points at.
+    ///
+    /// Falls back to the first NCX item of the manifest, see [Manifest::ncx_resource],
+    /// if the spine has no `toc` attribute or it points at no resource.
+    pub fn ncx_resource(&self) -> Option<&Resource> {
+        self.spine
+            .toc
+            .as_deref()
+            .and_then(|toc| self.get_res_by_id(toc))
+            .or_else(|| self.manifest.ncx_resource())
+    }
+
+    /// Get the media overlay document of the resource with the id `resource_id`,
+    /// the SMIL resource its `media-overlay` attribute refers to.
+    ///
+    /// Returns `None` if the resource has no media overlay, or the referenced resource
+    /// is missing or not a SMIL document.
+    pub fn media_overlay_for(&self, resource_id: &str) -> Option<&Resource> {
+        let resource = self.get_res_by_id(resource_id)?;
+        self.get_res_by_id(resource.media_overlay.as_deref()?)
+            .filter(|overlay| overlay.media_type == *media_types::SMIL)
+    }
+
+    /// Check if any resource in the manifest references remote resources,
+    /// see [Resource::uses_remote_resources].
+    ///
+    /// It relies on the `remote-resources` properties declared in the manifest,
+    /// the content documents are not scanned.
+    pub fn has_remote_resources(&self) -> bool {
+        self.manifest.iter().any(Resource::uses_remote_resources)
+    }
+
+    /// A sugar method to check if all the resources in the manifest are core media types.
+    pub fn all_core_media_types(&self) -> bool {
+        self.manifest.all_core_media_types()
+    }
+
+    /// Get the [Resource] of the in-book table of contents page.
+    ///
+    /// It is the target of the `toc` landmark in the navigation document.
+    pub fn toc_page_resource(&self, nav_doc: &NavDocument) -> Option<&Resource> {
+        let href = nav_doc.landmark("toc")?.label.href.as_ref()?;
+        self.get_res_by_href(href)
+    }
+}
+
+/// A summary of the package in a few lines, like:
+///
+/// ```text
+/// Title: 魔女之旅 5
+/// Author: 白石定规
+/// Language: zh-CN
+/// Identifier: urn:uuid:6a4e9a4b-6a8c-4c4e-9a4b-0f4d6c6e2b11
+/// Version: 3.0
+/// Resources: 8
+/// Spine: 4
+/// ```
+///
+/// The lines of the missing metadata are omitted.
+impl Display for Package {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let metadata = &self.metadata;
+        if let Some(title) = metadata.titles().first() {
+            writeln!(f, "Title: {}", title.value)?;
+        }
+        if let Some(author) = metadata.creators().first() {
+            writeln!(f, "Author: {}", author.value)?;
+        }
+        if let Some(language) = self.primary_language() {
+            writeln!(f, "Language: {}", language)?;
+        }
+        if let Some(identifier) = self.unique_identifier() {
+            writeln!(f, "Identifier: {}", identifier.value)?;
+        }
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Resources: {}", self.manifest.len())?;
+        write!(f, "Spine: {}", self.spine.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::package::nav::parse_nav_document;
+    use crate::package::parser::{PackageParseOptions, PackageParser};
+    use crate::package::spine::SpineReference;
+    use crate::package::Package;
+
+    const SAMPLE_OPF: &str = include_str!("../../res/sample/OEBPS/content.opf");
+    const SAMPLE_NAV: &str = include_str!("../../res/sample/OEBPS/nav.xhtml");
+
+    /// Parse a package document located at `epub:/OEBPS/content.opf`.
+    pub(crate) fn parse_opf(opf: &str) -> Package {
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        PackageParser::new(options).parse(opf).unwrap()
+    }
+
+    fn parse_sample() -> Package {
+        parse_opf(SAMPLE_OPF)
+    }
+
+    #[test]
+    fn test_display() {
+        let package = parse_sample();
+        let summary = package.to_string();
+        let lines = summary.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Title: 魔女之旅 5");
+        assert_eq!(lines[1], "Author: 白石定规");
+        assert_eq!(lines[2], "Language: zh-CN");
+        assert_eq!(
+            lines[3],
+            "Identifier: urn:uuid:6a4e9a4b-6a8c-4c4e-9a4b-0f4d6c6e2b11"
+        );
+        assert_eq!(lines[4], "Version: 3.0");
+        assert_eq!(lines[5], format!("Resources: {}", package.manifest.len()));
+        assert_eq!(lines[6], "Spine: 4");
+    }
+
+    #[test]
+    fn test_linear_refs() {
+        let package = parse_opf(&SAMPLE_OPF.replace(
+            r#"<itemref idref="cover"/>"#,
+            r#"<itemref idref="cover" linear="no"/>"#,
+        ));
+        let ids = |refs: Vec<&SpineReference>| {
+            refs.iter()
+                .map(|spine_ref| spine_ref.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(package.spine.linear_refs().collect()),
+            vec!["contents", "chapter1", "chapter2"]
+        );
+        assert_eq!(ids(package.spine.auxiliary_refs().collect()), vec!["cover"]);
+    }
+
+    #[test]
+    fn test_primary_language() {
+        let mut package = parse_sample();
+        assert_eq!(package.primary_language(), Some("zh-CN"));
+
+        let title = &package.metadata.titles()[0];
+        assert_eq!(title.effective_lang(&package), Some("zh-CN"));
+
+        package.lang = Some("ja".to_string());
+        assert_eq!(package.primary_language(), Some("zh-CN"));
+        let title = &package.metadata.titles()[0];
+        assert_eq!(title.effective_lang(&package), Some("ja"));
+
+        let mut title = title.clone();
+        title.lang = Some("en".to_string());
+        assert_eq!(title.effective_lang(&package), Some("en"));
+
+        let opf = SAMPLE_OPF.replace(
+            "<dc:language>zh-CN</dc:language>",
+            "<dc:language>en</dc:language><dc:language>ZH-cn</dc:language>",
+        );
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        let mut package = PackageParser::new(options).parse(&opf).unwrap();
+        assert_eq!(package.primary_language(), Some("en"));
+        package.lang = Some("zh-cn".to_string());
+        assert_eq!(package.primary_language(), Some("ZH-cn"));
+        package.lang = Some("zh-TW".to_string());
+        assert_eq!(package.primary_language(), Some("ZH-cn"));
+    }
+
+    #[test]
+    fn test_media_overlay_for() {
+        let opf = SAMPLE_OPF
+            .replace(
+                r#"<item id="chapter1" "#,
+                r#"<item id="chapter1" media-overlay="chapter1-overlay" "#,
+            )
+            .replace(
+                r#"<item id="chapter2" "#,
+                r#"<item id="chapter2" media-overlay="style" "#,
+            )
+            .replace(
+                "<manifest>",
+                r#"<manifest><item id="chapter1-overlay" href="Audio/chapter1.smil" media-type="application/smil+xml"/>"#,
+            );
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        let package = PackageParser::new(options).parse(&opf).unwrap();
+
+        let overlay = package.media_overlay_for("chapter1").unwrap();
+        assert_eq!(overlay.id, "chapter1-overlay");
+        assert_eq!(overlay.href.path(), "/OEBPS/Audio/chapter1.smil");
+        assert!(package.media_overlay_for("chapter2").is_none());
+        assert!(package.media_overlay_for("contents").is_none());
+        assert!(package.media_overlay_for("missing").is_none());
+    }
+
+    #[test]
+    fn test_has_remote_resources() {
+        let options = PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap());
+        let package = PackageParser::new(options.clone())
+            .parse(SAMPLE_OPF)
+            .unwrap();
+        assert!(!package.has_remote_resources());
+
+        let opf = SAMPLE_OPF.replace(
+            r#"<item id="chapter2" "#,
+            r#"<item id="chapter2" properties="scripted remote-resources" "#,
+        );
+        let package = PackageParser::new(options).parse(&opf).unwrap();
+        assert!(package.has_remote_resources());
+        assert!(package
+            .get_res_by_id("chapter2")
+            .unwrap()
+            .uses_remote_resources());
+        assert!(!package
+            .get_res_by_id("chapter1")
+            .unwrap()
+            .uses_remote_resources());
+    }
+
+    #[test]
+    fn test_spine_index_of_resource() {
+        let package = parse_sample();
+        assert_eq!(package.spine.position_of("cover"), Some(0));
+        assert_eq!(package.spine_index_of_resource("chapter1"), Some(2));
+        assert_eq!(package.spine_index_of_resource("nav"), None);
+
+        let href = package
+            .base_url
+            .join("Text/chapter2.xhtml#section1")
+            .unwrap();
+        let res = package.get_res_by_href(&href).unwrap();
+        let index = package.spine_index_of_resource(&res.id).unwrap();
+        assert_eq!(package.resource_at_spine_index(index).unwrap().id, res.id);
+    }
+
+    #[test]
+    fn test_all_core_media_types() {
+        let package = parse_sample();
+        assert!(package.all_core_media_types());
+    }
+
+    #[test]
+    fn test_resource_at_spine_index() {
+        let package = parse_sample();
+        assert_eq!(package.spine_len(), 4);
+        assert_eq!(package.resource_at_spine_index(0).unwrap().id, "cover");
+        assert_eq!(package.resource_at_spine_index(3).unwrap().id, "chapter2");
+        assert!(package.resource_at_spine_index(4).is_none());
+    }
+
+    #[test]
+    fn test_toc_page_resource() {
+        let package = parse_sample();
+        let nav_url = package.nav_resource().unwrap().href.clone();
+        let nav_doc = parse_nav_document(SAMPLE_NAV, &nav_url).unwrap();
+
+        let toc_page = package.toc_page_resource(&nav_doc).unwrap();
+        assert_eq!(toc_page.id, "contents");
+        assert_ne!(toc_page.id, package.nav_resource().unwrap().id);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let package = parse_sample();
+        let json = serde_json::to_string(&package).unwrap();
+        let deserialized: Package = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        assert_eq!(deserialized.metadata.titles(), package.metadata.titles());
+        assert_eq!(
+            deserialized.metadata.last_modified,
+            package.metadata.last_modified
+        );
+        assert_eq!(
+            deserialized.nav_resource().unwrap().id,
+            package.nav_resource().unwrap().id
+        );
+        assert_eq!(
+            deserialized.manifest.get_resource_by_id("chapter1"),
+            package.manifest.get_resource_by_id("chapter1")
+        );
+        assert_eq!(deserialized.spine, package.spine);
+    }
 }
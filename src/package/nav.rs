@@ -1,8 +1,19 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
 use minidom::Element;
 use thiserror::Error;
 use url::Url;
 
+use crate::cfi::{element_path, Cfi};
+use crate::charset::decode_document;
+use crate::file::Files;
+use crate::package::Package;
+use crate::utils::{file_key, strip_doctype};
+use crate::xhtml::{parse_xhtml, XHTML};
+
 /// The type of the nav.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum NavType {
     TOC,
     Landmarks,
@@ -10,6 +21,18 @@ pub enum NavType {
     Custom(String),
 }
 
+impl From<&str> for NavType {
+    /// Get the nav type from the `epub:type` attribute of the nav.
+    fn from(ty: &str) -> Self {
+        match ty {
+            "toc" => NavType::TOC,
+            "landmarks" => NavType::Landmarks,
+            "page-list" => NavType::PageList,
+            _ => NavType::Custom(ty.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Nav {
     /// The `epub:type` attribute of the nav.
@@ -22,6 +45,44 @@ pub struct Nav {
     pub children: Vec<NavPoint>,
 }
 
+impl Nav {
+    /// Get all the nav points in pre-order, paired with their depth.
+    ///
+    /// The depth of the top-level nav points is 0.
+    pub fn flatten(&self) -> Vec<(usize, &NavPoint)> {
+        let mut points = Vec::new();
+        let mut stack: Vec<(usize, &NavPoint)> =
+            self.children.iter().rev().map(|point| (0, point)).collect();
+        while let Some((depth, point)) = stack.pop() {
+            stack.extend(point.children.iter().rev().map(|child| (depth + 1, child)));
+            points.push((depth, point));
+        }
+        points
+    }
+
+    /// Find the nav point referring to `url`, like the location of the current chapter.
+    ///
+    /// The nav point whose href is exactly `url` is preferred, otherwise the first one
+    /// referring to the same document regardless of the fragment is returned.
+    pub fn find_by_href(&self, url: &Url) -> Option<&NavPoint> {
+        let points = self.flatten();
+        let exact = points
+            .iter()
+            .find(|(_, point)| point.label.href.as_ref() == Some(url));
+        let same_document = || {
+            let key = file_key(url);
+            points.iter().find(|(_, point)| {
+                point
+                    .label
+                    .href
+                    .as_ref()
+                    .is_some_and(|href| file_key(href) == key)
+            })
+        };
+        exact.or_else(same_document).map(|(_, point)| *point)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NavTitle {
     /// The text content of the nav title.
@@ -38,6 +99,12 @@ pub struct NavLabel {
 
     /// The href attribute of the nav label.
     pub href: Option<Url>,
+
+    /// The `epub:type` attribute of the nav label.
+    ///
+    /// It is used by the landmarks nav to identify the structural semantics of the target,
+    /// like `cover`, `toc` or `bodymatter`.
+    pub ty: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,44 +119,416 @@ pub struct NavPoint {
     pub children: Vec<NavPoint>,
 }
 
-const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
-
 #[derive(Debug, Error)]
 pub enum NavParseError {
     #[error("Invalid XML, {0}")]
     ParseError(#[from] minidom::Error),
 
-    #[error("Invalid root element, expected nav but found {0}")]
+    #[error("Invalid root element: {0}")]
     InvalidRoot(String),
+
+    #[error("Missing body element")]
+    MissingBody,
 }
 
-/// Parse the nav document.
+/// The navigation document.
 ///
-/// The structure of the nav document is as follows:
+/// It contains one or more nav elements, distinguished by their [NavType].
+#[derive(Debug, Clone)]
+pub struct NavDocument {
+    /// All the navs in the document.
+    pub navs: BTreeMap<NavType, Nav>,
+}
+
+impl NavDocument {
+    /// Get a nav by its type.
+    pub fn get(&self, ty: &NavType) -> Option<&Nav> {
+        self.navs.get(ty)
+    }
+
+    /// Get the table of contents.
+    pub fn toc(&self) -> Option<&Nav> {
+        self.get(&NavType::TOC)
+    }
+
+    /// Get the landmarks.
+    pub fn landmarks(&self) -> Option<&Nav> {
+        self.get(&NavType::Landmarks)
+    }
+
+    /// Get the landmark with the given `epub:type`, like `cover`, `toc` or `bodymatter`.
+    pub fn landmark(&self, ty: &str) -> Option<&NavPoint> {
+        self.landmarks()?.children.iter().find(|point| {
+            point
+                .label
+                .ty
+                .as_deref()
+                .is_some_and(|types| types.split_whitespace().any(|t| t == ty))
+        })
+    }
+
+    /// Get the page list.
+    ///
+    /// Each [NavPoint] of the page list maps a page label of the print source (e.g. `"42"`)
+    /// to its location in the content documents.
+    pub fn page_list(&self) -> Option<&Nav> {
+        self.get(&NavType::PageList)
+    }
+}
+
+/// Parse the nav element.
+///
+/// The structure of the nav element is as follows:
 ///
 /// ```xml
-/// <nav>
+/// <nav epub:type="toc">
 ///     <h1>Table of Contents</h1>
 ///     <ol>
 ///         <li><a href="cover.xhtml">Cover</a></li>
-///         <li><a href="chapter1.xhtml">Chapter 1</a></li>
+///         <li>
+///             <a href="chapter1.xhtml">Chapter 1</a>
+///             <ol>
+///                 <li><a href="chapter1.xhtml#section1">Section 1</a></li>
+///             </ol>
+///         </li>
 ///         <li><a href="chapter2.xhtml">Chapter 2</a></li>
-///         <li><a href="chapter3.xhtml">Chapter 3</a></li>
-///         <li><a href="chapter4.xhtml">Chapter 4</a></li>
-///         <li><a href="chapter5.xhtml">Chapter 5</a></li>
-///         <li><a href="chapter6.xhtml">Chapter 6</a></li>
-///         <li><a href="chapter7.xhtml">Chapter 7</a></li>
 ///     </ol>
 /// </nav>
 /// ```
-pub fn parse_nav(str: &str) -> Result<Nav, NavParseError> {
-    let root_elem = str.parse::<Element>()?;
+///
+/// Every `href` is resolved against `base_url`, which is the URL of the nav document.
+pub fn parse_nav(str: &str, base_url: &Url) -> Result<Nav, NavParseError> {
+    let root_elem = strip_doctype(str).parse::<Element>()?;
 
     if root_elem.name() != "nav" {
         return Err(NavParseError::InvalidRoot(root_elem.name().to_string()));
     }
 
-    let ty = root_elem.attr("epub:type").map(|s| s.to_string());
+    Ok(parse_nav_elem(&root_elem, base_url))
+}
+
+/// Parse the navigation document.
+///
+/// The navigation document is an XHTML document whose body contains several nav elements,
+/// like `toc`, `landmarks` and `page-list`. Each nav is parsed as [parse_nav] does and keyed by
+/// its [NavType]. The nav elements without an `epub:type` attribute are ignored.
+pub fn parse_nav_document(str: &str, base_url: &Url) -> Result<NavDocument, NavParseError> {
+    let root_elem = strip_doctype(str).parse::<Element>()?;
+
+    if root_elem.name() != "html" {
+        return Err(NavParseError::InvalidRoot(root_elem.name().to_string()));
+    }
+
+    let body = root_elem
+        .children()
+        .find(|n| n.name() == "body")
+        .ok_or(NavParseError::MissingBody)?;
+
+    let mut nav_elems = Vec::new();
+    find_nav_elems(body, &mut nav_elems);
+
+    let mut navs = BTreeMap::new();
+    for nav_elem in nav_elems {
+        let nav = parse_nav_elem(nav_elem, base_url);
+        if nav.ty.is_empty() {
+            continue;
+        }
+        navs.entry(NavType::from(nav.ty.as_str())).or_insert(nav);
+    }
+
+    Ok(NavDocument { navs })
+}
+
+fn find_nav_elems<'a>(elem: &'a Element, nav_elems: &mut Vec<&'a Element>) {
+    for child in elem.children() {
+        if child.name() == "nav" {
+            nav_elems.push(child);
+        } else {
+            find_nav_elems(child, nav_elems);
+        }
+    }
+}
+
+fn parse_nav_elem(nav_elem: &Element, base_url: &Url) -> Nav {
+    let ty = nav_elem.attr("epub:type").unwrap_or_default().to_string();
+
+    let title = nav_elem.children().find_map(|n| {
+        let level = match n.name() {
+            "h1" => 1,
+            "h2" => 2,
+            "h3" => 3,
+            "h4" => 4,
+            "h5" => 5,
+            "h6" => 6,
+            _ => return None,
+        };
+        Some(NavTitle {
+            text: text_content(n),
+            level,
+        })
+    });
+
+    let mut order = 0;
+    let children = nav_elem
+        .children()
+        .find(|n| n.name() == "ol")
+        .map(|ol| parse_nav_points(ol, base_url, &mut order))
+        .unwrap_or_default();
+
+    Nav {
+        ty,
+        title,
+        children,
+    }
+}
+
+fn parse_nav_points(ol_elem: &Element, base_url: &Url, order: &mut usize) -> Vec<NavPoint> {
+    ol_elem
+        .children()
+        .filter(|n| n.name() == "li")
+        .filter_map(|li| {
+            // the label is an `a` element or a `span` element for headings
+            let label_elem = li
+                .children()
+                .find(|n| n.name() == "a" || n.name() == "span")?;
+
+            let label = NavLabel {
+                text: text_content(label_elem),
+                href: label_elem
+                    .attr("href")
+                    .and_then(|href| base_url.join(href).ok()),
+                ty: label_elem.attr("epub:type").map(|s| s.to_string()),
+            };
+
+            let point_order = *order;
+            *order += 1;
+
+            let children = li
+                .children()
+                .find(|n| n.name() == "ol")
+                .map(|ol| parse_nav_points(ol, base_url, order))
+                .unwrap_or_default();
+
+            Some(NavPoint {
+                label,
+                order: point_order,
+                children,
+            })
+        })
+        .collect()
+}
+
+/// Get all the text content of an element with white space collapsed.
+fn text_content(elem: &Element) -> String {
+    fn collect(elem: &Element, buf: &mut String) {
+        for node in elem.nodes() {
+            if let Some(text) = node.as_text() {
+                buf.push_str(text);
+            } else if let Some(child) = node.as_element() {
+                collect(child, buf);
+            }
+        }
+    }
+
+    let mut buf = String::new();
+    collect(elem, &mut buf);
+    buf.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Package {
+    /// Compute the [Cfi] of every nav point of the nav, in document order.
+    ///
+    /// The nav point is resolved to the position of its target in the spine, and if its href
+    /// has a fragment, to the path of the element with that ID. The nav points whose target
+    /// is not in the spine are skipped. If the element can not be found, the CFI refers to
+    /// the whole content document.
+    pub async fn nav_points_with_cfi<F: Files>(
+        &self,
+        files: &mut F,
+        nav: &Nav,
+    ) -> Vec<(NavPoint, Cfi)> {
+        let mut documents: BTreeMap<usize, Option<XHTML>> = BTreeMap::new();
+        let mut result = Vec::new();
+        for (_, point) in nav.flatten() {
+            let Some(href) = &point.label.href else {
+                continue;
+            };
+            let Some(res) = self.get_res_by_href(href) else {
+                continue;
+            };
+            let Some(spine_index) = self.spine.iter().position(|r| r.id == res.id) else {
+                continue;
+            };
+
+            let mut path = Vec::new();
+            if let Some(fragment) = href.fragment() {
+                if let Entry::Vacant(entry) = documents.entry(spine_index) {
+                    let xhtml = match files.get(&res.href).await {
+                        Some(data) => parse_xhtml(&decode_document(data)).ok(),
+                        None => None,
+                    };
+                    entry.insert(xhtml);
+                }
+                if let Some(Some(xhtml)) = documents.get(&spine_index) {
+                    path = element_path(xhtml.root(), fragment).unwrap_or_default();
+                }
+            }
+
+            result.push((point.clone(), Cfi { spine_index, path }));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::book::parse_book;
+    use crate::file::read_from_dir;
+    use crate::package::nav::{parse_nav, parse_nav_document, NavType};
+
+    const SAMPLE_NAV: &str = include_str!("../../res/sample/OEBPS/nav.xhtml");
 
-    unimplemented!()
+    #[test]
+    fn test_parse_nav() {
+        let data = r#"<nav xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" epub:type="toc">
+    <h2>Contents</h2>
+    <ol>
+        <li><a href="cover.xhtml">Cover</a></li>
+        <li><span>Part 1</span>
+            <ol>
+                <li><a href="chapter1.xhtml"><span>Chapter</span> 1</a></li>
+            </ol>
+        </li>
+    </ol>
+</nav>"#;
+        let base_url = Url::parse("epub:/OEBPS/nav.xhtml").unwrap();
+        let nav = parse_nav(data, &base_url).unwrap();
+
+        assert_eq!(nav.ty, "toc");
+        let title = nav.title.unwrap();
+        assert_eq!(title.text, "Contents");
+        assert_eq!(title.level, 2);
+
+        assert_eq!(nav.children.len(), 2);
+        assert_eq!(nav.children[1].label.text, "Part 1");
+        assert!(nav.children[1].label.href.is_none());
+
+        let chapter1 = &nav.children[1].children[0];
+        assert_eq!(chapter1.label.text, "Chapter 1");
+        assert_eq!(chapter1.order, 2);
+        assert_eq!(
+            chapter1.label.href.as_ref().unwrap().as_str(),
+            "epub:/OEBPS/chapter1.xhtml"
+        );
+    }
+
+    #[test]
+    fn test_parse_nav_document() {
+        let base_url = Url::parse("epub:/OEBPS/nav.xhtml").unwrap();
+        let nav_doc = parse_nav_document(SAMPLE_NAV, &base_url).unwrap();
+
+        assert_eq!(nav_doc.navs.len(), 3);
+
+        let toc = nav_doc.toc().unwrap();
+        assert_eq!(toc.children.len(), 4);
+        assert_eq!(toc.children[2].children.len(), 2);
+
+        let landmarks = nav_doc.landmarks().unwrap();
+        let types = landmarks
+            .children
+            .iter()
+            .map(|p| p.label.ty.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(types, vec!["cover", "toc", "bodymatter"]);
+        assert_eq!(
+            landmarks.children[1].label.href.as_ref().unwrap().as_str(),
+            "epub:/OEBPS/Text/contents.xhtml"
+        );
+
+        assert!(nav_doc.get(&NavType::PageList).is_some());
+    }
+
+    #[test]
+    fn test_page_list() {
+        let base_url = Url::parse("epub:/OEBPS/nav.xhtml").unwrap();
+        let nav_doc = parse_nav_document(SAMPLE_NAV, &base_url).unwrap();
+
+        let page_list = nav_doc.page_list().unwrap();
+        let pages = page_list
+            .children
+            .iter()
+            .map(|p| {
+                (
+                    p.label.text.as_str(),
+                    p.label.href.as_ref().unwrap().as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pages,
+            vec![
+                ("1", "epub:/OEBPS/Text/chapter1.xhtml#page1"),
+                ("2", "epub:/OEBPS/Text/chapter1.xhtml#page2"),
+                ("3", "epub:/OEBPS/Text/chapter2.xhtml#page3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let base_url = Url::parse("epub:/OEBPS/nav.xhtml").unwrap();
+        let nav_doc = parse_nav_document(SAMPLE_NAV, &base_url).unwrap();
+        let toc = nav_doc.toc().unwrap();
+
+        let points = toc.flatten();
+        assert_eq!(points.len(), 6);
+        assert_eq!(
+            points.iter().map(|(depth, _)| *depth).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1, 1, 0]
+        );
+        assert!(points
+            .windows(2)
+            .all(|pair| pair[0].1.order < pair[1].1.order));
+
+        let chapter1 = base_url.join("Text/chapter1.xhtml").unwrap();
+        let point = toc.find_by_href(&chapter1).unwrap();
+        assert_eq!(point.label.href.as_ref(), Some(&chapter1));
+
+        let in_chapter1 = base_url.join("Text/chapter1.xhtml#unknown").unwrap();
+        assert_eq!(toc.find_by_href(&in_chapter1).unwrap().order, point.order);
+        assert!(toc
+            .find_by_href(&base_url.join("missing.xhtml").unwrap())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nav_points_with_cfi() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.packages().first().unwrap();
+        let nav_url = package.nav_resource().unwrap().href.clone();
+        let nav_doc = parse_nav_document(SAMPLE_NAV, &nav_url).unwrap();
+
+        let points = package
+            .nav_points_with_cfi(&mut files, nav_doc.toc().unwrap())
+            .await;
+        let cfis = points
+            .iter()
+            .map(|(point, cfi)| (point.label.text.as_str(), cfi.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cfis,
+            vec![
+                ("封面", "epubcfi(/6/2)".to_string()),
+                ("目录", "epubcfi(/6/4)".to_string()),
+                ("第一章 魔女的旅途", "epubcfi(/6/6)".to_string()),
+                ("第一节", "epubcfi(/6/6!/4/2/6[section1])".to_string()),
+                ("第二节", "epubcfi(/6/6!/4/2/14[section2])".to_string()),
+                ("第二章 Welcome Home", "epubcfi(/6/8)".to_string()),
+            ]
+        );
+    }
 }
@@ -1,20 +1,23 @@
 use std::marker::PhantomData;
 use std::str::FromStr;
 
+use chrono::DateTime;
 use minidom::Element;
 use thiserror::Error;
 use url::Url;
 
+use crate::package::guide::GuideReference;
 use crate::package::manifest::{Manifest, ManifestCheckError, Resource};
+use crate::package::media_type::MediaType;
 use crate::package::metadata::{
-    Link, Meta, Metadata, MetadataCheckError, MetadataElement, Refines,
+    LegacyMeta, Link, Meta, Metadata, MetadataCheckError, MetadataElement, Refines,
 };
 use crate::package::prefix::prefixes::*;
 use crate::package::prefix::{Prefixes, PrefixesStack};
-use crate::package::property::{Properties, Property, WithNamespace};
+use crate::package::property::{NamespaceError, Properties, Property, WithNamespace};
 use crate::package::spine::{Spine, SpineReference};
-use crate::package::Package;
-use crate::utils::invert;
+use crate::package::{Package, Version};
+use crate::utils::{invert, strip_bom, url_extension};
 
 #[derive(Debug, Error)]
 pub enum PackageError {
@@ -33,8 +36,23 @@ pub enum PackageError {
     #[error("throw error when checking metadata: {0}")]
     MetadataCheckError(#[from] MetadataCheckError),
 
-    #[error("Unsupported version: {0}, only support 3.0")]
+    #[error("Unsupported version: {0}, only support 2.0 and 3.0")]
     UnsupportedVersion(String),
+
+    #[error(
+        "unknown prefix '{}' in {attr} '{value}'",
+        prefix.as_deref().unwrap_or_default()
+    )]
+    UnknownPrefix {
+        /// The prefix that is not declared, `None` for the default namespace.
+        prefix: Option<String>,
+
+        /// The name of the attribute.
+        attr: String,
+
+        /// The value of the attribute.
+        value: String,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -44,12 +62,106 @@ pub struct PackageParseOptions {
     /// every url in the package document will be resolved against this url.
     pub base_url: Url,
 
+    /// root url of the container, like `epub:/` or `http://host/book/`.
+    ///
+    /// path-absolute manifest hrefs like `/OEBPS/Images/x.png` are resolved against it,
+    /// see [PackageParseOptions::with_root_url]. if it is `None`, they are resolved against `base_url`.
+    pub root_url: Option<Url>,
+
+    /// the prefixes resolved without a declaration in the `prefix` attribute of the package,
+    /// the reserved ones by default, see [PackageParseOptions::with_reserved_prefix].
     pub reserved_prefixes: Prefixes,
+
+    /// normalize the media types of the manifest items and links, see [MediaType::normalized].
+    pub normalize_media_types: bool,
+
+    /// only accept RFC 3339 values for `dcterms:modified`.
+    ///
+    /// if it is `false`, some common non-conformant forms are accepted, see [parse_date_time](crate::package::metadata::parse_date_time).
+    pub strict_dates: bool,
+
+    /// fail on the manifest children that are not `item` elements.
+    ///
+    /// if it is `false`, they are skipped and reported in [ParseState::warnings], like vendor extension elements.
+    pub strict_manifest: bool,
+
+    /// fail on EPUB 3 package documents without a `dcterms:modified` meta, as the spec requires.
+    ///
+    /// if it is `false`, [Metadata::last_modified] is `None` for them. EPUB 2 package documents never require it.
+    pub require_modified: bool,
+
+    /// infer the media type of the manifest items without one from the extension of their hrefs.
+    ///
+    /// if it is `false`, a missing `media-type` fails the parse. The inferred ones are reported
+    /// in [ParseState::warnings], see [MediaType::from_extension].
+    pub infer_media_types: bool,
+}
+
+impl PackageParseOptions {
+    /// Create parse options with the reserved prefixes and the default settings.
+    pub fn new(base_url: Url) -> Self {
+        PackageParseOptions {
+            base_url,
+            root_url: None,
+            reserved_prefixes: Prefixes::reserved(),
+            normalize_media_types: true,
+            strict_dates: false,
+            strict_manifest: false,
+            require_modified: true,
+            infer_media_types: false,
+        }
+    }
+
+    /// Treat a prefix as reserved, like a house vocabulary such as `calibre:`.
+    ///
+    /// The `prefix` attribute of the package still takes precedence over it.
+    pub fn with_reserved_prefix(mut self, name: &str, uri: &str) -> Self {
+        self.reserved_prefixes
+            .insert(Some(name.to_string()), uri.to_string());
+        self
+    }
+
+    /// Resolve path-absolute hrefs against the root of the container instead of the origin of `base_url`.
+    pub fn with_root_url(mut self, root_url: Url) -> Self {
+        self.root_url = Some(root_url);
+        self
+    }
+
+    /// Resolve a manifest href, a leading `/` refers to the root of the container.
+    fn resolve_href(&self, href: &str) -> Result<Url, url::ParseError> {
+        match &self.root_url {
+            Some(root_url) if href.starts_with('/') && !href.starts_with("//") => {
+                root_url.join(href.trim_start_matches('/'))
+            }
+            _ => self.base_url.join(href),
+        }
+    }
+
+    fn parse_media_type(&self, s: &str) -> Result<MediaType, ()> {
+        if self.normalize_media_types {
+            Ok(MediaType::normalized(s))
+        } else {
+            s.parse()
+        }
+    }
+}
+
+/// The metadata of a package document with its cover image, see [PackageParser::parse_metadata_only].
+#[derive(Debug, Clone)]
+pub struct PackageMetadata {
+    /// The metadata of the package.
+    pub metadata: Metadata,
+
+    /// The manifest item of the cover image.
+    pub cover: Option<Resource>,
 }
 
 #[derive(Debug)]
 pub struct ParseState {
     pub prefixes_stack: PrefixesStack,
+
+    /// The recoverable problems found by the last parse, like skipped manifest elements.
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -68,6 +180,7 @@ impl PackageParser {
             options,
             parse_state: ParseState {
                 prefixes_stack: PrefixesStack::default(),
+                warnings: Vec::new(),
             },
             _private: Default::default(),
         }
@@ -76,21 +189,55 @@ impl PackageParser {
     /// Clear the parser state.
     pub fn clear(&mut self) {
         self.parse_state.prefixes_stack.clear();
+        self.parse_state.warnings.clear();
     }
 
     /// Parse a package document.
     ///
     /// # Arguments
     ///
-    /// - `str` - A string slice that holds the package document.
+    /// - `str` - A string slice that holds the package document, a leading byte order mark is ignored.
     pub fn parse(&mut self, str: &str) -> Result<Package, PackageError> {
+        let package_elem = self.parse_root(str)?;
+        let res = self.parse_package(&package_elem);
+        self.parse_state.prefixes_stack.pop();
+        res
+    }
+
+    /// Parse only the metadata of a package document and its cover image, skipping the manifest and the spine.
+    ///
+    /// It is meant for cataloguing a library, where only the titles, the creators, the cover
+    /// and so on are needed. The document is parsed only up to the end tag of the manifest,
+    /// so the spine, the guide and the rest are neither parsed nor checked.
+    /// The whole document is parsed if the manifest has no end tag, or the cut document is not well-formed.
+    ///
+    /// The cover is resolved like [Package::cover_resource]: the manifest item with the `cover-image`
+    /// property, or the one the EPUB 2 `cover` meta refers to. Only that item is parsed,
+    /// and it is `None` if the manifest is missing.
+    ///
+    /// # Arguments
+    ///
+    /// - `str` - A string slice that holds the package document, a leading byte order mark is ignored.
+    pub fn parse_metadata_only(&mut self, str: &str) -> Result<PackageMetadata, PackageError> {
+        let str = strip_bom(str);
+        let package_elem = match truncate_after_manifest(str).map(|head| self.parse_root(&head)) {
+            Some(Ok(package_elem)) => package_elem,
+            _ => self.parse_root(str)?,
+        };
+        let res = self.parse_package_metadata(&package_elem);
+        self.parse_state.prefixes_stack.pop();
+        res
+    }
+
+    /// Parse the root element of a package document, and push the prefixes it declares.
+    fn parse_root(&mut self, str: &str) -> Result<Element, PackageError> {
         self.clear();
         self.parse_state
             .prefixes_stack
             .push(self.options.reserved_prefixes.clone());
 
         let root = Element::from_reader_with_prefixes(
-            str.as_bytes(),
+            strip_bom(str).as_bytes(),
             self.options.reserved_prefixes.inner().clone(),
         )
         .map_err(PackageError::ParseError)?;
@@ -101,67 +248,111 @@ impl PackageParser {
             ));
         }
 
-        let package_elem = root;
-
-        let prefixes = package_elem.prefixes.declared_prefixes().clone();
+        let prefixes = root.prefixes.declared_prefixes().clone();
         self.parse_state
             .prefixes_stack
             .push(Prefixes::new(prefixes));
+        Ok(root)
+    }
 
-        let res = self.parse_package(&package_elem);
+    /// Parse the metadata and the cover of a package element to [PackageMetadata].
+    fn parse_package_metadata(
+        &mut self,
+        package_elem: &Element,
+    ) -> Result<PackageMetadata, PackageError> {
+        let version = parse_version(package_elem)?;
+        let prefix: Option<String> = parse_attr(package_elem, "prefix")?;
+        self.parse_state
+            .prefixes_stack
+            .push(declared_prefixes(prefix.as_deref()));
+
+        let res = find_child(package_elem, "metadata")
+            .and_then(|metadata_elem| self.parse_metadata(metadata_elem, version))
+            .and_then(|metadata| {
+                let cover = match package_elem.children().find(|n| n.name() == "manifest") {
+                    Some(manifest_elem) => self.parse_cover_item(manifest_elem, &metadata)?,
+                    None => None,
+                };
+                Ok(PackageMetadata { metadata, cover })
+            });
         self.parse_state.prefixes_stack.pop();
         res
     }
 
+    /// Parse the manifest item of the cover image, see [PackageParser::parse_metadata_only].
+    fn parse_cover_item(
+        &mut self,
+        manifest_elem: &Element,
+        metadata: &Metadata,
+    ) -> Result<Option<Resource>, PackageError> {
+        let items = manifest_elem
+            .children()
+            .filter(|elem| elem.name() == "item")
+            .collect::<Vec<_>>();
+        let has_cover_property = |elem: &Element| {
+            elem.attr("properties")
+                .is_some_and(|properties| properties.split_whitespace().any(|p| p == "cover-image"))
+        };
+        let legacy_id = metadata.legacy_meta("cover");
+        let cover_elem = items
+            .iter()
+            .find(|elem| has_cover_property(elem))
+            .or_else(|| {
+                let legacy_id = legacy_id?;
+                items.iter().find(|elem| elem.attr("id") == Some(legacy_id))
+            });
+        let Some(elem) = cover_elem else {
+            return Ok(None);
+        };
+
+        let elem_prefixes = elem.prefixes.declared_prefixes().clone();
+        self.parse_state
+            .prefixes_stack
+            .push(Prefixes::new(elem_prefixes));
+        let res = self.parse_manifest_elem(elem);
+        self.parse_state.prefixes_stack.pop();
+        res.map(Some)
+    }
+
     /// Parse a package element to [Package].
     fn parse_package(&mut self, package_elem: &Element) -> Result<Package, PackageError> {
         // get unique-identifier
-        let unique_identifier_ref = parse_attr_some::<String>(&package_elem, "unique-identifier")?;
+        let unique_identifier_ref = parse_attr_some::<String>(package_elem, "unique-identifier")?;
 
         // get version
-        let version = parse_attr_some(&package_elem, "version")?;
-
-        if version != "3.0" {
-            return Err(PackageError::UnsupportedVersion(version));
-        }
+        let version = parse_version(package_elem)?;
 
         // get more attributes
-        let prefix = parse_attr(&package_elem, "prefix")?;
-        let dir = parse_attr(&package_elem, "dir")?;
-        let id = parse_attr(&package_elem, "id")?;
-        let lang = parse_attr(&package_elem, "xml:lang")?;
+        let prefix: Option<String> = parse_attr(package_elem, "prefix")?;
+        let dir = parse_attr(package_elem, "dir")?;
+        let id = parse_attr(package_elem, "id")?;
+        let lang = parse_attr(package_elem, "xml:lang")?;
 
-        // get metadata
-        let metadata_elem = package_elem
-            .children()
-            .find(|n| n.name() == "metadata")
-            .ok_or(PackageError::InvalidElementError(
-                "metadata is missing".to_string(),
-            ))?;
+        // the prefixes declared by the prefix attribute apply to the whole package
+        self.parse_state
+            .prefixes_stack
+            .push(declared_prefixes(prefix.as_deref()));
 
-        let metadata = self.parse_metadata(metadata_elem)?;
+        // get metadata
+        let metadata = self.parse_metadata(find_child(package_elem, "metadata")?, version)?;
 
         // get manifest
-        let manifest_elem = package_elem
-            .children()
-            .find(|n| n.name() == "manifest")
-            .ok_or(PackageError::InvalidElementError(
-                "manifest is missing".to_string(),
-            ))?;
-
-        let manifest = self.parse_manifest(manifest_elem)?;
+        let manifest = self.parse_manifest(find_child(package_elem, "manifest")?, version)?;
 
         // get spine
-        let spine_elem = package_elem
+        let spine = self.parse_spine(find_child(package_elem, "spine")?)?;
+
+        // the guide is optional
+        let guide = package_elem
             .children()
-            .find(|n| n.name() == "spine")
-            .ok_or(PackageError::InvalidElementError(
-                "spine is missing".to_string(),
-            ))?;
+            .find(|n| n.name() == "guide")
+            .map(|guide_elem| self.parse_guide(guide_elem))
+            .unwrap_or_default();
 
-        let spine = self.parse_spine(spine_elem)?;
+        self.parse_state.prefixes_stack.pop();
 
         Ok(Package {
+            base_url: self.options.base_url.clone(),
             unique_identifier_ref,
             version,
             prefix,
@@ -171,14 +362,20 @@ impl PackageParser {
             metadata,
             manifest,
             spine,
+            guide,
         })
     }
 
     /// Parse a metadata element to [Metadata].
-    fn parse_metadata(&mut self, metadata_elem: &Element) -> Result<Metadata, PackageError> {
+    fn parse_metadata(
+        &mut self,
+        metadata_elem: &Element,
+        version: Version,
+    ) -> Result<Metadata, PackageError> {
         let mut elems = Vec::new();
         let mut metas = Vec::new();
         let mut links = Vec::new();
+        let mut legacy_metas = Vec::new();
 
         let metadata_prefixes = metadata_elem.prefixes.declared_prefixes().clone();
         self.parse_state
@@ -191,15 +388,37 @@ impl PackageParser {
                 .prefixes_stack
                 .push(Prefixes::new(elem_prefixes));
 
-            let res = self.parse_metadata_elem(elem, &mut elems, &mut metas, &mut links);
+            let res = self.parse_metadata_elem(
+                elem,
+                &mut elems,
+                &mut metas,
+                &mut links,
+                &mut legacy_metas,
+            );
             self.parse_state.prefixes_stack.pop();
             res?
         }
 
-        Ok(Metadata::new(elems, metas, links)?)
+        if self.options.strict_dates {
+            if let Some(modified) = metas
+                .iter()
+                .find(|meta| meta.property.is(&DCTERMS, "modified"))
+            {
+                DateTime::parse_from_rfc3339(&modified.value)
+                    .map_err(MetadataCheckError::DateParseError)?;
+            }
+        }
+
+        let mut metadata = match version {
+            Version::V3 if self.options.require_modified => Metadata::new(elems, metas, links)?,
+            _ => Metadata::new_epub2(elems, metas, links)?,
+        }
+        .with_base_url(self.options.base_url.clone());
+        metadata.legacy_metas = legacy_metas;
+        Ok(metadata)
     }
 
-    /// Parse a metadata element to [MetadataElement], [Meta] or [Link].
+    /// Parse a metadata element to [MetadataElement], [Meta], [Link] or [LegacyMeta].
     /// And add them to the corresponding vector.
     fn parse_metadata_elem(
         &self,
@@ -207,20 +426,29 @@ impl PackageParser {
         elems: &mut Vec<MetadataElement>,
         metas: &mut Vec<Meta>,
         links: &mut Vec<Link>,
+        legacy_metas: &mut Vec<LegacyMeta>,
     ) -> Result<(), PackageError> {
         match elem.name() {
+            // EPUB 2 meta element, like `<meta name="cover" content="cover-image"/>`
+            "meta" if elem.attr("property").is_none() && elem.attr("name").is_some() => {
+                let name = parse_attr_some(elem, "name")?;
+                let content = parse_attr(elem, "content")?.unwrap_or_default();
+                legacy_metas.push(LegacyMeta { name, content });
+                Ok(())
+            }
+
             // meta element
             "meta" => {
                 let id = parse_attr(elem, "id")?;
                 let lang = parse_attr(elem, "xml:lang")?;
                 let dir = parse_attr(elem, "dir")?;
-                let property = parse_attr_some_fn(elem, "property", |s| {
+                let property = parse_ns_attr_some(elem, "property", |s| {
                     Property::from_str(s, &self.parse_state.prefixes_stack)
                 })?;
                 let refines = parse_attr_fn(elem, "refines", |s| {
                     Refines::from_relative_url(s, &self.options.base_url)
                 })?;
-                let scheme = parse_attr_fn(elem, "scheme", |s| {
+                let scheme = parse_ns_attr(elem, "scheme", |s| {
                     Property::from_str(s, &self.parse_state.prefixes_stack)
                 })?;
                 let value = elem.text();
@@ -242,11 +470,12 @@ impl PackageParser {
                 let id = parse_attr(elem, "id")?;
                 let href = parse_attr_some_fn(elem, "href", |s| self.options.base_url.join(s))?;
                 let hreflang = parse_attr(elem, "hreflang")?;
-                let rel = parse_attr_some_fn(elem, "rel", |s| {
+                let rel = parse_ns_attr_some(elem, "rel", |s| {
                     Properties::from_str(s, &self.parse_state.prefixes_stack)
                 })?;
-                let media_type = parse_attr(elem, "media-type")?;
-                let property = parse_attr_fn(elem, "properties", |s| {
+                let media_type =
+                    parse_attr_fn(elem, "media-type", |s| self.options.parse_media_type(s))?;
+                let property = parse_ns_attr(elem, "properties", |s| {
                     Property::from_str(s, &self.parse_state.prefixes_stack)
                 })?;
                 let refines = parse_attr_fn(elem, "refines", |s| {
@@ -279,11 +508,27 @@ impl PackageParser {
                         reference: elem.name().to_string(),
                     };
 
+                    let value = elem.text();
+
+                    // attributes in other namespaces, like `opf:event`,
+                    // the ones with an undeclared prefix are ignored
+                    let attrs = elem
+                        .attrs()
+                        .filter(|(name, _)| name.contains(':') && !name.starts_with("xml:"))
+                        .filter_map(|(name, value)| {
+                            WithNamespace::from_str(name, &self.parse_state.prefixes_stack)
+                                .ok()
+                                .map(|name| (name, value.to_string()))
+                        })
+                        .collect();
+
                     elems.push(MetadataElement {
                         id,
                         lang,
                         dir,
                         tag_name,
+                        value,
+                        attrs,
                     });
                     Ok(())
                 } else {
@@ -297,27 +542,39 @@ impl PackageParser {
     }
 
     /// Parse a manifest element to [Manifest].
-    fn parse_manifest(&mut self, manifest_elem: &Element) -> Result<Manifest, PackageError> {
+    fn parse_manifest(
+        &mut self,
+        manifest_elem: &Element,
+        version: Version,
+    ) -> Result<Manifest, PackageError> {
         let id = manifest_elem.attr("id");
-        let resources = manifest_elem
-            .children()
-            .map(|elem| {
-                let elem_prefixes = elem.prefixes.declared_prefixes().clone();
+        let mut resources = Vec::new();
+        for elem in manifest_elem.children() {
+            if elem.name() != "item" && !self.options.strict_manifest {
                 self.parse_state
-                    .prefixes_stack
-                    .push(Prefixes::new(elem_prefixes));
+                    .warnings
+                    .push(format!("skipped manifest element <{}>", elem.name()));
+                continue;
+            }
 
-                let res = self.parse_manifest_elem(elem);
-                self.parse_state.prefixes_stack.pop();
-                res
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+            let elem_prefixes = elem.prefixes.declared_prefixes().clone();
+            self.parse_state
+                .prefixes_stack
+                .push(Prefixes::new(elem_prefixes));
+
+            let res = self.parse_manifest_elem(elem);
+            self.parse_state.prefixes_stack.pop();
+            resources.push(res?);
+        }
 
-        Ok(Manifest::new(id, resources)?)
+        match version {
+            Version::V2 => Ok(Manifest::new_epub2(id, resources)?),
+            Version::V3 => Ok(Manifest::new(id, resources)?),
+        }
     }
 
     /// Parse a manifest item element to [Resource].
-    fn parse_manifest_elem(&self, elem: &Element) -> Result<Resource, PackageError> {
+    fn parse_manifest_elem(&mut self, elem: &Element) -> Result<Resource, PackageError> {
         if elem.name() != "item" {
             return Err(PackageError::InvalidElementError(
                 "Invalid manifest item".to_string(),
@@ -325,9 +582,26 @@ impl PackageParser {
         }
 
         let id = parse_attr_some(elem, "id")?;
-        let href = parse_attr_some_fn(elem, "href", |s| self.options.base_url.join(s))?;
-        let media_type = parse_attr_some(elem, "media-type")?;
-        let properties = parse_attr_fn(elem, "properties", |s| {
+        let href = parse_attr_some_fn(elem, "href", |s| self.options.resolve_href(s))?;
+        let media_type = match elem.attr("media-type") {
+            None if self.options.infer_media_types => {
+                let media_type = url_extension(&href)
+                    .and_then(MediaType::from_extension)
+                    .ok_or_else(|| {
+                        PackageError::InvalidElementAttrError(format!(
+                            "media-type is missing and can not be inferred from {}",
+                            href
+                        ))
+                    })?;
+                self.parse_state.warnings.push(format!(
+                    "inferred media type {} of manifest item {}",
+                    media_type, id
+                ));
+                media_type
+            }
+            _ => parse_attr_some_fn(elem, "media-type", |s| self.options.parse_media_type(s))?,
+        };
+        let properties = parse_ns_attr(elem, "properties", |s| {
             Properties::from_str(s, &self.parse_state.prefixes_stack)
         })?;
         let fallback = parse_attr(elem, "fallback")?;
@@ -347,6 +621,7 @@ impl PackageParser {
     fn parse_spine(&mut self, spine_elem: &Element) -> Result<Spine, PackageError> {
         let id = parse_attr(spine_elem, "id")?;
         let dir = parse_attr(spine_elem, "page-progression-direction")?;
+        let toc = parse_attr(spine_elem, "toc")?;
         let refs = spine_elem
             .children()
             .map(|elem| {
@@ -361,7 +636,24 @@ impl PackageParser {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Spine { id, dir, refs })
+        Ok(Spine { id, dir, toc, refs })
+    }
+
+    /// Parse a guide element to its references.
+    ///
+    /// The guide is deprecated, so the references without a `type` or a valid `href` are skipped.
+    fn parse_guide(&self, guide_elem: &Element) -> Vec<GuideReference> {
+        guide_elem
+            .children()
+            .filter(|elem| elem.name() == "reference")
+            .filter_map(|elem| {
+                Some(GuideReference {
+                    ty: elem.attr("type")?.to_string(),
+                    title: elem.attr("title").map(|title| title.to_string()),
+                    href: self.options.base_url.join(elem.attr("href")?).ok()?,
+                })
+            })
+            .collect()
     }
 
     /// Parse a spine itemref element to [SpineReference].
@@ -373,12 +665,69 @@ impl PackageParser {
         }
 
         let id = parse_attr_some(elem, "idref")?;
-        let linear = parse_attr(elem, "linear")?;
+        let linear = parse_attr_fn(elem, "linear", |s| match s {
+            "yes" => Ok(true),
+            "no" => Ok(false),
+            _ => Err(()),
+        })?
+        .unwrap_or(true);
+        let properties = parse_ns_attr(elem, "properties", |s| {
+            Properties::from_str(s, &self.parse_state.prefixes_stack)
+        })?;
 
-        Ok(SpineReference { id, linear })
+        Ok(SpineReference {
+            id,
+            linear,
+            properties,
+        })
     }
 }
 
+/// Parse the `version` attribute of the package element.
+/// Cut a package document after the end tag of its manifest, and close the root element.
+///
+/// It returns `None` if the manifest has no end tag, like an empty `<manifest/>`.
+fn truncate_after_manifest(str: &str) -> Option<String> {
+    let root_start = str
+        .match_indices('<')
+        .map(|(i, _)| i + 1)
+        .find(|&i| !matches!(str.as_bytes().get(i), Some(b'?' | b'!')))?;
+    let root_name = str[root_start..]
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()?;
+
+    // the end tag may have a namespace prefix, like `</opf:manifest>`
+    let end = str.match_indices("</").find_map(|(i, _)| {
+        let name_start = i + "</".len();
+        let name_end = name_start + str[name_start..].find('>')?;
+        let name = str[name_start..name_end].trim_end();
+        (name.rsplit(':').next() == Some("manifest")).then_some(name_end + 1)
+    })?;
+    Some(format!("{}</{}>", &str[..end], root_name))
+}
+
+fn parse_version(package_elem: &Element) -> Result<Version, PackageError> {
+    let version_str = parse_attr_some::<String>(package_elem, "version")?;
+    version_str
+        .parse::<Version>()
+        .map_err(|_| PackageError::UnsupportedVersion(version_str))
+}
+
+/// The prefixes declared by the `prefix` attribute of the package element.
+fn declared_prefixes(prefix: Option<&str>) -> Prefixes {
+    prefix
+        .map(Prefixes::from_prefix_attr)
+        .unwrap_or_else(|| Prefixes::new(Default::default()))
+}
+
+/// Find a required child element of the package element, like `metadata` or `spine`.
+fn find_child<'a>(package_elem: &'a Element, name: &str) -> Result<&'a Element, PackageError> {
+    package_elem
+        .children()
+        .find(|n| n.name() == name)
+        .ok_or_else(|| PackageError::InvalidElementError(format!("{} is missing", name)))
+}
+
 fn parse_attr<T>(elem: &Element, name: &str) -> Result<Option<T>, PackageError>
 where
     T: FromStr,
@@ -397,14 +746,6 @@ where
     })
 }
 
-fn parse_attr_primitive<'a>(elem: &'a Element, name: &str) -> Result<&'a str, PackageError> {
-    elem.attr(name)
-        .ok_or(PackageError::InvalidElementAttrError(format!(
-            "{} is missing",
-            name
-        )))
-}
-
 fn parse_attr_some<T>(elem: &Element, name: &str) -> Result<T, PackageError>
 where
     T: FromStr,
@@ -425,3 +766,359 @@ where
         PackageError::InvalidElementAttrError(format!("{} is invalid: {}", name, attr_str.unwrap()))
     })
 }
+
+/// Parse an attribute holding properties, reporting the unknown prefix if it fails.
+fn parse_ns_attr<T, F>(elem: &Element, name: &str, f: F) -> Result<Option<T>, PackageError>
+where
+    F: FnOnce(&str) -> Result<T, NamespaceError>,
+{
+    elem.attr(name)
+        .map(|value| {
+            f(value).map_err(|err| PackageError::UnknownPrefix {
+                prefix: err.prefix().map(|prefix| prefix.to_string()),
+                attr: name.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .transpose()
+}
+
+fn parse_ns_attr_some<T, F>(elem: &Element, name: &str, f: F) -> Result<T, PackageError>
+where
+    F: FnOnce(&str) -> Result<T, NamespaceError>,
+{
+    parse_ns_attr(elem, name, f)?.ok_or(PackageError::InvalidElementAttrError(format!(
+        "{} is missing",
+        name
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use url::Url;
+
+    use crate::package::manifest::ManifestCheckError;
+    use crate::package::media_type::media_types::{JPG, PNG};
+    use crate::package::metadata::MetadataCheckError;
+    use crate::package::parser::{
+        truncate_after_manifest, PackageError, PackageMetadata, PackageParseOptions, PackageParser,
+    };
+    use crate::package::{Direction, Package};
+
+    fn parse_with_manifest(manifest: &str, options: PackageParseOptions) -> Package {
+        let opf = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" unique-identifier="uid" xmlns="http://www.idpf.org/2007/opf">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="uid">urn:uuid:1234</dc:identifier>
+        <dc:title>Title</dc:title>
+        <dc:language>en</dc:language>
+        <meta property="dcterms:modified">2024-08-13T04:09:43Z</meta>
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+        {manifest}
+    </manifest>
+    <spine>
+        <itemref idref="nav"/>
+    </spine>
+</package>"#
+        );
+        PackageParser::new(options).parse(&opf).unwrap()
+    }
+
+    fn options() -> PackageParseOptions {
+        PackageParseOptions::new(Url::parse("epub:/OEBPS/content.opf").unwrap())
+    }
+
+    #[test]
+    fn test_normalize_media_types() {
+        let manifest = r#"<item id="cover" href="cover.jpg" media-type="Image/JPEG"/>"#;
+
+        let package = parse_with_manifest(manifest, options());
+        let cover = package.get_res_by_id("cover").unwrap();
+        assert!(cover.media_type.is_core_media_type());
+        assert_eq!(&cover.media_type, JPG.deref());
+        assert_eq!(cover.media_type.raw(), "Image/JPEG");
+
+        let package = parse_with_manifest(
+            manifest,
+            PackageParseOptions {
+                normalize_media_types: false,
+                ..options()
+            },
+        );
+        let cover = package.get_res_by_id("cover").unwrap();
+        assert!(!cover.media_type.is_core_media_type());
+    }
+
+    #[test]
+    fn test_parse_bom() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let package = PackageParser::new(options())
+            .parse(&format!("\u{FEFF}{}", opf))
+            .unwrap();
+        assert_eq!(package.metadata.titles()[0].value, "魔女之旅 5");
+    }
+
+    #[test]
+    fn test_package_prefix_attr() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let package = PackageParser::new(options()).parse(opf).unwrap();
+
+        let timestamp = package
+            .metadata
+            .metas
+            .iter()
+            .find(|meta| meta.property.reference == "timestamp")
+            .unwrap();
+        assert_eq!(timestamp.property.ns, "https://calibre-ebook.com");
+        assert_eq!(timestamp.value, "2024-08-13T04:09:43+00:00");
+    }
+
+    #[test]
+    fn test_unknown_prefix() {
+        let opf = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" unique-identifier="uid" xmlns="http://www.idpf.org/2007/opf">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="uid">urn:uuid:1234</dc:identifier>
+        <dc:title>Title</dc:title>
+        <dc:language>en</dc:language>
+        <meta property="calibre:timestamp">2024-08-13T04:09:43+00:00</meta>
+        <meta property="dcterms:modified">2024-08-13T04:09:43Z</meta>
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    </manifest>
+    <spine>
+        <itemref idref="nav"/>
+    </spine>
+</package>"#;
+
+        let err = PackageParser::new(options()).parse(opf).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown prefix 'calibre' in property 'calibre:timestamp'"
+        );
+
+        let options = options().with_reserved_prefix("calibre", "https://calibre-ebook.com");
+        let package = PackageParser::new(options).parse(opf).unwrap();
+        assert_eq!(
+            package.metadata.metas[0].property.ns,
+            "https://calibre-ebook.com"
+        );
+    }
+
+    #[test]
+    fn test_skip_unknown_manifest_elements() {
+        let manifest = r#"<item id="extra" href="Text/extra.xhtml" media-type="application/xhtml+xml"/>
+        <vendor:extension xmlns:vendor="https://example.com/vendor"/>"#;
+
+        let mut parser = PackageParser::new(options());
+        let opf = include_str!("../../res/sample/OEBPS/content.opf")
+            .replace("<manifest>", &format!("<manifest>{}", manifest));
+        let package = parser.parse(&opf).unwrap();
+        assert!(package.get_res_by_id("extra").is_some());
+        assert_eq!(
+            parser.parse_state.warnings,
+            vec!["skipped manifest element <extension>"]
+        );
+
+        let mut strict = options();
+        strict.strict_manifest = true;
+        let err = PackageParser::new(strict).parse(&opf).unwrap_err();
+        assert!(matches!(err, PackageError::InvalidElementError(_)));
+    }
+
+    #[test]
+    fn test_infer_media_types() {
+        let manifest = r#"<item id="extra" href="Images/extra.PNG"/>"#;
+        let opf = include_str!("../../res/sample/OEBPS/content.opf")
+            .replace("<manifest>", &format!("<manifest>{}", manifest));
+
+        let err = PackageParser::new(options()).parse(&opf).unwrap_err();
+        assert!(matches!(err, PackageError::InvalidElementAttrError(_)));
+
+        let mut lenient = options();
+        lenient.infer_media_types = true;
+        let mut parser = PackageParser::new(lenient);
+        let package = parser.parse(&opf).unwrap();
+        let extra = package.get_res_by_id("extra").unwrap();
+        assert_eq!(extra.extension(), Some("PNG"));
+        assert_eq!(&extra.media_type, PNG.deref());
+        assert_eq!(
+            parser.parse_state.warnings,
+            vec!["inferred media type image/png of manifest item extra"]
+        );
+
+        let opf = opf.replace("extra.PNG", "extra.bin");
+        let err = parser.parse(&opf).unwrap_err();
+        assert!(matches!(err, PackageError::InvalidElementAttrError(_)));
+    }
+
+    #[test]
+    fn test_parse_direction() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf")
+            .replace(r#"<package "#, r#"<package dir="rtl" "#)
+            .replace(
+                r#"<dc:title id="title">"#,
+                r#"<dc:title id="title" dir="auto">"#,
+            );
+        let package = PackageParser::new(options()).parse(&opf).unwrap();
+        assert_eq!(package.dir, Some(Direction::Rtl));
+        assert!(package.dir.unwrap().is_rtl());
+        assert_eq!(package.metadata.titles()[0].dir, Some(Direction::Auto));
+
+        let opf = opf.replace(r#"dir="rtl""#, r#"dir="right""#);
+        let err = PackageParser::new(options()).parse(&opf).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid element attribute: dir is invalid: right"
+        );
+    }
+
+    #[test]
+    fn test_equivalent_hrefs() {
+        let manifest = r#"<item id="x" href="./Images/x.png" media-type="image/png"/>
+        <item id="y" href="/OEBPS/Images/y.png" media-type="image/png"/>"#;
+        let opf = include_str!("../../res/sample/OEBPS/content.opf")
+            .replace("<manifest>", &format!("<manifest>{}", manifest));
+        let package = PackageParser::new(options()).parse(&opf).unwrap();
+
+        for (id, href) in [("x", "Images/x.png"), ("y", "Images/y.png")] {
+            let url = package.base_url.join(href).unwrap();
+            assert_eq!(package.get_res_by_id(id).unwrap().href, url);
+            assert_eq!(package.get_res_by_href(&url).unwrap().id, id);
+        }
+
+        // path-absolute hrefs are resolved against the root of the container, not the host
+        let base_url = Url::parse("http://host/book/OEBPS/content.opf").unwrap();
+        let root_url = Url::parse("http://host/book/").unwrap();
+        let remote_options = PackageParseOptions::new(base_url).with_root_url(root_url);
+        let remote = PackageParser::new(remote_options).parse(&opf).unwrap();
+        let url = Url::parse("http://host/book/OEBPS/Images/y.png").unwrap();
+        assert_eq!(remote.get_res_by_id("y").unwrap().href, url);
+        assert_eq!(remote.get_res_by_href(&url).unwrap().id, "y");
+
+        // the same file can not be declared twice with equivalent hrefs
+        let opf = opf.replace(
+            "<manifest>",
+            r#"<manifest><item id="z" href="Images/./x.png" media-type="image/png"/>"#,
+        );
+        let err = PackageParser::new(options()).parse(&opf).unwrap_err();
+        assert!(matches!(
+            err,
+            PackageError::ManifestCheckError(ManifestCheckError::DeduplicatedHref(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_metadata_only() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let mut parser = PackageParser::new(options());
+        let PackageMetadata { metadata, cover } = parser.parse_metadata_only(opf).unwrap();
+        let package = parser.parse(opf).unwrap();
+        assert_eq!(metadata.metas, package.metadata.metas);
+        assert_eq!(metadata.last_modified, package.metadata.last_modified);
+        assert_eq!(metadata.titles()[0].value, "魔女之旅 5");
+        assert_eq!(cover.as_ref(), package.cover_resource());
+        assert_eq!(cover.unwrap().id, "cover-image");
+
+        // the EPUB 2 cover meta
+        let opf2 = include_str!("../../res/sample-epub2/OEBPS/content.opf").replace(
+            "<manifest>",
+            r#"<manifest><item id="cover-image" href="Images/cover.jpg" media-type="image/jpeg"/>"#,
+        );
+        let base_url = Url::parse("epub:/OEBPS/content.opf").unwrap();
+        let mut parser2 = PackageParser::new(PackageParseOptions::new(base_url));
+        let cover = parser2.parse_metadata_only(&opf2).unwrap().cover;
+        assert_eq!(cover.unwrap().id, "cover-image");
+
+        // the manifest and the spine are not checked
+        let start = opf.find("<manifest>").unwrap();
+        let opf = format!("{}</package>", &opf[..start]);
+        assert!(parser.parse(&opf).is_err());
+        let only = parser.parse_metadata_only(&opf).unwrap();
+        assert!(only.cover.is_none());
+    }
+
+    #[test]
+    fn test_parse_metadata_only_skips_spine() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let mut parser = PackageParser::new(options());
+
+        // the spine is not even parsed as XML
+        let broken = opf.replace("<spine>", "<spine><broken");
+        assert!(parser.parse(&broken).is_err());
+        let only = parser.parse_metadata_only(&broken).unwrap();
+        assert_eq!(only.cover.unwrap().id, "cover-image");
+
+        // prefixed elements
+        let prefixed = opf
+            .replace(
+                "<package ",
+                "<opf:package xmlns:opf=\"http://www.idpf.org/2007/opf\" ",
+            )
+            .replace("</package>", "</opf:package>")
+            .replace("<manifest>", "<opf:manifest>")
+            .replace("</manifest>", "</opf:manifest>")
+            .replace("<spine>", "<spine><broken");
+        let head = truncate_after_manifest(&prefixed).unwrap();
+        assert!(head.ends_with("</opf:manifest></opf:package>"));
+        assert!(!head.contains("<spine>"));
+    }
+
+    #[test]
+    fn test_require_modified() {
+        let opf = include_str!("../../res/sample/OEBPS/content.opf");
+        let start = opf.find("<meta property=\"dcterms:modified\"").unwrap();
+        let end = start + opf[start..].find("</meta>").unwrap() + "</meta>".len();
+        let opf = format!("{}{}", &opf[..start], &opf[end..]);
+
+        let err = PackageParser::new(options()).parse(&opf).unwrap_err();
+        assert!(matches!(
+            err,
+            PackageError::MetadataCheckError(MetadataCheckError::MissingLastModifiedError(_))
+        ));
+
+        let mut lenient = options();
+        lenient.require_modified = false;
+        let package = PackageParser::new(lenient).parse(&opf).unwrap();
+        assert!(package.metadata.last_modified.is_none());
+    }
+
+    #[test]
+    fn test_strict_dates() {
+        let opf = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package version="3.0" unique-identifier="uid" xmlns="http://www.idpf.org/2007/opf">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="uid">urn:uuid:1234</dc:identifier>
+        <dc:title>Title</dc:title>
+        <dc:language>en</dc:language>
+        <meta property="dcterms:modified">2011-01-01</meta>
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    </manifest>
+    <spine>
+        <itemref idref="nav"/>
+    </spine>
+</package>"#;
+
+        let package = PackageParser::new(options()).parse(opf).unwrap();
+        assert_eq!(
+            package.metadata.last_modified.unwrap().to_rfc3339(),
+            "2011-01-01T00:00:00+00:00"
+        );
+
+        let mut strict = options();
+        strict.strict_dates = true;
+        let err = PackageParser::new(strict).parse(opf).unwrap_err();
+        assert!(matches!(
+            err,
+            PackageError::MetadataCheckError(MetadataCheckError::DateParseError(_))
+        ));
+    }
+}
@@ -3,6 +3,7 @@ use std::ops::{Deref, DerefMut};
 
 use crate::package::prefix::prefixes::*;
 use once_cell::sync::Lazy;
+use url::{ParseError, Url};
 
 /// A map of prefixes to namespaces.
 ///
@@ -13,12 +14,25 @@ pub trait PrefixMap {
     /// # Arguments
     ///
     /// - `prefix` - The prefix to get the namespace URI for.
-    /// If the prefix is `None`, the default namespace is returned.
+    ///   If the prefix is `None`, the default namespace is returned.
     ///
     /// # Returns
     ///
     /// The namespace URI for the given prefix, or `None` if the prefix is not found.
     fn get(&self, prefix: &Option<String>) -> Option<&String>;
+
+    /// Get a prefix for a given namespace URI, the reverse of [PrefixMap::get].
+    ///
+    /// The default namespace is preferred when it matches, which is returned as `Some(None)`.
+    ///
+    /// # Returns
+    ///
+    /// The prefix whose namespace URI is `uri`, or `None` if the namespace is not found.
+    /// It returns `None` by default, for maps that can not be searched by namespace.
+    fn find_prefix(&self, uri: &str) -> Option<Option<&String>> {
+        let _ = uri;
+        None
+    }
 }
 
 /// A prefix for a namespace.
@@ -53,6 +67,30 @@ pub struct Prefix {
     pub uri: String,
 }
 
+impl Prefix {
+    /// Create a prefix, checking that the namespace is an absolute URI.
+    ///
+    /// The `name` is `None` for the default namespace. The URI is kept as it is given,
+    /// so it can be compared with the namespaces declared in documents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::package::prefix::Prefix;
+    ///
+    /// let calibre = Prefix::try_new(Some("calibre"), "https://calibre-ebook.com").unwrap();
+    /// assert_eq!(calibre.uri, "https://calibre-ebook.com");
+    /// assert!(Prefix::try_new(Some("vendor"), "not a uri").is_err());
+    /// ```
+    pub fn try_new(name: Option<&str>, uri: &str) -> Result<Self, ParseError> {
+        Url::parse(uri)?;
+        Ok(Prefix {
+            name: name.map(|name| name.to_string()),
+            uri: uri.to_string(),
+        })
+    }
+}
+
 pub mod prefixes {
     use once_cell::sync::Lazy;
 
@@ -147,6 +185,14 @@ impl PrefixMap for Prefixes {
     fn get(&self, prefix: &Option<String>) -> Option<&String> {
         self.0.get(prefix)
     }
+
+    fn find_prefix(&self, uri: &str) -> Option<Option<&String>> {
+        // `None` is ordered first, so the default namespace is preferred
+        self.0
+            .iter()
+            .find(|(_, ns)| ns.as_str() == uri)
+            .map(|(name, _)| name.as_ref())
+    }
 }
 
 impl Prefixes {
@@ -164,6 +210,28 @@ impl Prefixes {
         RESERVED.clone().into()
     }
 
+    /// Parse the `prefix` attribute of the package element.
+    ///
+    /// The attribute is a whitespace-separated list of `name: uri` pairs,
+    /// like `"calibre: https://calibre-ebook.com foaf: http://xmlns.com/foaf/spec/"`.
+    /// Malformed pairs, including the ones whose URI is not absolute, are skipped.
+    pub fn from_prefix_attr(s: &str) -> Self {
+        let mut prefixes = BTreeMap::new();
+        let mut tokens = s.split_whitespace();
+        while let Some(name) = tokens.next() {
+            let Some(name) = name.strip_suffix(':') else {
+                continue;
+            };
+            let Some(uri) = tokens.next() else {
+                break;
+            };
+            if let Ok(prefix) = Prefix::try_new(Some(name), uri) {
+                prefixes.insert(prefix.name, prefix.uri);
+            }
+        }
+        Prefixes(prefixes)
+    }
+
     /// Get the inner map of prefixes to namespaces.
     pub fn inner(&self) -> &PrefixesInner {
         &self.0
@@ -190,24 +258,18 @@ impl From<Prefixes> for BTreeMap<Option<String>, String> {
     }
 }
 
-impl Into<Prefixes> for BTreeMap<Option<String>, String> {
-    fn into(self) -> Prefixes {
-        Prefixes(self)
+impl From<BTreeMap<Option<String>, String>> for Prefixes {
+    fn from(value: BTreeMap<Option<String>, String>) -> Self {
+        Prefixes(value)
     }
 }
 
 /// A stack of prefixes.
 ///
 /// It is used to record the prefixes declared in the XML document tree.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct PrefixesStack(Vec<Prefixes>);
 
-impl Default for PrefixesStack {
-    fn default() -> Self {
-        PrefixesStack(vec![])
-    }
-}
-
 impl Deref for PrefixesStack {
     type Target = Vec<Prefixes>;
 
@@ -242,4 +304,19 @@ impl PrefixMap for PrefixesStack {
         }
         None
     }
+
+    /// Get a prefix for a given namespace URI.
+    ///
+    /// The prefixes on the top of the stack are preferred,
+    /// and a prefix is skipped if it is redefined for another namespace above.
+    fn find_prefix(&self, uri: &str) -> Option<Option<&String>> {
+        self.0
+            .iter()
+            .rev()
+            .flat_map(|prefixes| prefixes.iter())
+            .filter(|(_, ns)| ns.as_str() == uri)
+            .map(|(name, _)| name)
+            .find(|name| self.get(name).map(String::as_str) == Some(uri))
+            .map(Option::as_ref)
+    }
 }
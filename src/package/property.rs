@@ -1,18 +1,26 @@
 use std::cmp::PartialEq;
-use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 use thiserror::Error;
 
+use crate::package::prefix::prefixes::*;
 use crate::package::prefix::{Prefix, PrefixMap};
 
 #[derive(Debug, Error)]
 #[error("Invalid namespace: {0:?}")]
 pub struct NamespaceError(Option<String>);
 
+impl NamespaceError {
+    /// The prefix that is not found, `None` for the default namespace.
+    pub fn prefix(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
 /// A value with a namespace.
 ///
 /// It can be used to represent a property or a tag name.
 #[derive(Debug, PartialEq, Clone, Hash, Eq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WithNamespace {
     /// The namespace of the value.
     pub ns: String,
@@ -34,6 +42,22 @@ impl WithNamespace {
         WithNamespace::new(prefix.uri.clone(), reference)
     }
 
+    /// Check if the value is `reference` in the namespace of `prefix`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::package::prefix::prefixes::DCTERMS;
+    /// use eparser::package::prefix::Prefixes;
+    /// use eparser::package::property::WithNamespace;
+    ///
+    /// let with_ns = WithNamespace::from_str("dcterms:modified", &Prefixes::reserved()).unwrap();
+    /// assert!(with_ns.is(&DCTERMS, "modified"));
+    /// ```
+    pub fn is(&self, prefix: &Prefix, reference: &str) -> bool {
+        self.ns == prefix.uri && self.reference == reference
+    }
+
     /// Create a new WithNamespace from a string.
     ///
     /// The string must have the format `prefix:reference` or `reference`.
@@ -52,7 +76,8 @@ impl WithNamespace {
     /// # Examples
     ///
     /// ```
-    /// use eparser::package::prefix::{Prefix, PrefixMap, Prefixes, DC};
+    /// use eparser::package::prefix::prefixes::DC;
+    /// use eparser::package::prefix::Prefixes;
     /// use eparser::package::property::WithNamespace;
     ///
     /// fn main() {
@@ -67,22 +92,54 @@ impl WithNamespace {
     /// }
     /// ```
     pub fn from_str(s: &str, prefixes: &impl PrefixMap) -> Result<Self, NamespaceError> {
-        let split: Vec<&str> = s.split(':').collect();
-        let prefix = if split.len() == 2 {
-            Some(split[0].to_string())
-        } else {
-            None
+        // only the first colon separates the prefix, the reference may contain colons
+        let (prefix, reference) = match s.split_once(':') {
+            Some((prefix, reference)) => (Some(prefix.to_string()), reference.to_string()),
+            None => (None, s.to_string()),
         };
         let namespace = prefixes
             .get(&prefix)
             .ok_or(NamespaceError(prefix.clone()))?
             .clone();
-        let reference = split.last().unwrap().to_string();
         Ok(WithNamespace {
             ns: namespace,
             reference,
         })
     }
+
+    /// Get the `prefix:reference` form of the value, the reverse of [WithNamespace::from_str].
+    ///
+    /// It finds a prefix for the namespace in the `prefixes` map, see [PrefixMap::find_prefix].
+    /// The prefix is omitted for the default namespace.
+    ///
+    /// Returns `None` if no prefix is found for the namespace, since
+    /// the value could not be read back by [WithNamespace::from_str].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::package::prefix::prefixes::{DCTERMS, OPF};
+    /// use eparser::package::prefix::Prefixes;
+    /// use eparser::package::property::WithNamespace;
+    ///
+    /// let mut prefixes = Prefixes::reserved();
+    /// prefixes.insert(OPF.name.clone(), OPF.uri.clone());
+    ///
+    /// let modified = WithNamespace::from_prefix(&DCTERMS, "modified".to_string());
+    /// assert_eq!(modified.to_display_string(&prefixes).unwrap(), "dcterms:modified");
+    ///
+    /// let nav = WithNamespace::from_prefix(&OPF, "nav".to_string());
+    /// assert_eq!(nav.to_display_string(&prefixes).unwrap(), "nav");
+    ///
+    /// let unknown = WithNamespace::new("http://example.com/ns#".to_string(), "foo".to_string());
+    /// assert!(unknown.to_display_string(&prefixes).is_none());
+    /// ```
+    pub fn to_display_string(&self, prefixes: &impl PrefixMap) -> Option<String> {
+        match prefixes.find_prefix(&self.ns)? {
+            Some(name) => Some(format!("{}:{}", name, self.reference)),
+            None => Some(self.reference.clone()),
+        }
+    }
 }
 
 /// The property data type is a compact means of expressing a URL and
@@ -95,6 +152,7 @@ impl WithNamespace {
 ///
 /// [EPUB 3.3 SPEC property-datatype](https://www.w3.org/TR/epub-33/#sec-property-datatype)
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Property(WithNamespace);
 
 impl Deref for Property {
@@ -128,10 +186,36 @@ impl Property {
     pub fn from_str(s: &str, prefixes: &impl PrefixMap) -> Result<Self, NamespaceError> {
         Ok(Property(WithNamespace::from_str(s, prefixes)?))
     }
+
+    /// Create a new Property in the default `opf` namespace, e.g. `nav`, `cover-image`.
+    pub fn opf(reference: &str) -> Self {
+        Property::from_prefix(&OPF, reference.to_string())
+    }
+
+    /// Create a new Property in the `dcterms` namespace, e.g. `dcterms:modified`.
+    pub fn dcterms(reference: &str) -> Self {
+        Property::from_prefix(&DCTERMS, reference.to_string())
+    }
+
+    /// Create a new Property in the `rendition` namespace, e.g. `rendition:layout`.
+    pub fn rendition(reference: &str) -> Self {
+        Property::from_prefix(&RENDITION, reference.to_string())
+    }
+
+    /// Create a new Property in the `media` namespace, e.g. `media:duration`.
+    pub fn media(reference: &str) -> Self {
+        Property::from_prefix(&MEDIA, reference.to_string())
+    }
+
+    /// Create a new Property in the `schema` namespace, e.g. `schema:accessMode`.
+    pub fn schema(reference: &str) -> Self {
+        Property::from_prefix(&SCHEMA, reference.to_string())
+    }
 }
 
 /// A white space-separated list of property values.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Properties(Vec<Property>);
 
 impl Properties {
@@ -153,6 +237,18 @@ impl Properties {
         Ok(Properties(properties))
     }
 
+    /// Get the whitespace-separated form of the properties, the reverse of [Properties::from_str].
+    ///
+    /// See [WithNamespace::to_display_string] for the form of each property.
+    /// Returns `None` if no prefix is found for the namespace of any property.
+    pub fn to_display_string(&self, prefixes: &impl PrefixMap) -> Option<String> {
+        let properties = self
+            .iter()
+            .map(|property| property.to_display_string(prefixes))
+            .collect::<Option<Vec<_>>>()?;
+        Some(properties.join(" "))
+    }
+
     /// Check if the properties contains a property.
     pub fn contains(&self, property: &Property) -> bool {
         self.iter().any(|p| p == property)
@@ -172,3 +268,57 @@ impl DerefMut for Properties {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::package::prefix::prefixes::{MARC, OPF};
+    use crate::package::prefix::{Prefixes, PrefixesStack};
+    use crate::package::property::{Properties, Property, WithNamespace};
+
+    #[test]
+    fn test_from_str_with_colons() {
+        let mut prefixes = Prefixes::reserved();
+        prefixes.insert(
+            Some("foo".to_string()),
+            "http://example.com/foo#".to_string(),
+        );
+        prefixes.insert(OPF.name.clone(), OPF.uri.clone());
+
+        let with_ns = WithNamespace::from_str("foo:bar:baz", &prefixes).unwrap();
+        assert_eq!(with_ns.ns, "http://example.com/foo#");
+        assert_eq!(with_ns.reference, "bar:baz");
+
+        let with_ns = WithNamespace::from_str("marc:relators", &prefixes).unwrap();
+        assert!(with_ns.is(&MARC, "relators"));
+
+        let with_ns = WithNamespace::from_str("nav", &prefixes).unwrap();
+        assert!(with_ns.is(&OPF, "nav"));
+
+        let err = WithNamespace::from_str("bar:baz:qux", &prefixes).unwrap_err();
+        assert_eq!(err.prefix(), Some("bar"));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut prefixes = Prefixes::reserved();
+        prefixes.insert(OPF.name.clone(), OPF.uri.clone());
+
+        let s = "nav rendition:layout-pre-paginated marc:relators";
+        let properties = Properties::from_str(s, &prefixes).unwrap();
+        assert_eq!(properties.to_display_string(&prefixes).unwrap(), s);
+
+        // the prefix declared above shadows the outer one
+        let outer = Prefixes::from_prefix_attr("foo: http://example.com/foo#");
+        let inner =
+            Prefixes::from_prefix_attr("foo: http://example.com/bar# baz: http://example.com/foo#");
+        let stack = PrefixesStack::new(vec![outer.clone(), inner]);
+        let property = Property::from_str("foo:x", &outer).unwrap();
+        assert_eq!(property.to_display_string(&stack).unwrap(), "baz:x");
+
+        let unknown = Property::new("http://example.com/qux#".to_string(), "x".to_string());
+        assert!(unknown.to_display_string(&stack).is_none());
+        let properties = Properties::new(vec![property.clone(), unknown]);
+        assert!(properties.to_display_string(&stack).is_none());
+        assert_eq!(Property::from_str("baz:x", &stack).unwrap(), property);
+    }
+}
@@ -0,0 +1,301 @@
+use std::ops::Deref;
+
+use url::Url;
+
+use crate::charset::decode_document;
+use crate::css::css_urls;
+use crate::file::Files;
+use crate::package::manifest::Resource;
+use crate::package::media_type::media_types::CSS;
+use crate::package::Package;
+use crate::utils::{file_key, is_under};
+use crate::xhtml::parse_xhtml;
+
+impl Package {
+    /// Check if a resource is referenced by a content document, the nav document or a style sheet.
+    ///
+    /// The content documents of the spine, the nav document and the CSS resources of the manifest are scanned.
+    /// The documents that can not be found or parsed are skipped.
+    pub async fn is_referenced<F: Files>(&self, files: &mut F, res: &Resource) -> bool {
+        let mut target = res.href.clone();
+        target.set_fragment(None);
+
+        let documents = self
+            .spine
+            .iter()
+            .filter_map(|spine_ref| self.get_res_by_ref(spine_ref))
+            .chain(self.nav_resource())
+            .map(|doc| doc.href.clone())
+            .collect::<Vec<_>>();
+
+        for href in documents {
+            if href == target {
+                continue;
+            }
+            if let Some(refs) = document_refs(files, &href).await {
+                if refs.contains(&target) {
+                    return true;
+                }
+            }
+        }
+
+        let style_sheets = self
+            .manifest
+            .iter()
+            .filter(|r| &r.media_type == CSS.deref() && r.href != target)
+            .map(|r| r.href.clone())
+            .collect::<Vec<_>>();
+
+        for href in style_sheets {
+            let Some(data) = files.get(&href).await else {
+                continue;
+            };
+            let Ok(str) = std::str::from_utf8(data) else {
+                continue;
+            };
+            if css_urls(str, &href).contains(&target) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Get the resources of the manifest whose file is not found in `files`.
+    ///
+    /// Remote resources, whose href is not under the root URL of `files`, are not checked.
+    pub async fn missing_resources<F: Files>(&self, files: &mut F) -> Vec<&Resource> {
+        let root_url = files.root_url().clone();
+        let mut missing = Vec::new();
+        for res in self.manifest.iter() {
+            if !is_under(&root_url, &res.href) {
+                continue;
+            }
+            if files.get(&res.href).await.is_none() {
+                missing.push(res);
+            }
+        }
+        missing
+    }
+
+    /// Load the reading-order content of the publication into `files`, for offline reading.
+    ///
+    /// The nav document, the linear content documents of the spine, the resources
+    /// they reference and the resources referenced by those style sheets are prefetched and got,
+    /// so that they are held by the cache of `files`.
+    /// Remote resources, whose href is not under the root URL of `files`, are not loaded.
+    ///
+    /// It returns the URLs of the files that can not be found.
+    pub async fn pin_for_offline<F: Files>(&self, files: &mut F) -> Vec<Url> {
+        let root_url = files.root_url().clone();
+        let mut pinned = Vec::new();
+        let mut failed = Vec::new();
+
+        let documents = self
+            .nav_resource()
+            .into_iter()
+            .chain(
+                self.spine
+                    .linear_refs()
+                    .filter_map(|spine_ref| self.get_res_by_ref(spine_ref)),
+            )
+            .map(|doc| file_key(&doc.href))
+            .fold(Vec::new(), |mut urls, url| {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+                urls
+            });
+        files.prefetch(&documents).await;
+
+        let mut refs = Vec::new();
+        for href in documents {
+            match document_refs(files, &href).await {
+                Some(doc_refs) => refs.extend(doc_refs.iter().map(file_key)),
+                None if files.get(&href).await.is_none() => failed.push(href.clone()),
+                None => {}
+            }
+            pinned.push(href);
+        }
+
+        let mut pending = refs;
+        while !pending.is_empty() {
+            let urls = pending
+                .drain(..)
+                .filter(|url| is_under(&root_url, url))
+                .fold(Vec::new(), |mut urls, url| {
+                    if !pinned.contains(&url) && !urls.contains(&url) {
+                        urls.push(url);
+                    }
+                    urls
+                });
+            files.prefetch(&urls).await;
+
+            for url in urls {
+                let is_css = self
+                    .get_res_by_href(&url)
+                    .is_some_and(|res| &res.media_type == CSS.deref());
+                match files.get(&url).await {
+                    Some(data) if is_css => {
+                        if let Ok(str) = std::str::from_utf8(data) {
+                            pending.extend(css_urls(str, &url).iter().map(file_key));
+                        }
+                    }
+                    Some(_) => {}
+                    None => failed.push(url.clone()),
+                }
+                pinned.push(url);
+            }
+        }
+
+        failed
+    }
+}
+
+async fn document_refs<F: Files>(files: &mut F, href: &Url) -> Option<Vec<Url>> {
+    let data = files.get(href).await?;
+    let xhtml = parse_xhtml(&decode_document(data)).ok()?;
+    Some(xhtml.resource_refs(href))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use url::Url;
+
+    use crate::book::parse_book;
+    use crate::file::{lazy_read_from_dir, read_from_dir, MemoryFiles, OnEntry};
+    use crate::package::builder::PackageBuilder;
+    use crate::package::manifest::Resource;
+    use crate::package::media_type::MediaType;
+    use crate::package::tests::parse_opf;
+
+    const SAMPLE_OPF: &str = include_str!("../../res/sample/OEBPS/content.opf");
+
+    #[tokio::test]
+    async fn test_is_referenced() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.packages().first().unwrap();
+
+        // referenced by the `@font-face` rule of the style sheet
+        let font = package.get_res_by_id("font").unwrap();
+        assert!(package.is_referenced(&mut files, font).await);
+
+        // referenced by the `img` element of the cover
+        let cover = package.get_res_by_id("cover-image").unwrap();
+        assert!(package.is_referenced(&mut files, cover).await);
+
+        let nav = package.nav_resource().unwrap();
+        assert!(!package.is_referenced(&mut files, nav).await);
+    }
+
+    #[tokio::test]
+    async fn test_missing_resources() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.primary_package().unwrap();
+        assert!(package.missing_resources(&mut files).await.is_empty());
+
+        let resource = |id: &str, href: &str, media_type: &str| Resource {
+            id: id.to_string(),
+            href: Url::parse(href).unwrap(),
+            media_type: MediaType::new(media_type),
+            fallback: None,
+            media_overlay: None,
+            properties: None,
+        };
+        let package = PackageBuilder::new("urn:uuid:1234")
+            .title("Title")
+            .language("en")
+            .nav(resource(
+                "nav",
+                "epub:/OEBPS/nav.xhtml",
+                "application/xhtml+xml",
+            ))
+            .resource(resource("image", "epub:/OEBPS/image.png", "image/png"))
+            .resource(resource(
+                "audio",
+                "https://example.com/audio.mp3",
+                "audio/mpeg",
+            ))
+            .build()
+            .unwrap();
+
+        let mut files = MemoryFiles::empty();
+        files.insert("OEBPS/nav.xhtml", Vec::new()).unwrap();
+        let missing = package.missing_resources(&mut files).await;
+        assert_eq!(
+            missing
+                .iter()
+                .map(|res| res.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["image"]
+        );
+
+        // a sibling directory sharing the prefix of the root is not under it
+        let package = PackageBuilder::new("urn:uuid:1234")
+            .title("Title")
+            .language("en")
+            .nav(resource(
+                "nav",
+                "http://host/book/OEBPS/nav.xhtml",
+                "application/xhtml+xml",
+            ))
+            .resource(resource(
+                "image",
+                "http://host/book/OEBPS/image.png",
+                "image/png",
+            ))
+            .resource(resource(
+                "shelf",
+                "http://host/bookshelf/image.png",
+                "image/png",
+            ))
+            .build()
+            .unwrap();
+        let mut files = MemoryFiles::new(Url::parse("http://host/book").unwrap());
+        files.insert("book/OEBPS/nav.xhtml", Vec::new()).unwrap();
+        let missing = package.missing_resources(&mut files).await;
+        assert_eq!(
+            missing
+                .iter()
+                .map(|res| res.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["image"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pin_for_offline() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample");
+        let package = parse_opf(&SAMPLE_OPF.replace(
+            r#"<itemref idref="cover"/>"#,
+            r#"<itemref idref="cover" linear="no"/>"#,
+        ));
+
+        let loaded = Arc::new(Mutex::new(Vec::new()));
+        let on_entry = loaded.clone();
+        let mut lazy = lazy_read_from_dir(path)
+            .unwrap()
+            .with_on_entry(OnEntry::new(move |url, _, _| {
+                on_entry.lock().unwrap().push(url.clone());
+            }));
+        assert!(package.pin_for_offline(&mut lazy).await.is_empty());
+        let loaded = loaded.lock().unwrap().clone();
+        for id in ["nav", "contents", "chapter1", "style", "font"] {
+            let href = &package.get_res_by_id(id).unwrap().href;
+            assert!(loaded.contains(href), "{id} is not pinned");
+        }
+        // only referenced by the cover, which is not linear
+        let cover = &package.get_res_by_id("cover-image").unwrap().href;
+        assert!(!loaded.contains(cover));
+
+        let mut files = MemoryFiles::empty();
+        files.insert("OEBPS/nav.xhtml", Vec::new()).unwrap();
+        let failed = package.pin_for_offline(&mut files).await;
+        assert!(failed.contains(&Url::parse("epub:/OEBPS/Text/chapter1.xhtml").unwrap()));
+        assert!(!failed.contains(&Url::parse("epub:/OEBPS/nav.xhtml").unwrap()));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::charset::decode_document;
+use crate::file::Files;
+use crate::package::Package;
+use crate::xhtml::{count_words, parse_xhtml};
+
+/// A block of text of a content document, used to build a search index.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndexEntry {
+    /// The index of the content document in the [Spine](crate::package::spine::Spine).
+    pub spine_index: usize,
+
+    /// The byte offset of the text in the chapter text.
+    ///
+    /// The chapter text is the one returned by [XHTML::body_text](crate::xhtml::XHTML::body_text).
+    pub offset: usize,
+
+    /// The text of the block.
+    pub text: String,
+}
+
+impl Package {
+    /// Extract the text blocks of every content document in the spine.
+    ///
+    /// The documents that can not be found or parsed are skipped.
+    pub async fn index_entries<F: Files>(&self, files: &mut F) -> Vec<IndexEntry> {
+        let mut entries = Vec::new();
+        for (spine_index, spine_ref) in self.spine.iter().enumerate() {
+            let Some(res) = self.get_res_by_ref(spine_ref) else {
+                continue;
+            };
+            let Some(data) = files.get(&res.href).await else {
+                continue;
+            };
+            let Ok(xhtml) = parse_xhtml(&decode_document(data)) else {
+                continue;
+            };
+
+            let mut offset = 0;
+            for text in xhtml.body_text_blocks() {
+                let len = text.len();
+                entries.push(IndexEntry {
+                    spine_index,
+                    offset,
+                    text,
+                });
+                // blocks are joined by a line break
+                offset += len + 1;
+            }
+        }
+        entries
+    }
+}
+
+impl Package {
+    /// Count the words of every content document in the spine, see [count_words].
+    ///
+    /// The counts are paired with the idref of the spine item, in spine order.
+    /// The documents that can not be found or parsed count as 0 words.
+    /// See [Package::total_words] for the total.
+    pub async fn word_counts<F: Files>(&self, files: &mut F) -> Vec<(String, usize)> {
+        let mut counts = Vec::new();
+        for spine_ref in self.spine.iter() {
+            let mut count = 0;
+            if let Some(res) = self.get_res_by_ref(spine_ref) {
+                if let Some(data) = files.get(&res.href).await {
+                    if let Ok(xhtml) = parse_xhtml(&decode_document(data)) {
+                        count = count_words(&xhtml.body_text());
+                    }
+                }
+            }
+            counts.push((spine_ref.id.clone(), count));
+        }
+        counts
+    }
+
+    /// Count the words of the whole publication, the sum of [Package::word_counts].
+    pub async fn total_words<F: Files>(&self, files: &mut F) -> usize {
+        self.word_counts(files)
+            .await
+            .iter()
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::parse_book;
+    use crate::file::read_from_dir;
+    use crate::xhtml::count_words;
+
+    #[tokio::test]
+    async fn test_index_entries() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.packages().first().unwrap();
+
+        let entries = package.index_entries(&mut files).await;
+        assert!(!entries.is_empty());
+
+        for pair in entries.windows(2) {
+            if pair[0].spine_index == pair[1].spine_index {
+                assert!(pair[0].offset < pair[1].offset);
+            } else {
+                assert!(pair[0].spine_index < pair[1].spine_index);
+                assert_eq!(pair[1].offset, 0);
+            }
+        }
+
+        let chapter1 = entries.iter().find(|e| e.text == "第一节").unwrap();
+        assert_eq!(chapter1.spine_index, 2);
+    }
+
+    #[test]
+    fn test_count_words() {
+        assert_eq!(count_words("Hello, world! It's fine."), 4);
+        assert_eq!(count_words("第一章 魔女的旅途"), 8);
+        assert_eq!(count_words("ひらがなとカタカナ"), 9);
+        assert_eq!(count_words("第二章 Welcome Home。End"), 6);
+        assert_eq!(count_words("  "), 0);
+    }
+
+    #[tokio::test]
+    async fn test_word_counts() {
+        let mut files = read_from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/res/sample")).unwrap();
+        let book = parse_book(&mut files).await.unwrap();
+        let package = book.primary_package().unwrap();
+
+        let counts = package.word_counts(&mut files).await;
+        let ids = counts.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["cover", "contents", "chapter1", "chapter2"]);
+        let words = counts.iter().map(|(_, count)| *count).collect::<Vec<_>>();
+        // the note reference is inline, so `country.1` is one word
+        assert_eq!(words, vec![0, 15, 46, 14]);
+        assert_eq!(package.total_words(&mut files).await, 75);
+    }
+}
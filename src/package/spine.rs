@@ -1,19 +1,50 @@
 use std::ops::{Deref, DerefMut};
 
+use crate::package::prefix::Prefix;
+use crate::package::property::Properties;
+
 /// The itemref element associates an item with a spine.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpineReference {
     /// Reference to the resource in the manifest by its ID.
     pub id: String,
 
-    /// The linear property of the spine reference.
-    pub linear: Option<bool>,
+    /// The `linear` attribute of the spine reference.
+    ///
+    /// The non-linear items, like footnotes, are supplementary content which is not part
+    /// of the default reading order. It is `true` if the attribute is absent.
+    pub linear: bool,
+
+    /// The properties attribute of the spine reference, like `page-spread-left`
+    /// or `rendition:layout-pre-paginated`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub properties: Option<Properties>,
+}
+
+impl SpineReference {
+    /// Create a linear reference to the resource with the given id.
+    pub fn new(id: &str) -> Self {
+        SpineReference {
+            id: id.to_string(),
+            linear: true,
+            properties: None,
+        }
+    }
+
+    /// Check if the reference has the property `reference` in the namespace of `prefix`.
+    pub fn has_property(&self, prefix: &Prefix, reference: &str) -> bool {
+        self.properties
+            .as_ref()
+            .is_some_and(|properties| properties.iter().any(|p| p.is(prefix, reference)))
+    }
 }
 
 /// The spine element defines the default reading order of the publication.
 ///
 /// It is made up of a list of spine references that point to resources in the manifest.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spine {
     /// The unique identifier of the spine element.
     pub id: Option<String>,
@@ -21,10 +52,32 @@ pub struct Spine {
     /// The direction of the primary text progression in the spine.
     pub dir: Option<String>,
 
+    /// The `toc` attribute, the id of the NCX resource in the manifest.
+    ///
+    /// It is used by EPUB 2 publications, and kept by EPUB 3 ones for backward compatibility.
+    pub toc: Option<String>,
+
     /// The list of spine references.
     pub refs: Vec<SpineReference>,
 }
 
+impl Spine {
+    /// The references in the default reading order, the ones whose `linear` is `true`.
+    pub fn linear_refs(&self) -> impl Iterator<Item = &SpineReference> {
+        self.refs.iter().filter(|spine_ref| spine_ref.linear)
+    }
+
+    /// The position of the first reference to the resource with the given id.
+    pub fn position_of(&self, idref: &str) -> Option<usize> {
+        self.refs.iter().position(|spine_ref| spine_ref.id == idref)
+    }
+
+    /// The references to the supplementary content, the ones whose `linear` is `false`.
+    pub fn auxiliary_refs(&self) -> impl Iterator<Item = &SpineReference> {
+        self.refs.iter().filter(|spine_ref| !spine_ref.linear)
+    }
+}
+
 impl Deref for Spine {
     type Target = Vec<SpineReference>;
 
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use minidom::Element;
+use url::Url;
+
+use crate::package::prefix::prefixes::OPF;
+use crate::package::prefix::{Prefixes, RESERVED};
+use crate::package::property::{Properties, WithNamespace};
+use crate::package::Package;
+
+/// Namespace to prefix lookup used to write properties back in the `prefix:reference` form.
+struct PrefixWriter {
+    /// namespace to prefix
+    names: BTreeMap<String, Option<String>>,
+
+    /// prefixes that are not reserved and need to be declared in the `prefix` attribute
+    declared: Vec<(String, String)>,
+}
+
+impl PrefixWriter {
+    fn new(package_prefix: Option<&str>) -> Self {
+        let mut writer = PrefixWriter {
+            names: BTreeMap::new(),
+            declared: Vec::new(),
+        };
+        writer.names.insert(OPF.uri.clone(), None);
+        for (name, uri) in RESERVED.iter() {
+            writer.names.entry(uri.clone()).or_insert(name.clone());
+        }
+
+        // the declarations of the package are kept as they are
+        let declared = Prefixes::from_prefix_attr(package_prefix.unwrap_or_default());
+        for (name, uri) in declared.iter() {
+            if let Some(name) = name {
+                writer.declare(name.clone(), uri.clone());
+            }
+        }
+        writer
+    }
+
+    fn declare(&mut self, name: String, uri: String) {
+        self.names.insert(uri.clone(), Some(name.clone()));
+        self.declared.push((name, uri));
+    }
+
+    /// Get the `prefix:reference` form, declaring a new prefix for an unknown namespace.
+    fn write(&mut self, value: &WithNamespace) -> String {
+        if !self.names.contains_key(&value.ns) {
+            let name = format!("ns{}", self.declared.len() + 1);
+            self.declare(name, value.ns.clone());
+        }
+        match &self.names[&value.ns] {
+            Some(name) => format!("{}:{}", name, value.reference),
+            None => value.reference.clone(),
+        }
+    }
+
+    fn write_all(&mut self, properties: &Properties) -> String {
+        properties
+            .iter()
+            .map(|property| self.write(property))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Get the XML namespace prefix of an attribute in the namespace `ns`,
+    /// the OPF namespace is written with the conventional `opf` prefix.
+    fn xml_prefix(&self, ns: &str, index: usize) -> String {
+        if ns == OPF.uri {
+            return "opf".to_string();
+        }
+        match self.names.get(ns) {
+            Some(Some(name)) => name.clone(),
+            _ => format!("ns{}", index + 1),
+        }
+    }
+
+    /// The value of the `prefix` attribute.
+    fn prefix_attr(&self) -> Option<String> {
+        if self.declared.is_empty() {
+            return None;
+        }
+        let prefix = self
+            .declared
+            .iter()
+            .map(|(name, uri)| format!("{}: {}", name, uri))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(prefix)
+    }
+}
+
+/// Get the URL relative to the package document, as it is written in the package document.
+fn relative(base_url: &Url, url: &Url) -> String {
+    base_url
+        .make_relative(url)
+        .unwrap_or_else(|| url.to_string())
+}
+
+impl Package {
+    /// Serialize the package to an EPUB 3 package document.
+    ///
+    /// The hrefs are written relative to [Package::base_url], and the properties
+    /// whose namespace is not reserved get a prefix declared in the `prefix` attribute.
+    /// Parsing the output again produces an equivalent package.
+    pub fn to_opf_string(&self) -> String {
+        let mut prefixes = PrefixWriter::new(self.prefix.as_deref());
+        let base_url = &self.base_url;
+
+        // metadata
+        let mut metadata = Element::builder("metadata", OPF.uri.as_str()).build();
+        for elem in self.metadata.elems.values().flatten() {
+            let mut builder =
+                Element::builder(elem.tag_name.reference.as_str(), elem.tag_name.ns.as_str())
+                    .attr("id", elem.id.clone())
+                    .attr("xml:lang", elem.lang.clone())
+                    .attr("dir", elem.dir.map(|dir| dir.to_string()));
+
+            // the namespaces of the attributes are declared on the element itself
+            let mut declared: BTreeMap<&str, String> = BTreeMap::new();
+            for (name, value) in &elem.attrs {
+                if !declared.contains_key(name.ns.as_str()) {
+                    let prefix = prefixes.xml_prefix(&name.ns, declared.len());
+                    builder = builder
+                        .prefix(Some(prefix.clone()), name.ns.as_str())
+                        .expect("the prefixes of an element are distinct");
+                    declared.insert(name.ns.as_str(), prefix);
+                }
+                let prefix = &declared[name.ns.as_str()];
+                builder = builder.attr(format!("{}:{}", prefix, name.reference), value.as_str());
+            }
+
+            metadata.append_child(builder.append(elem.value.as_str()).build());
+        }
+        for meta in &self.metadata.metas {
+            let child = Element::builder("meta", OPF.uri.as_str())
+                .attr("id", meta.id.clone())
+                .attr("xml:lang", meta.lang.clone())
+                .attr("dir", meta.dir.map(|dir| dir.to_string()))
+                .attr("property", prefixes.write(&meta.property))
+                .attr(
+                    "refines",
+                    meta.refines.as_ref().map(|r| relative(base_url, r.url())),
+                )
+                .attr("scheme", meta.scheme.as_ref().map(|s| prefixes.write(s)))
+                .append(meta.value.as_str())
+                .build();
+            metadata.append_child(child);
+        }
+        for legacy in &self.metadata.legacy_metas {
+            let child = Element::builder("meta", OPF.uri.as_str())
+                .attr("name", legacy.name.as_str())
+                .attr("content", legacy.content.as_str())
+                .build();
+            metadata.append_child(child);
+        }
+        for link in &self.metadata.links {
+            let mut child = Element::builder("link", OPF.uri.as_str())
+                .attr("id", link.id.clone())
+                .attr("href", relative(base_url, &link.href))
+                .attr("rel", prefixes.write_all(&link.rel))
+                .attr("hreflang", link.hreflang.clone())
+                .attr("media-type", link.media_type.as_ref().map(|m| m.raw()))
+                .attr(
+                    "properties",
+                    link.property.as_ref().map(|p| prefixes.write(p)),
+                )
+                .attr(
+                    "refines",
+                    link.refines.as_ref().map(|r| relative(base_url, r.url())),
+                )
+                .build();
+            if !link.value.is_empty() {
+                child.append_text_node(link.value.as_str());
+            }
+            metadata.append_child(child);
+        }
+
+        // manifest
+        let mut manifest = Element::builder("manifest", OPF.uri.as_str())
+            .attr("id", self.manifest.id.clone())
+            .build();
+        for res in self.manifest.iter() {
+            let child = Element::builder("item", OPF.uri.as_str())
+                .attr("id", res.id.as_str())
+                .attr("href", relative(base_url, &res.href))
+                .attr("media-type", res.media_type.raw())
+                .attr("fallback", res.fallback.clone())
+                .attr("media-overlay", res.media_overlay.clone())
+                .attr(
+                    "properties",
+                    res.properties.as_ref().map(|p| prefixes.write_all(p)),
+                )
+                .build();
+            manifest.append_child(child);
+        }
+
+        // spine
+        let mut spine = Element::builder("spine", OPF.uri.as_str())
+            .attr("id", self.spine.id.clone())
+            .attr("page-progression-direction", self.spine.dir.clone())
+            .attr("toc", self.spine.toc.clone())
+            .build();
+        for spine_ref in self.spine.iter() {
+            // the default value is omitted
+            let linear = (!spine_ref.linear).then_some("no");
+            let child = Element::builder("itemref", OPF.uri.as_str())
+                .attr("idref", spine_ref.id.as_str())
+                .attr("linear", linear)
+                .attr(
+                    "properties",
+                    spine_ref.properties.as_ref().map(|p| prefixes.write_all(p)),
+                )
+                .build();
+            spine.append_child(child);
+        }
+
+        // guide
+        let guide = (!self.guide.is_empty()).then(|| {
+            let mut guide = Element::builder("guide", OPF.uri.as_str()).build();
+            for reference in &self.guide {
+                let child = Element::builder("reference", OPF.uri.as_str())
+                    .attr("type", reference.ty.as_str())
+                    .attr("title", reference.title.clone())
+                    .attr("href", relative(base_url, &reference.href))
+                    .build();
+                guide.append_child(child);
+            }
+            guide
+        });
+
+        let package = Element::builder("package", OPF.uri.as_str())
+            .attr("version", self.version.to_string())
+            .attr("unique-identifier", self.unique_identifier_ref.as_str())
+            .attr("id", self.id.clone())
+            .attr("prefix", prefixes.prefix_attr())
+            .attr("dir", self.dir.map(|dir| dir.to_string()))
+            .attr("xml:lang", self.lang.clone())
+            .append(metadata)
+            .append(manifest)
+            .append(spine)
+            .append_all(guide)
+            .build();
+
+        let mut buf = Vec::new();
+        package
+            .write_to_decl(&mut buf)
+            .expect("writing to a buffer never fails");
+        String::from_utf8(buf).expect("the XML writer produces UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::package::tests::parse_opf;
+
+    const SAMPLE_OPF: &str = include_str!("../../res/sample/OEBPS/content.opf");
+    const SAMPLE_EPUB2_OPF: &str = include_str!("../../res/sample-epub2/OEBPS/content.opf");
+
+    #[test]
+    fn test_round_trip() {
+        // a non-linear spine item
+        let package = parse_opf(&SAMPLE_OPF.replace(
+            r#"<itemref idref="cover"/>"#,
+            r#"<itemref idref="cover" linear="no"/>"#,
+        ));
+        assert!(!package.spine[0].linear);
+        let opf = package.to_opf_string();
+        let reparsed = parse_opf(&opf);
+
+        assert_eq!(reparsed.version, package.version);
+        assert_eq!(reparsed.id, package.id);
+        assert_eq!(reparsed.unique_identifier(), package.unique_identifier());
+        assert_eq!(reparsed.metadata.elems, package.metadata.elems);
+        assert_eq!(reparsed.metadata.metas, package.metadata.metas);
+        assert_eq!(reparsed.metadata.links, package.metadata.links);
+        assert_eq!(
+            reparsed.metadata.last_modified,
+            package.metadata.last_modified
+        );
+        assert!(reparsed.manifest.iter().eq(package.manifest.iter()));
+        assert_eq!(reparsed.spine, package.spine);
+        assert!(!reparsed.spine[0].linear);
+
+        // writing again gives the same document
+        assert_eq!(reparsed.to_opf_string(), opf);
+    }
+
+    #[test]
+    fn test_round_trip_foreign_attrs() {
+        let package = parse_opf(SAMPLE_EPUB2_OPF);
+        let opf = package.to_opf_string();
+        let reparsed = parse_opf(&opf);
+
+        assert_eq!(reparsed.metadata.elems, package.metadata.elems);
+        assert_eq!(
+            reparsed.metadata.legacy_metas,
+            package.metadata.legacy_metas
+        );
+        assert_eq!(
+            reparsed.metadata.date_for_event("publication"),
+            Some("1865-11-26")
+        );
+    }
+}
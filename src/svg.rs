@@ -0,0 +1,152 @@
+use minidom::Element;
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::{strip_bom, strip_doctype};
+
+/// An SVG content document, like the full-page wrapper of a fixed-layout picture book.
+#[derive(Debug)]
+pub struct SvgDocument {
+    element: Element,
+}
+
+/// The `viewBox` attribute of an SVG element.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ViewBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl SvgDocument {
+    pub fn new(element: Element) -> Result<Self, SvgParseError> {
+        if element.name() != "svg" {
+            return Err(SvgParseError::InvalidRootElement);
+        }
+        Ok(Self { element })
+    }
+
+    pub fn root(&self) -> &Element {
+        &self.element
+    }
+
+    /// The `width` attribute of the root element, with its unit, like `600` or `100%`.
+    pub fn width(&self) -> Option<&str> {
+        self.element.attr("width")
+    }
+
+    /// The `height` attribute of the root element, with its unit, like `800` or `100%`.
+    pub fn height(&self) -> Option<&str> {
+        self.element.attr("height")
+    }
+
+    /// The `viewBox` attribute of the root element.
+    ///
+    /// Returns `None` if it is absent or is not four numbers separated by white space or commas.
+    pub fn view_box(&self) -> Option<ViewBox> {
+        let values = self
+            .element
+            .attr("viewBox")?
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<f64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match values[..] {
+            [min_x, min_y, width, height] => Some(ViewBox {
+                min_x,
+                min_y,
+                width,
+                height,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Get the URLs of the images embedded by the `image` elements.
+    ///
+    /// The `href` and `xlink:href` attributes are resolved against `base_url`, which is the URL of the document.
+    /// The fragments of the URLs are removed.
+    pub fn image_refs(&self, base_url: &Url) -> Vec<Url> {
+        let mut refs = Vec::new();
+        collect_image_refs(&self.element, base_url, &mut refs);
+        refs
+    }
+}
+
+fn collect_image_refs(elem: &Element, base_url: &Url, refs: &mut Vec<Url>) {
+    if elem.name() == "image" {
+        let href = elem.attr("href").or_else(|| elem.attr("xlink:href"));
+        if let Some(mut url) = href.and_then(|s| base_url.join(s.trim()).ok()) {
+            url.set_fragment(None);
+            refs.push(url);
+        }
+    }
+    for child in elem.children() {
+        collect_image_refs(child, base_url, refs);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SvgParseError {
+    #[error("Failed to parse SVG")]
+    ParseError(#[from] minidom::Error),
+
+    #[error("Invalid root element")]
+    InvalidRootElement,
+}
+
+pub fn parse_svg(s: &str) -> Result<SvgDocument, SvgParseError> {
+    let svg = strip_doctype(strip_bom(s)).parse::<Element>()?;
+    SvgDocument::new(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::svg::{parse_svg, SvgParseError, ViewBox};
+
+    #[test]
+    fn test_parse_svg() {
+        let svg = parse_svg(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"
+    width="100%" height="100%" viewBox="0,0 1200 1600" preserveAspectRatio="xMidYMid meet">
+    <image width="1200" height="1600" xlink:href="../Images/page1.jpg"/>
+    <g><image href="../Images/page1-overlay.png#layer"/></g>
+</svg>"#,
+        )
+        .unwrap();
+
+        assert_eq!(svg.width(), Some("100%"));
+        assert_eq!(svg.height(), Some("100%"));
+        assert_eq!(
+            svg.view_box(),
+            Some(ViewBox {
+                min_x: 0.0,
+                min_y: 0.0,
+                width: 1200.0,
+                height: 1600.0,
+            })
+        );
+
+        let base_url = Url::parse("epub:/OEBPS/Text/page1.svg").unwrap();
+        let refs = svg
+            .image_refs(&base_url)
+            .iter()
+            .map(|url| url.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            refs,
+            vec![
+                "epub:/OEBPS/Images/page1.jpg",
+                "epub:/OEBPS/Images/page1-overlay.png"
+            ]
+        );
+
+        let err = parse_svg(r#"<html xmlns="http://www.w3.org/1999/xhtml"/>"#).unwrap_err();
+        assert!(matches!(err, SvgParseError::InvalidRootElement));
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::BTreeSet;
+
+/// The elements which never have content, like `<br>`, written as empty elements.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// The elements whose content is not markup, like the code of a script.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// The entities predefined by XML.
+const XML_ENTITIES: [&str; 5] = ["amp", "lt", "gt", "quot", "apos"];
+
+/// The HTML entities commonly found in books, which XML does not know.
+const HTML_ENTITIES: [(&str, u32); 19] = [
+    ("nbsp", 160),
+    ("copy", 169),
+    ("reg", 174),
+    ("trade", 8482),
+    ("mdash", 8212),
+    ("ndash", 8211),
+    ("hellip", 8230),
+    ("lsquo", 8216),
+    ("rsquo", 8217),
+    ("ldquo", 8220),
+    ("rdquo", 8221),
+    ("laquo", 171),
+    ("raquo", 187),
+    ("middot", 183),
+    ("times", 215),
+    ("deg", 176),
+    ("ensp", 8194),
+    ("emsp", 8195),
+    ("thinsp", 8201),
+];
+
+/// The namespaces of the prefixes content documents use without declaring them.
+const KNOWN_PREFIXES: [(&str, &str); 4] = [
+    ("epub", "http://www.idpf.org/2007/ops"),
+    ("xlink", "http://www.w3.org/1999/xlink"),
+    ("svg", "http://www.w3.org/2000/svg"),
+    ("m", "http://www.w3.org/1998/Math/MathML"),
+];
+
+static XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+/// Rewrite HTML markup, which is not well-formed XML, to well-formed XHTML.
+///
+/// It recovers from the mistakes commonly found in the content documents of real books:
+/// - unescaped `&` and `<` in text and attribute values, and HTML entities like `&nbsp;`;
+/// - void elements like `<br>` and `<img>` which are not closed;
+/// - unclosed and mismatched tags, the unclosed ones are closed at the end of their parent
+///   and the end tags without a start tag are dropped;
+/// - attributes without a value or quotes, and duplicated attributes;
+/// - the missing XHTML namespace and undeclared prefixes like `epub:`.
+///
+/// Well-formed documents are kept as they are, except for the formatting of the tags.
+pub fn tidy_html(s: &str) -> String {
+    let mut tidy = Tidy::default();
+    tidy.run(s);
+    tidy.finish()
+}
+
+#[derive(Default)]
+struct Tidy {
+    out: String,
+
+    /// The open elements, their lowercase names to match the end tags and their original names.
+    open: Vec<(String, String)>,
+
+    /// The position in the output to insert the namespace declarations of the root element.
+    root_end: Option<usize>,
+
+    prefixes_used: BTreeSet<String>,
+    prefixes_declared: BTreeSet<String>,
+}
+
+struct StartTag<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, Option<&'a str>)>,
+    self_closing: bool,
+}
+
+impl Tidy {
+    fn run(&mut self, s: &str) {
+        let mut rest = s;
+        while let Some(lt) = rest.find('<') {
+            self.text(&rest[..lt]);
+            rest = &rest[lt..];
+
+            let markup = [
+                ("<!--", "-->"),
+                ("<![CDATA[", "]]>"),
+                ("<?", "?>"),
+                ("<!", ">"),
+            ]
+            .into_iter()
+            .find(|(start, _)| rest.starts_with(start));
+            if let Some((start, end)) = markup {
+                let len = rest[start.len()..]
+                    .find(end)
+                    .map_or(rest.len(), |i| start.len() + i + end.len());
+                if self.in_root_or_prolog() {
+                    self.out.push_str(&rest[..len]);
+                    if len == rest.len() && !rest.ends_with(end) {
+                        self.out.push_str(end);
+                    }
+                }
+                rest = &rest[len..];
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let len = tag.find('>').map_or(tag.len(), |i| i + 1);
+                self.end_tag(tag[..len].trim_end_matches('>').trim());
+                rest = &tag[len..];
+            } else if let Some((tag, len)) = parse_start_tag(&rest[1..]) {
+                rest = &rest[1 + len..];
+                let lname = tag.name.to_ascii_lowercase();
+                let is_raw_text = RAW_TEXT_ELEMENTS.contains(&lname.as_str());
+                self.start_tag(tag);
+                if is_raw_text && self.open.last().is_some_and(|(name, _)| *name == lname) {
+                    let len = find_ignore_case(rest, &format!("</{}", lname)).unwrap_or(rest.len());
+                    self.raw_text(&rest[..len]);
+                    rest = &rest[len..];
+                }
+            } else {
+                self.text("&lt;");
+                rest = &rest[1..];
+            }
+        }
+        self.text(rest);
+    }
+
+    /// Check if markup can be written here, that is before the end of the root element.
+    fn in_root_or_prolog(&self) -> bool {
+        self.root_end.is_none() || !self.open.is_empty()
+    }
+
+    fn text(&mut self, text: &str) {
+        // XML has no text outside of the root element
+        if self.open.is_empty() {
+            return;
+        }
+        escape_text(text, &mut self.out);
+    }
+
+    fn raw_text(&mut self, text: &str) {
+        if text.contains(['<', '&']) && !text.contains("]]>") {
+            self.out.push_str("<![CDATA[");
+            self.out.push_str(text);
+            self.out.push_str("]]>");
+        } else {
+            escape_text(text, &mut self.out);
+        }
+    }
+
+    fn start_tag(&mut self, tag: StartTag<'_>) {
+        if !self.in_root_or_prolog() || !is_name(tag.name) {
+            return;
+        }
+        let lname = tag.name.to_ascii_lowercase();
+        self.use_name(tag.name);
+
+        self.out.push('<');
+        self.out.push_str(tag.name);
+        let mut written = BTreeSet::new();
+        for (name, value) in tag.attrs {
+            if !is_name(name) || !written.insert(name) {
+                continue;
+            }
+            self.use_name(name);
+            self.out.push(' ');
+            self.out.push_str(name);
+            self.out.push_str("=\"");
+            escape_text(value.unwrap_or(name), &mut self.out);
+            self.out.push('"');
+        }
+
+        if self.root_end.is_none() {
+            if !written.contains("xmlns") {
+                self.out
+                    .push_str(&format!(" xmlns=\"{}\"", XHTML_NAMESPACE));
+            }
+            self.root_end = Some(self.out.len());
+        }
+
+        if tag.self_closing || VOID_ELEMENTS.contains(&lname.as_str()) {
+            self.out.push_str("/>");
+        } else {
+            self.out.push('>');
+            self.open.push((lname, tag.name.to_string()));
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        let lname = name.to_ascii_lowercase();
+        let Some(index) = self.open.iter().rposition(|(open, _)| *open == lname) else {
+            return;
+        };
+        for (_, name) in self.open.split_off(index).into_iter().rev() {
+            self.out.push_str("</");
+            self.out.push_str(&name);
+            self.out.push('>');
+        }
+    }
+
+    /// Record the prefix of an element or attribute name, and the prefix it declares.
+    fn use_name(&mut self, name: &str) {
+        if let Some(prefix) = name.strip_prefix("xmlns:") {
+            self.prefixes_declared.insert(prefix.to_string());
+        } else if let Some((prefix, _)) = name.split_once(':') {
+            if prefix != "xml" && prefix != "xmlns" {
+                self.prefixes_used.insert(prefix.to_string());
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.end_tag(
+            &self
+                .open
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+        );
+
+        let Some(root_end) = self.root_end else {
+            return self.out;
+        };
+        let declarations = self
+            .prefixes_used
+            .difference(&self.prefixes_declared)
+            .map(|prefix| {
+                let ns = KNOWN_PREFIXES
+                    .iter()
+                    .find(|(known, _)| known == prefix)
+                    .map_or_else(
+                        || format!("urn:x-undeclared:{}", prefix),
+                        |(_, ns)| ns.to_string(),
+                    );
+                format!(" xmlns:{}=\"{}\"", prefix, ns)
+            })
+            .collect::<String>();
+        self.out.insert_str(root_end, &declarations);
+        self.out
+    }
+}
+
+/// Parse a start tag after its `<`, returning the tag and the length of the rest of it.
+///
+/// Returns `None` if it is not a tag, like the `<` of `a < b`, or the tag is not closed.
+fn parse_start_tag(s: &str) -> Option<(StartTag<'_>, usize)> {
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let is_name_end = |c: char| c.is_whitespace() || c == '/' || c == '>';
+    let name_len = s.find(is_name_end).unwrap_or(s.len());
+    let mut tag = StartTag {
+        name: &s[..name_len],
+        attrs: Vec::new(),
+        self_closing: false,
+    };
+
+    let mut i = name_len;
+    loop {
+        i += s[i..].len() - s[i..].trim_start().len();
+        match s[i..].chars().next()? {
+            '>' => return Some((tag, i + 1)),
+            '/' if s[i + 1..].starts_with('>') => {
+                tag.self_closing = true;
+                return Some((tag, i + 2));
+            }
+            '/' => i += 1,
+            _ => {
+                let name_len = s[i..]
+                    .find(|c: char| is_name_end(c) || c == '=')
+                    .unwrap_or(s.len() - i);
+                let name = &s[i..i + name_len];
+                i += name_len;
+
+                let after_ws = s[i..].trim_start();
+                let Some(value) = after_ws.strip_prefix('=') else {
+                    tag.attrs.push((name, None));
+                    continue;
+                };
+                let value = value.trim_start();
+                i = s.len() - value.len();
+                let value = match value.chars().next()? {
+                    quote @ ('"' | '\'') => {
+                        let len = value[1..].find(quote)?;
+                        i += len + 2;
+                        &value[1..len + 1]
+                    }
+                    _ => {
+                        let len = value
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        i += len;
+                        &value[..len]
+                    }
+                };
+                tag.attrs.push((name, Some(value)));
+            }
+        }
+    }
+}
+
+/// Check if a string is a valid XML name, like `img` or `epub:type`.
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Find a string in another one, comparing the ASCII characters case-insensitively.
+fn find_ignore_case(s: &str, pattern: &str) -> Option<usize> {
+    s.to_ascii_lowercase().find(pattern)
+}
+
+/// Escape a text or an attribute value, keeping the valid character and entity references.
+fn escape_text(text: &str, out: &mut String) {
+    let mut rest = text;
+    while let Some(i) = rest.find(['&', '<', '"']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with('<') {
+            out.push_str("&lt;");
+        } else if rest.starts_with('"') {
+            out.push_str("&quot;");
+        } else {
+            match entity(rest) {
+                Some((reference, len)) => {
+                    out.push_str(&reference);
+                    rest = &rest[len..];
+                    continue;
+                }
+                None => out.push_str("&amp;"),
+            }
+        }
+        rest = &rest[1..];
+    }
+    out.push_str(rest);
+}
+
+/// Parse the entity reference at the start of `s`, returning its XML form and its length.
+///
+/// Returns `None` if it is not a reference XML knows, which means the `&` has to be escaped.
+fn entity(s: &str) -> Option<(String, usize)> {
+    let end = s.find(';')?;
+    let name = &s[1..end];
+    let reference = if let Some(code) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+        u32::from_str_radix(code, 16)
+            .ok()
+            .and_then(char::from_u32)?;
+        s[..end + 1].to_string()
+    } else if let Some(code) = name.strip_prefix('#') {
+        code.parse::<u32>().ok().and_then(char::from_u32)?;
+        s[..end + 1].to_string()
+    } else if XML_ENTITIES.contains(&name) {
+        s[..end + 1].to_string()
+    } else {
+        let (_, code) = HTML_ENTITIES.iter().find(|(entity, _)| *entity == name)?;
+        format!("&#{};", code)
+    };
+    Some((reference, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tidy::tidy_html;
+    use crate::xhtml::parse_xhtml;
+
+    #[test]
+    fn test_tidy_html() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Tom & Jerry</title><meta charset=utf-8></head>
+<body>
+<p class=first>Line<br>break&nbsp;&copy; &#169; &amp; &unknown;</p>
+<p epub:type="footnote">Unclosed <b>bold</p>
+<p>Stray</i> end tag, 1 < 2</p>
+<img src="a.png" alt="A" alt="B" hidden>
+<script>if (a < b && c) {}</script>
+</body></html>"#;
+        assert!(parse_xhtml(html).is_err());
+
+        let xhtml = parse_xhtml(&tidy_html(html)).unwrap();
+        assert_eq!(
+            xhtml.body_text_blocks(),
+            vec![
+                "Line",
+                "break © © & &unknown;",
+                "Unclosed bold",
+                "Stray end tag, 1 < 2",
+                "if (a < b && c) {}",
+            ]
+        );
+        assert_eq!(xhtml.elements_with_epub_type("footnote").len(), 1);
+
+        let body = xhtml.body();
+        let img = body.children().find(|elem| elem.name() == "img").unwrap();
+        assert_eq!(img.attr("alt"), Some("A"));
+        assert_eq!(img.attr("hidden"), Some("hidden"));
+        let script = body
+            .children()
+            .find(|elem| elem.name() == "script")
+            .unwrap();
+        assert_eq!(script.text(), "if (a < b && c) {}");
+    }
+
+    #[test]
+    fn test_tidy_well_formed() {
+        let xhtml = r#"<html xmlns="http://www.w3.org/1999/xhtml"><head></head><body><p>Text</p><br/></body></html>"#;
+        assert_eq!(tidy_html(xhtml), xhtml);
+    }
+}
@@ -1,3 +1,171 @@
+use std::borrow::Cow;
+
+use url::Url;
+
 pub(crate) fn invert<T, E>(x: Option<Result<T, E>>) -> Result<Option<T>, E> {
     x.map_or(Ok(None), |v| v.map(Some))
-}
\ No newline at end of file
+}
+
+/// Remove the byte order mark at the start of a decoded document.
+///
+/// A UTF-8 or UTF-16 BOM decodes to `U+FEFF`, which the XML parser rejects before `<?xml`.
+pub(crate) fn strip_bom(s: &str) -> &str {
+    s.strip_prefix('\u{FEFF}').unwrap_or(s)
+}
+
+/// Remove the document type declaration from an XML document.
+///
+/// The XML parser does not support DTDs, but almost every XHTML document starts with `<!DOCTYPE html>`.
+pub(crate) fn strip_doctype(s: &str) -> Cow<'_, str> {
+    let Some(start) = s.find("<!DOCTYPE").or_else(|| s.find("<!doctype")) else {
+        return Cow::Borrowed(s);
+    };
+
+    // skip the internal subset if there is one
+    let rest = &s[start..];
+    let end = match (rest.find('['), rest.find('>')) {
+        (Some(open), Some(close)) if open < close => rest.find("]>").map(|i| i + 2),
+        (_, Some(close)) => Some(close + 1),
+        _ => None,
+    };
+
+    match end {
+        Some(end) => Cow::Owned(format!("{}{}", &s[..start], &rest[end..])),
+        None => Cow::Borrowed(s),
+    }
+}
+
+/// Escape the special characters of a string for XML text and attribute values.
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Decode the two hexadecimal digits after the byte at `i`, the `XX` of a `%XX` sequence.
+///
+/// Unlike [u8::from_str_radix], signs like the `+` of `%+1` are not digits.
+fn percent_decoded(bytes: &[u8], i: usize) -> Option<u8> {
+    let hex = bytes.get(i + 1..i + 3)?;
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let digit = |b: u8| (b as char).to_digit(16).unwrap() as u8;
+    Some(digit(hex[0]) << 4 | digit(hex[1]))
+}
+
+/// Normalize the percent-encoding of the path of a URL, so that equivalent URLs are equal.
+///
+/// The hexadecimal digits are uppercased and the encoded unreserved characters
+/// (`A-Z a-z 0-9 - . _ ~`) are decoded, e.g. `a%2dB%c3%a9` becomes `a-B%C3%A9`.
+/// Spaces and non-ASCII characters are already encoded by the URL parser.
+pub(crate) fn normalize_url(url: &Url) -> Url {
+    let path = url.path();
+    if !path.contains('%') {
+        return url.clone();
+    }
+
+    let bytes = path.as_bytes();
+    let mut normalized = String::with_capacity(path.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], percent_decoded(bytes, i)) {
+            (b'%', Some(byte)) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                normalized.push(byte as char);
+                i += 3;
+            }
+            (b'%', Some(byte)) => {
+                normalized.push_str(&format!("%{:02X}", byte));
+                i += 3;
+            }
+            (byte, _) => {
+                normalized.push(byte as char);
+                i += 1;
+            }
+        }
+    }
+
+    let mut url = url.clone();
+    url.set_path(&normalized);
+    url
+}
+
+/// Decode the percent-encoded bytes of a URL path, like `My%20Book.xhtml` to `My Book.xhtml`.
+///
+/// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+pub(crate) fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], percent_decoded(bytes, i)) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Get the file extension of the last path segment of a URL, like `xhtml` for `epub:/Text/a.xhtml`.
+///
+/// Dot files like `.hidden` have no extension.
+pub(crate) fn url_extension(url: &Url) -> Option<&str> {
+    let name = url.path_segments()?.next_back()?;
+    let (stem, ext) = name.rsplit_once('.')?;
+    (!stem.is_empty() && !ext.is_empty()).then_some(ext)
+}
+
+/// Get the URL of a file in a container by its path, like the name of a ZIP entry.
+///
+/// The path is not a URL, so the characters which would start a query or a fragment are encoded.
+pub(crate) fn file_url(root_url: &Url, path: &str) -> Result<Url, url::ParseError> {
+    let path = path.replace('#', "%23").replace('?', "%3F");
+    Ok(normalize_url(&root_url.join(&path)?))
+}
+
+/// Check if a URL is inside the directory of a root URL, like `http://host/book/OEBPS/a.xhtml`
+/// under `http://host/book/`.
+///
+/// The root is taken as a directory even without a trailing `/`,
+/// so `http://host/book` does not contain `http://host/bookshelf/a.xhtml`.
+pub(crate) fn is_under(root_url: &Url, url: &Url) -> bool {
+    if root_url.scheme() != url.scheme()
+        || root_url.host() != url.host()
+        || root_url.port_or_known_default() != url.port_or_known_default()
+    {
+        return false;
+    }
+    let mut root_url = root_url.clone();
+    if !root_url.path().ends_with('/') {
+        root_url.set_path(&format!("{}/", root_url.path()));
+    }
+    root_url
+        .make_relative(url)
+        .is_some_and(|relative| relative != ".." && !relative.starts_with("../"))
+}
+
+/// Get the key of a file to look up, which is the normalized URL without the fragment.
+pub(crate) fn file_key(url: &Url) -> Url {
+    // joining an empty string removes the fragment
+    let url = if url.cannot_be_a_base() {
+        url.clone()
+    } else {
+        url.join("").unwrap()
+    };
+    normalize_url(&url)
+}
@@ -1,5 +1,11 @@
+use std::collections::BTreeMap;
+
 use minidom::Element;
 use thiserror::Error;
+use url::Url;
+
+use crate::css::css_urls;
+use crate::utils::strip_doctype;
 
 #[derive(Debug)]
 pub struct XHTML {
@@ -8,6 +14,9 @@ pub struct XHTML {
 
 static XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
 
+/// The namespace of the `epub:type` attribute.
+static OPS_NAMESPACE: &str = "http://www.idpf.org/2007/ops";
+
 impl XHTML {
     pub fn new(element: Element) -> Result<Self, XHTMLParseError> {
         // check that the root element is an XHTML element
@@ -56,6 +65,310 @@ impl XHTML {
     pub fn root_str(&self) -> String {
         Self::elem_to_str(&self.element)
     }
+
+    /// Get the text blocks of the body.
+    ///
+    /// Every block-level element (paragraphs, headings, list items, ...) becomes one block,
+    /// with its white space collapsed. Empty blocks are skipped.
+    pub fn body_text_blocks(&self) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut current = String::new();
+        collect_text_blocks(self.body(), &mut blocks, &mut current);
+        flush_text_block(&mut blocks, &mut current);
+        blocks
+    }
+
+    /// Get the URLs of the resources referenced by the document.
+    ///
+    /// It collects the `href`, `src`, `xlink:href`, `poster` and `data` attributes, and the URLs in
+    /// the `style` elements and attributes, resolved against `base_url`, which is the URL of the document.
+    /// The fragments of the URLs are removed, so links inside the document are resolved to the document itself.
+    pub fn resource_refs(&self, base_url: &Url) -> Vec<Url> {
+        let mut refs = Vec::new();
+        collect_resource_refs(&self.element, base_url, &mut refs);
+        refs
+    }
+
+    /// Get the plain text of the body.
+    ///
+    /// The text blocks returned by [XHTML::body_text_blocks] are joined with a line break.
+    pub fn body_text(&self) -> String {
+        self.body_text_blocks().join("\n")
+    }
+
+    /// Get the namespaces declared on the root element, keyed by prefix.
+    ///
+    /// The default namespace has the `None` prefix. It tells whether the document embeds
+    /// SVG or MathML, which are usually declared as `svg:` and `m:`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::xhtml::parse_xhtml;
+    ///
+    /// let xhtml = parse_xhtml(
+    ///     r#"<html xmlns="http://www.w3.org/1999/xhtml"
+    ///         xmlns:m="http://www.w3.org/1998/Math/MathML"><head></head><body></body></html>"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let namespaces = xhtml.namespaces();
+    /// assert_eq!(namespaces[&None], "http://www.w3.org/1999/xhtml");
+    /// assert_eq!(namespaces[&Some("m".to_string())], "http://www.w3.org/1998/Math/MathML");
+    /// ```
+    pub fn namespaces(&self) -> &BTreeMap<Option<String>, String> {
+        self.element.prefixes.declared_prefixes()
+    }
+
+    /// Get the elements whose `epub:type` attribute contains the given token,
+    /// like `noteref` or `footnote`, in document order.
+    ///
+    /// The attribute is a space-separated list of tokens, and its prefix is the one
+    /// the document binds to the `http://www.idpf.org/2007/ops` namespace, `epub` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::xhtml::parse_xhtml;
+    ///
+    /// let xhtml = parse_xhtml(
+    ///     r##"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+    ///     <head></head><body>
+    ///         <p>Text<a epub:type="noteref" href="#n1">1</a></p>
+    ///         <aside id="n1" epub:type="footnote rearnote">Note</aside>
+    ///     </body></html>"##,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(xhtml.elements_with_epub_type("noteref")[0].name(), "a");
+    /// assert_eq!(xhtml.elements_with_epub_type("footnote")[0].name(), "aside");
+    /// assert!(xhtml.elements_with_epub_type("pagebreak").is_empty());
+    /// ```
+    pub fn elements_with_epub_type(&self, ty: &str) -> Vec<&Element> {
+        let mut elems = Vec::new();
+        collect_elements_with_epub_type(&self.element, "epub", ty, &mut elems);
+        elems
+    }
+
+    /// Get the element with the given `id`, or the legacy `name` attribute of an anchor,
+    /// which is the target of a fragment like `chapter3.xhtml#sec2`.
+    ///
+    /// The `id` attribute takes precedence, the first matching element in document order is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::xhtml::parse_xhtml;
+    ///
+    /// let xhtml = parse_xhtml(
+    ///     r#"<html xmlns="http://www.w3.org/1999/xhtml"><head></head><body>
+    ///         <section id="sec1"><h2>One</h2></section>
+    ///         <section><a name="sec2"/><h2>Two</h2></section>
+    ///     </body></html>"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(xhtml.element_by_id("sec1").unwrap().name(), "section");
+    /// assert_eq!(xhtml.element_by_id("sec2").unwrap().name(), "a");
+    /// assert!(xhtml.element_by_id("sec3").is_none());
+    /// ```
+    pub fn element_by_id(&self, id: &str) -> Option<&Element> {
+        find_element_by_attr(&self.element, "id", id)
+            .or_else(|| find_element_by_attr(&self.element, "name", id))
+    }
+
+    /// Get the width and height of the viewport meta element in the head,
+    /// which are the page dimensions of a fixed-layout document.
+    ///
+    /// The content is a comma-separated list of `key=value` pairs, both `width` and
+    /// `height` must be positive integers, an optional `px` unit is accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eparser::xhtml::parse_xhtml;
+    ///
+    /// let xhtml = parse_xhtml(
+    ///     r#"<html xmlns="http://www.w3.org/1999/xhtml"><head>
+    ///         <meta name="viewport" content="width=1200, height=1800"/>
+    ///     </head><body></body></html>"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(xhtml.viewport(), Some((1200, 1800)));
+    /// ```
+    pub fn viewport(&self) -> Option<(u32, u32)> {
+        let content = self
+            .head()
+            .children()
+            .filter(|elem| elem.name() == "meta")
+            .find(|elem| {
+                elem.attr("name")
+                    .is_some_and(|name| name.trim().eq_ignore_ascii_case("viewport"))
+            })?
+            .attr("content")?;
+
+        let (mut width, mut height) = (None, None);
+        for pair in content.split([',', ';']) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let value = value.strip_suffix("px").unwrap_or(value).trim();
+            let value = value.parse::<u32>().ok().filter(|value| *value > 0);
+            match key.trim().to_ascii_lowercase().as_str() {
+                "width" => width = value,
+                "height" => height = value,
+                _ => {}
+            }
+        }
+        Some((width?, height?))
+    }
+}
+
+fn collect_elements_with_epub_type<'a>(
+    elem: &'a Element,
+    prefix: &str,
+    ty: &str,
+    elems: &mut Vec<&'a Element>,
+) {
+    // a prefix declared on the element applies to its own attributes and its descendants
+    let prefix = elem
+        .prefixes
+        .declared_prefixes()
+        .iter()
+        .find(|(_, ns)| ns.as_str() == OPS_NAMESPACE)
+        .and_then(|(prefix, _)| prefix.as_deref())
+        .unwrap_or(prefix);
+
+    let types = elem.attr(&format!("{}:type", prefix)).unwrap_or_default();
+    if types.split_whitespace().any(|token| token == ty) {
+        elems.push(elem);
+    }
+    for child in elem.children() {
+        collect_elements_with_epub_type(child, prefix, ty, elems);
+    }
+}
+
+fn find_element_by_attr<'a>(elem: &'a Element, attr: &str, value: &str) -> Option<&'a Element> {
+    if elem.attr(attr) == Some(value) {
+        return Some(elem);
+    }
+    elem.children()
+        .find_map(|child| find_element_by_attr(child, attr, value))
+}
+
+/// Elements that start a new text block.
+static BLOCK_ELEMENTS: [&str; 27] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "caption",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "nav",
+    "p",
+    "pre",
+    "section",
+    "td",
+];
+
+fn collect_text_blocks(elem: &Element, blocks: &mut Vec<String>, current: &mut String) {
+    for node in elem.nodes() {
+        if let Some(text) = node.as_text() {
+            current.push_str(text);
+        } else if let Some(child) = node.as_element() {
+            if BLOCK_ELEMENTS.contains(&child.name()) {
+                flush_text_block(blocks, current);
+                collect_text_blocks(child, blocks, current);
+                flush_text_block(blocks, current);
+            } else {
+                collect_text_blocks(child, blocks, current);
+            }
+        }
+    }
+}
+
+/// Attributes referencing resources.
+static REF_ATTRS: [&str; 5] = ["href", "src", "xlink:href", "poster", "data"];
+
+fn collect_resource_refs(elem: &Element, base_url: &Url, refs: &mut Vec<Url>) {
+    for attr in REF_ATTRS {
+        if let Some(mut url) = elem.attr(attr).and_then(|s| base_url.join(s.trim()).ok()) {
+            url.set_fragment(None);
+            refs.push(url);
+        }
+    }
+    if let Some(style) = elem.attr("style") {
+        refs.extend(css_urls(style, base_url));
+    }
+    if elem.name() == "style" {
+        refs.extend(css_urls(&elem.text(), base_url));
+    }
+    for child in elem.children() {
+        collect_resource_refs(child, base_url, refs);
+    }
+}
+
+fn flush_text_block(blocks: &mut Vec<String>, current: &mut String) {
+    let block = current.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    current.clear();
+}
+
+/// Check if a character is written without spaces between words,
+/// like the Chinese characters and the Japanese kana.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+        | '\u{31F0}'..='\u{31FF}' // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+        | '\u{20000}'..='\u{3134F}' // CJK Unified Ideographs Extension B to G
+    )
+}
+
+/// Count the words of a text.
+///
+/// Every CJK character counts as a word, and the other words are separated by
+/// white space, CJK characters or non-ASCII punctuation like `。`.
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if c.is_whitespace() || !c.is_ascii() {
+            in_word = false;
+        }
+    }
+    count
 }
 
 #[derive(Debug, Error)]
@@ -73,8 +386,26 @@ pub enum XHTMLParseError {
     InvalidRootElement,
 }
 
-
 pub fn parse_xhtml(s: &str) -> Result<XHTML, XHTMLParseError> {
-    let xhtml = s.parse::<Element>()?;
+    let xhtml = strip_doctype(s).parse::<Element>()?;
     XHTML::new(xhtml)
-}
\ No newline at end of file
+}
+
+/// Parse an XHTML document, recovering from malformed markup like unclosed `<br>` tags.
+///
+/// The document is parsed as it is first, and rewritten by [tidy_html](crate::tidy::tidy_html)
+/// if it is not well-formed XML, so a single unclosed tag does not make the whole page blank.
+///
+/// # Examples
+///
+/// ```
+/// use eparser::xhtml::parse_xhtml_lenient;
+///
+/// let xhtml = parse_xhtml_lenient("<html><head></head><body><p>Tom & Jerry<br></p></body></html>")
+///     .unwrap();
+/// assert_eq!(xhtml.body_text(), "Tom & Jerry");
+/// ```
+#[cfg(feature = "tidy")]
+pub fn parse_xhtml_lenient(s: &str) -> Result<XHTML, XHTMLParseError> {
+    parse_xhtml(s).or_else(|_| parse_xhtml(&crate::tidy::tidy_html(s)))
+}